@@ -0,0 +1,637 @@
+//! 素材库：按内容哈希存储的素材文件
+//!
+//! 生成的分镜图、配音片段以及导入的视频统一复制到 `<app_data>/assets/objects/`，
+//! 以 BLAKE3 校验值命名，相同内容只保存一份。`index.json` 记录每个素材的类型、
+//! 生成来源（提示词/模型）、尺寸与时长、导入时间、标签和引用它的项目。
+//! 不再被任何项目、历史版本或自动保存快照引用的素材可以通过垃圾回收删除；任何项目
+//! 文件无法读取时垃圾回收直接失败，避免把仍在使用的素材当成无人引用。
+
+use crate::backend::{FfmpegBackend, MediaBackend};
+use crate::error::{AppError, AppResult};
+use crate::fs_utils::{self, AtomicOutput};
+use crate::media;
+use crate::autosave;
+use crate::history;
+use crate::media_scope;
+use crate::project;
+use crate::project_schema::{AssetKind, Project};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+/// 素材库所在的应用数据子目录
+pub const STORE_DIR: &str = "assets";
+
+const OBJECTS_DIR: &str = "objects";
+const INDEX_FILE: &str = "index.json";
+const INDEX_VERSION: u32 = 1;
+
+/// 垃圾回收默认只删除早于该时长（秒）的素材，避免删掉刚生成、尚未保存进项目的文件
+const DEFAULT_GC_MIN_AGE_SECS: u64 = 24 * 60 * 60;
+
+// 串行化对索引的读改写
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+// 生成素材的来源
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetSource {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+// 素材库中的一个素材
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredAsset {
+    /// BLAKE3 校验值（十六进制），同时是素材 ID
+    pub hash: String,
+    pub kind: AssetKind,
+    pub path: String,
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<AssetSource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    pub created_at: u64,
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    /// 引用该素材的项目 ID
+    #[serde(default)]
+    pub projects: BTreeSet<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct AssetIndex {
+    version: u32,
+    assets: BTreeMap<String, StoredAsset>,
+}
+
+// 导入参数
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportAssetParams {
+    pub path: String,
+    /// 为空时按扩展名判断
+    #[serde(default)]
+    pub kind: Option<AssetKind>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub source: Option<AssetSource>,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+// 导入结果
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportAssetResult {
+    pub asset: StoredAsset,
+    /// 素材库中已有相同内容，未重复保存
+    pub deduplicated: bool,
+}
+
+// 查询条件，各项同时满足
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetQuery {
+    #[serde(default)]
+    pub kind: Option<AssetKind>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    /// 匹配名称、提示词或模型（不区分大小写）
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+// 垃圾回收结果
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetGcResult {
+    pub removed: Vec<String>,
+    pub freed_bytes: u64,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Guess an asset kind from a file extension.
+pub fn kind_for_path(path: &Path) -> AssetKind {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    match ext.as_str() {
+        "png" | "jpg" | "jpeg" | "webp" | "gif" | "bmp" => AssetKind::Image,
+        "mp4" | "mov" | "mkv" | "webm" | "avi" | "m4v" => AssetKind::Video,
+        "mp3" | "wav" | "m4a" | "aac" | "flac" | "ogg" | "opus" => AssetKind::Audio,
+        "srt" | "ass" | "vtt" => AssetKind::Subtitle,
+        _ => AssetKind::Other,
+    }
+}
+
+/// Lowercased alphanumeric extension of `path`, if it has a sensible one.
+fn object_extension(path: &Path) -> Option<String> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    (!ext.is_empty() && ext.len() <= 10 && ext.chars().all(|c| c.is_ascii_alphanumeric())).then_some(ext)
+}
+
+fn normalize_tags(tags: &[String]) -> impl Iterator<Item = String> + '_ {
+    tags.iter().map(|t| t.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+/// Width, height and duration of a media file, as far as the backend can tell.
+fn probe_media(backend: &dyn MediaBackend, path: &str, kind: AssetKind) -> (Option<u32>, Option<u32>, Option<f64>) {
+    match kind {
+        AssetKind::Video => match media::probe_video(backend, path) {
+            Ok(meta) => (Some(meta.width), Some(meta.height), Some(meta.duration)),
+            Err(_) => (None, None, None),
+        },
+        AssetKind::Image => match media::probe_video(backend, path) {
+            Ok(meta) => (Some(meta.width), Some(meta.height), None),
+            Err(_) => (None, None, None),
+        },
+        AssetKind::Audio => (None, None, media::probe_duration(backend, path).ok()),
+        AssetKind::Subtitle | AssetKind::Other => (None, None, None),
+    }
+}
+
+/// A content-addressed store rooted at a directory.
+pub struct AssetStore {
+    root: PathBuf,
+}
+
+impl AssetStore {
+    pub fn new(root: impl Into<PathBuf>) -> AssetStore {
+        AssetStore { root: root.into() }
+    }
+
+    pub fn objects_dir(&self) -> PathBuf {
+        self.root.join(OBJECTS_DIR)
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.root.join(INDEX_FILE)
+    }
+
+    fn object_path(&self, hash: &str, ext: Option<&str>) -> PathBuf {
+        let name = match ext {
+            Some(ext) => format!("{}.{}", hash, ext),
+            None => hash.to_string(),
+        };
+        self.objects_dir().join(&hash[..2]).join(name)
+    }
+
+    fn load_index(&self) -> AppResult<AssetIndex> {
+        let path = self.index_path();
+        if !path.exists() {
+            return Ok(AssetIndex { version: INDEX_VERSION, assets: BTreeMap::new() });
+        }
+        let content = fs::read_to_string(&path).map_err(|e| AppError::io("read_file", Some(&path), e))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    fn save_index(&self, index: &AssetIndex) -> AppResult<()> {
+        fs_utils::atomic_write(&self.index_path(), serde_json::to_string_pretty(index)?.as_bytes())
+    }
+
+    /// Run `f` against the index while holding the store lock, saving it afterwards.
+    fn update<T>(&self, f: impl FnOnce(&mut AssetIndex) -> AppResult<T>) -> AppResult<T> {
+        let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut index = self.load_index()?;
+        let result = f(&mut index)?;
+        self.save_index(&index)?;
+        Ok(result)
+    }
+
+    /// Copy a file into the store, or merge metadata into the existing copy of the same content.
+    ///
+    /// Hashing, copying and probing can take long for large videos, so they run without the
+    /// store lock; the lock is only held to check for duplicates and add the index entry.
+    pub fn import(&self, backend: Option<&dyn MediaBackend>, params: &ImportAssetParams) -> AppResult<ImportAssetResult> {
+        let source = Path::new(&params.path);
+        if !source.is_file() {
+            return Err(AppError::NotFound { path: params.path.clone() });
+        }
+        let hash = fs_utils::hash_file(source)?;
+        let merged = self.update(|index| Ok(index.assets.get_mut(&hash).map(|existing| merge_import(existing, params))))?;
+        if let Some(result) = merged {
+            return Ok(result);
+        }
+
+        let dest = self.object_path(&hash, object_extension(source).as_deref());
+        let output = AtomicOutput::new(&dest)?;
+        fs::copy(source, output.temp_path()).map_err(|e| AppError::io("write_file", Some(&dest), e))?;
+        let kind = params.kind.unwrap_or_else(|| kind_for_path(source));
+        let (width, height, duration) = match backend {
+            Some(backend) => probe_media(backend, &output.temp_path_str(), kind),
+            None => (None, None, None),
+        };
+        let size = fs::metadata(output.temp_path()).map(|m| m.len()).unwrap_or(0);
+
+        self.update(|index| {
+            // 复制期间相同内容可能已被另一次导入加入
+            if let Some(existing) = index.assets.get_mut(&hash) {
+                return Ok(merge_import(existing, params));
+            }
+            let dest = output.commit()?;
+            let asset = StoredAsset {
+                hash: hash.clone(),
+                kind,
+                path: dest.to_string_lossy().to_string(),
+                size,
+                name: params
+                    .name
+                    .clone()
+                    .or_else(|| source.file_name().map(|n| n.to_string_lossy().to_string())),
+                source: params.source.clone(),
+                width,
+                height,
+                duration,
+                created_at: now_secs(),
+                tags: normalize_tags(&params.tags).collect(),
+                projects: params.project_id.iter().cloned().collect(),
+            };
+            index.assets.insert(hash.clone(), asset.clone());
+            Ok(ImportAssetResult { asset, deduplicated: false })
+        })
+    }
+
+    /// Assets matching every condition in `query`, newest first.
+    pub fn query(&self, query: &AssetQuery) -> AppResult<Vec<StoredAsset>> {
+        let index = {
+            let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            self.load_index()?
+        };
+        let text = query.text.as_deref().map(str::to_lowercase);
+        let contains = |field: &Option<String>, needle: &str| {
+            field.as_deref().is_some_and(|f| f.to_lowercase().contains(needle))
+        };
+        let mut assets: Vec<StoredAsset> = index
+            .assets
+            .into_values()
+            .filter(|a| query.kind.is_none_or(|kind| a.kind == kind))
+            .filter(|a| query.tag.as_ref().is_none_or(|tag| a.tags.contains(tag)))
+            .filter(|a| query.project_id.as_ref().is_none_or(|id| a.projects.contains(id)))
+            .filter(|a| {
+                text.as_deref().is_none_or(|needle| {
+                    let source = a.source.clone().unwrap_or_default();
+                    contains(&a.name, needle) || contains(&source.prompt, needle) || contains(&source.model, needle)
+                })
+            })
+            .collect();
+        assets.sort_by_key(|a| std::cmp::Reverse(a.created_at));
+        Ok(assets)
+    }
+
+    /// Add and remove tags on one asset.
+    pub fn tag(&self, hash: &str, add: &[String], remove: &[String]) -> AppResult<StoredAsset> {
+        self.update(|index| {
+            let asset = index
+                .assets
+                .get_mut(hash)
+                .ok_or_else(|| AppError::NotFound { path: hash.to_string() })?;
+            asset.tags.extend(normalize_tags(add));
+            for tag in normalize_tags(remove) {
+                asset.tags.remove(&tag);
+            }
+            Ok(asset.clone())
+        })
+    }
+
+    /// Whether a project asset points at the stored asset, by hash or by path.
+    fn references(&self, stored: &StoredAsset, project: &Project) -> bool {
+        let stored_path = Path::new(&stored.path);
+        project.assets.iter().any(|asset| {
+            asset.content_hash.as_deref().is_some_and(|h| h.eq_ignore_ascii_case(&stored.hash))
+                || Path::new(&asset.path) == stored_path
+                || asset.proxy_path.as_deref().is_some_and(|p| Path::new(p) == stored_path)
+        })
+    }
+
+    /// Refresh project references from `projects` and delete assets nobody references.
+    ///
+    /// Assets in `retained` or imported less than `min_age_secs` ago are kept even when no
+    /// project references them. With `dry_run`, reports what would be removed without touching anything.
+    pub fn collect_garbage(
+        &self,
+        projects: &[Project],
+        retained: &RetainedRefs,
+        min_age_secs: u64,
+        dry_run: bool,
+    ) -> AppResult<AssetGcResult> {
+        let now = now_secs();
+        let collect = |index: &mut AssetIndex| {
+            let mut result = AssetGcResult::default();
+            for stored in index.assets.values_mut() {
+                stored.projects = projects
+                    .iter()
+                    .filter(|p| self.references(stored, p))
+                    .map(|p| p.id.clone())
+                    .collect();
+            }
+            let garbage: Vec<String> = index
+                .assets
+                .values()
+                .filter(|a| a.projects.is_empty() && !retained.covers(a))
+                .filter(|a| now.saturating_sub(a.created_at) >= min_age_secs)
+                .map(|a| a.hash.clone())
+                .collect();
+            for hash in garbage {
+                let Some(asset) = index.assets.get(&hash) else { continue };
+                if !dry_run {
+                    let path = Path::new(&asset.path);
+                    if let Err(e) = fs::remove_file(path) {
+                        if path.exists() {
+                            warn!("删除素材失败: {} ({})", path.display(), e);
+                            continue;
+                        }
+                    }
+                    result.freed_bytes += asset.size;
+                    index.assets.remove(&hash);
+                } else {
+                    result.freed_bytes += asset.size;
+                }
+                result.removed.push(hash);
+            }
+            Ok(result)
+        };
+
+        if dry_run {
+            let _guard = STORE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            collect(&mut self.load_index()?)
+        } else {
+            self.update(collect)
+        }
+    }
+}
+
+/// Merge the metadata of a repeated import into the stored copy of the same content.
+fn merge_import(existing: &mut StoredAsset, params: &ImportAssetParams) -> ImportAssetResult {
+    existing.tags.extend(normalize_tags(&params.tags));
+    existing.projects.extend(params.project_id.clone());
+    if existing.name.is_none() {
+        existing.name = params.name.clone();
+    }
+    if existing.source.is_none() {
+        existing.source = params.source.clone();
+    }
+    ImportAssetResult { asset: existing.clone(), deduplicated: true }
+}
+
+/// Asset hashes and paths referenced by project history and autosave snapshots.
+///
+/// They keep stored assets alive without counting as project references, so restoring an
+/// old version or an autosave never points at deleted objects.
+#[derive(Debug, Default)]
+pub struct RetainedRefs {
+    hashes: BTreeSet<String>,
+    paths: BTreeSet<PathBuf>,
+}
+
+impl RetainedRefs {
+    /// Record the assets of raw project JSON; history and autosave keep it unmigrated.
+    pub fn add_project_value(&mut self, project: &Value) {
+        let Some(assets) = project.get("assets").and_then(Value::as_array) else { return };
+        for asset in assets {
+            if let Some(hash) = asset.get("contentHash").and_then(Value::as_str) {
+                self.hashes.insert(hash.to_ascii_lowercase());
+            }
+            for key in ["path", "proxyPath"] {
+                if let Some(path) = asset.get(key).and_then(Value::as_str) {
+                    self.paths.insert(PathBuf::from(path));
+                }
+            }
+        }
+    }
+
+    fn covers(&self, stored: &StoredAsset) -> bool {
+        self.hashes.contains(&stored.hash.to_ascii_lowercase()) || self.paths.contains(Path::new(&stored.path))
+    }
+}
+
+/// The app's asset store; its objects directory is added to the media scope.
+fn store(app_handle: &AppHandle) -> AppResult<AssetStore> {
+    let store = AssetStore::new(project::app_data_dir(app_handle)?.join(STORE_DIR));
+    let objects = store.objects_dir();
+    fs::create_dir_all(&objects).map_err(|e| AppError::io("create_dir", Some(&objects), e))?;
+    media_scope::allow(&objects);
+    Ok(store)
+}
+
+/// 导入素材到素材库，内容相同的文件只保存一份
+#[tauri::command]
+pub async fn import_asset(params: ImportAssetParams, app_handle: AppHandle) -> AppResult<ImportAssetResult> {
+    media_scope::check(&params.path)?;
+    if let Some(project_id) = &params.project_id {
+        project::validate_project_id(project_id)?;
+    }
+    let backend = FfmpegBackend::new("asset").ok();
+    let result = store(&app_handle)?.import(backend.as_ref().map(|b| b as &dyn MediaBackend), &params)?;
+    info!(
+        "素材已导入: {} -> {}{}",
+        params.path,
+        result.asset.hash,
+        if result.deduplicated { "（已存在）" } else { "" }
+    );
+    Ok(result)
+}
+
+/// 查询素材库
+#[tauri::command]
+pub fn query_assets(query: Option<AssetQuery>, app_handle: AppHandle) -> AppResult<Vec<StoredAsset>> {
+    store(&app_handle)?.query(&query.unwrap_or_default())
+}
+
+/// 为素材添加或移除标签
+#[tauri::command]
+pub fn tag_asset(
+    hash: String,
+    add: Option<Vec<String>>,
+    remove: Option<Vec<String>>,
+    app_handle: AppHandle,
+) -> AppResult<StoredAsset> {
+    store(&app_handle)?.tag(&hash, &add.unwrap_or_default(), &remove.unwrap_or_default())
+}
+
+/// 删除不再被任何项目引用的素材
+///
+/// 历史版本与自动保存快照引用的素材同样保留，任何项目文件无法读取时直接返回错误。
+/// 默认保留一天内导入的素材；`dry_run` 为真时只返回将被删除的素材。
+#[tauri::command]
+pub fn collect_asset_garbage(
+    min_age_secs: Option<u64>,
+    dry_run: Option<bool>,
+    app_handle: AppHandle,
+) -> AppResult<AssetGcResult> {
+    let projects = project::read_all_projects_in(&project::projects_dir(&app_handle)?)?;
+    let mut retained = RetainedRefs::default();
+    history::store(&app_handle)?.for_each_version(|content| retained.add_project_value(content))?;
+    autosave::store(&app_handle)?.for_each_snapshot(|content| retained.add_project_value(content))?;
+    let result = store(&app_handle)?.collect_garbage(
+        &projects,
+        &retained,
+        min_age_secs.unwrap_or(DEFAULT_GC_MIN_AGE_SECS),
+        dry_run.unwrap_or(false),
+    )?;
+    info!("素材库清理完成: 删除 {} 个素材, 释放 {} 字节", result.removed.len(), result.freed_bytes);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::RecordingBackend;
+    use crate::project_schema;
+    use crate::test_support::TempDir;
+    use serde_json::json;
+
+    fn params(path: &str) -> ImportAssetParams {
+        ImportAssetParams { path: path.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn duplicate_imports_share_one_object() {
+        let dir = TempDir::new();
+        let store = AssetStore::new(dir.0.join("store"));
+        let a = dir.write("panel_a.png", b"same pixels");
+        let b = dir.write("panel_b.PNG", b"same pixels");
+
+        let first = store
+            .import(None, &ImportAssetParams {
+                source: Some(AssetSource { prompt: Some("雨夜街道".into()), model: Some("sdxl".into()) }),
+                project_id: Some("p1".into()),
+                ..params(&a)
+            })
+            .unwrap();
+        assert!(!first.deduplicated);
+        assert_eq!(first.asset.kind, AssetKind::Image);
+        assert_eq!(first.asset.hash, fs_utils::hash_file(Path::new(&a)).unwrap());
+        assert!(first.asset.path.ends_with(".png"));
+        assert_eq!(fs::read(&first.asset.path).unwrap(), b"same pixels");
+
+        let second = store
+            .import(None, &ImportAssetParams { project_id: Some("p2".into()), tags: vec!["雨".into()], ..params(&b) })
+            .unwrap();
+        assert!(second.deduplicated);
+        assert_eq!(second.asset.path, first.asset.path);
+        assert_eq!(second.asset.projects.len(), 2);
+        assert_eq!(second.asset.source, first.asset.source);
+
+        let objects: usize = fs::read_dir(store.objects_dir())
+            .unwrap()
+            .map(|d| fs::read_dir(d.unwrap().path()).unwrap().count())
+            .sum();
+        assert_eq!(objects, 1);
+    }
+
+    #[test]
+    fn import_probes_media_metadata() {
+        let dir = TempDir::new();
+        let store = AssetStore::new(dir.0.join("store"));
+        let clip = dir.write("clip.mp4", b"video");
+        let backend = RecordingBackend::new().with_probe_output(
+            r#"{"streams":[{"codec_type":"video","width":1280,"height":720,"r_frame_rate":"24/1"}],"format":{"duration":"3.5"}}"#,
+        );
+        let asset = store.import(Some(&backend), &params(&clip)).unwrap().asset;
+        assert_eq!((asset.kind, asset.width, asset.height, asset.duration), (AssetKind::Video, Some(1280), Some(720), Some(3.5)));
+        assert_eq!(fs::read(&asset.path).unwrap(), b"video");
+        let leftovers = fs::read_dir(Path::new(&asset.path).parent().unwrap()).unwrap().count();
+        assert_eq!(leftovers, 1, "the probed partial copy is renamed into place");
+    }
+
+    #[test]
+    fn query_and_tag() {
+        let dir = TempDir::new();
+        let store = AssetStore::new(dir.0.join("store"));
+        let panel = store
+            .import(None, &ImportAssetParams {
+                source: Some(AssetSource { prompt: Some("Rainy street".into()), model: None }),
+                ..params(&dir.write("panel.png", b"1"))
+            })
+            .unwrap()
+            .asset;
+        store.import(None, &params(&dir.write("voice.wav", b"2"))).unwrap();
+
+        let images = store.query(&AssetQuery { kind: Some(AssetKind::Image), ..Default::default() }).unwrap();
+        assert_eq!(images.len(), 1);
+        let by_prompt = store.query(&AssetQuery { text: Some("RAINY".into()), ..Default::default() }).unwrap();
+        assert_eq!(by_prompt[0].hash, panel.hash);
+
+        store.tag(&panel.hash, &["封面".into(), " ".into()], &[]).unwrap();
+        let tagged = store.query(&AssetQuery { tag: Some("封面".into()), ..Default::default() }).unwrap();
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].tags.len(), 1);
+        let untagged = store.tag(&panel.hash, &[], &["封面".into()]).unwrap();
+        assert!(untagged.tags.is_empty());
+
+        assert!(matches!(store.tag("missing", &[], &[]), Err(AppError::NotFound { .. })));
+    }
+
+    #[test]
+    fn garbage_collection_keeps_referenced_and_recent_assets() {
+        let dir = TempDir::new();
+        let store = AssetStore::new(dir.0.join("store"));
+        let kept = store.import(None, &params(&dir.write("kept.png", b"kept"))).unwrap().asset;
+        let by_hash = store.import(None, &params(&dir.write("hash.png", b"hash"))).unwrap().asset;
+        let orphan = store.import(None, &params(&dir.write("orphan.png", b"orphan"))).unwrap().asset;
+
+        let project = project_schema::load_value(json!({
+            "schemaVersion": 1, "id": "p1", "name": "测试",
+            "assets": [
+                { "id": "a", "kind": "image", "path": kept.path },
+                { "id": "b", "kind": "image", "path": "/elsewhere/hash.png", "contentHash": by_hash.hash }
+            ]
+        }))
+        .unwrap();
+
+        // 刚导入的素材在默认期限内不会被删除
+        assert!(store.collect_garbage(std::slice::from_ref(&project), &RetainedRefs::default(), 3600, false).unwrap().removed.is_empty());
+
+        let preview = store.collect_garbage(std::slice::from_ref(&project), &RetainedRefs::default(), 0, true).unwrap();
+        assert_eq!(preview.removed, vec![orphan.hash.clone()]);
+        assert!(Path::new(&orphan.path).exists());
+
+        let result = store.collect_garbage(std::slice::from_ref(&project), &RetainedRefs::default(), 0, false).unwrap();
+        assert_eq!(result, AssetGcResult { removed: vec![orphan.hash.clone()], freed_bytes: orphan.size });
+        assert!(!Path::new(&orphan.path).exists());
+        let remaining = store.query(&AssetQuery::default()).unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining.iter().all(|a| a.projects.contains("p1")));
+    }
+
+    #[test]
+    fn garbage_collection_keeps_assets_of_old_versions() {
+        let dir = TempDir::new();
+        let store = AssetStore::new(dir.0.join("store"));
+        let by_path = store.import(None, &params(&dir.write("old.png", b"old"))).unwrap().asset;
+        let by_hash = store.import(None, &params(&dir.write("snap.png", b"snap"))).unwrap().asset;
+        let orphan = store.import(None, &params(&dir.write("orphan.png", b"orphan"))).unwrap().asset;
+
+        let mut retained = RetainedRefs::default();
+        retained.add_project_value(&json!({ "assets": [{ "id": "a", "kind": "image", "path": by_path.path }] }));
+        retained.add_project_value(&json!({ "assets": [{ "id": "b", "path": "/gone.png", "contentHash": by_hash.hash.to_uppercase() }] }));
+        retained.add_project_value(&json!({ "name": "no assets" }));
+
+        let result = store.collect_garbage(&[], &retained, 0, false).unwrap();
+        assert_eq!(result.removed, vec![orphan.hash]);
+        assert!(store.query(&AssetQuery::default()).unwrap().iter().all(|a| a.projects.is_empty()));
+    }
+}
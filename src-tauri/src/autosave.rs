@@ -0,0 +1,390 @@
+//! 自动保存
+//!
+//! 前端把有未保存修改的项目状态推送到后端，后台线程按 `AppSettings.auto_save_interval`
+//! 把最新状态写成 `<app_data>/autosave/<项目 ID>/<毫秒时间戳>.json`，每个项目只保留最近
+//! [`KEEP_SNAPSHOTS`] 份。启动后若某个项目的自动保存比最后一次手动保存更新，
+//! 前端可以据此提示恢复。
+
+use crate::error::{AppError, AppResult};
+use crate::fs_utils;
+use crate::project;
+use crate::project_schema::{self, Project};
+use log::{info, warn};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+/// 自动保存所在的应用数据子目录
+const AUTOSAVE_DIR: &str = "autosave";
+
+/// 每个项目保留的自动保存份数
+pub const KEEP_SNAPSHOTS: usize = 10;
+
+/// 自动保存间隔下限（秒），避免设置过小导致频繁写盘
+const MIN_INTERVAL_SECS: u32 = 5;
+
+#[derive(Debug, Clone, Copy)]
+struct AutosaveConfig {
+    enabled: bool,
+    interval_secs: u32,
+}
+
+static CONFIG: RwLock<AutosaveConfig> = RwLock::new(AutosaveConfig { enabled: true, interval_secs: 300 });
+
+// 待写入的项目状态，按项目 ID 只保留最新一份
+static PENDING: Mutex<Option<HashMap<String, Project>>> = Mutex::new(None);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+// 后台写入期间持有；手动保存、恢复与删除项目时也持有，保证排队中的旧状态不会在之后写成快照
+static FLUSH_LOCK: Mutex<()> = Mutex::new(());
+
+// 可恢复的自动保存
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryCandidate {
+    pub project_id: String,
+    pub project_name: String,
+    /// 最新自动保存时间（毫秒时间戳）
+    pub autosaved_at: u64,
+    /// 最后一次手动保存时间，项目文件不存在时为空
+    pub saved_at: Option<u64>,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+fn modified_millis(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    modified.duration_since(UNIX_EPOCH).ok().map(|d| d.as_millis() as u64)
+}
+
+/// Apply the autosave settings; takes effect on the loop's next tick.
+pub fn configure(enabled: bool, interval_secs: u32) {
+    if let Ok(mut config) = CONFIG.write() {
+        *config = AutosaveConfig { enabled, interval_secs: interval_secs.max(MIN_INTERVAL_SECS) };
+    }
+}
+
+fn config() -> AutosaveConfig {
+    CONFIG.read().map(|c| *c).unwrap_or(AutosaveConfig { enabled: false, interval_secs: 300 })
+}
+
+fn with_pending<T>(f: impl FnOnce(&mut HashMap<String, Project>) -> T) -> T {
+    let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+    f(pending.get_or_insert_with(HashMap::new))
+}
+
+/// Drop queued state for a project, e.g. after an explicit save made it redundant.
+pub fn forget_pending(project_id: &str) {
+    with_pending(|pending| pending.remove(project_id));
+}
+
+/// Write an explicit save of `project` to `path` and drop its queued state.
+///
+/// Holds the flush lock, so a flush that already took the older queued state cannot
+/// write it as a snapshot that looks newer than this save.
+pub fn write_saved(path: &Path, project: &Project) -> AppResult<()> {
+    let _guard = FLUSH_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    project::write_project(path, project)?;
+    forget_pending(&project.id);
+    Ok(())
+}
+
+/// Drop every trace of a deleted project: its queued state and all snapshots.
+pub fn forget_project(store: &AutosaveStore, project_id: &str) -> AppResult<()> {
+    let _guard = FLUSH_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    forget_pending(project_id);
+    store.discard(project_id)
+}
+
+/// Rotating autosave snapshots under a root directory.
+pub struct AutosaveStore {
+    root: PathBuf,
+}
+
+impl AutosaveStore {
+    pub fn new(root: impl Into<PathBuf>) -> AutosaveStore {
+        AutosaveStore { root: root.into() }
+    }
+
+    fn project_dir(&self, project_id: &str) -> AppResult<PathBuf> {
+        project::validate_project_id(project_id)?;
+        fs_utils::resolve_within(&self.root, project_id)
+    }
+
+    /// Snapshots of a project as `(timestamp, path)`, newest first.
+    pub fn snapshots(&self, project_id: &str) -> AppResult<Vec<(u64, PathBuf)>> {
+        let Ok(entries) = fs::read_dir(self.project_dir(project_id)?) else { return Ok(Vec::new()) };
+        let mut snapshots: Vec<(u64, PathBuf)> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|p| Some((p.file_stem()?.to_str()?.parse().ok()?, p)))
+            .collect();
+        snapshots.sort_by_key(|(at, _)| std::cmp::Reverse(*at));
+        Ok(snapshots)
+    }
+
+    /// Write a snapshot stamped `at` and delete all but the newest `keep`.
+    pub fn write(&self, project: &Project, at: u64, keep: usize) -> AppResult<PathBuf> {
+        let path = self.project_dir(&project.id)?.join(format!("{}.json", at));
+        fs_utils::atomic_write(&path, serde_json::to_string_pretty(project)?.as_bytes())?;
+        for (_, old) in self.snapshots(&project.id)?.into_iter().skip(keep) {
+            if let Err(e) = fs::remove_file(&old) {
+                warn!("删除旧的自动保存失败: {} ({})", old.display(), e);
+            }
+        }
+        Ok(path)
+    }
+
+    /// The newest snapshot of a project, if any.
+    pub fn latest(&self, project_id: &str) -> AppResult<Option<Project>> {
+        match self.snapshots(project_id)?.first() {
+            Some((_, path)) => project::read_project(path).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Projects whose newest snapshot is newer than their file in `projects_dir`.
+    pub fn recovery_candidates(&self, projects_dir: &Path) -> Vec<RecoveryCandidate> {
+        let Ok(entries) = fs::read_dir(&self.root) else { return Vec::new() };
+        let mut candidates = Vec::new();
+        for entry in entries.flatten() {
+            let Some(project_id) = entry.file_name().to_str().map(str::to_string) else { continue };
+            let Ok(project_file) = project::project_file_in(projects_dir, &project_id) else { continue };
+            let Some((autosaved_at, path)) = self.snapshots(&project_id).ok().and_then(|s| s.into_iter().next())
+            else {
+                continue;
+            };
+            let saved_at = modified_millis(&project_file);
+            if saved_at.is_some_and(|saved| saved >= autosaved_at) {
+                continue;
+            }
+            match project::read_project(&path) {
+                Ok(project) => candidates.push(RecoveryCandidate {
+                    project_id,
+                    project_name: project.name,
+                    autosaved_at,
+                    saved_at,
+                }),
+                Err(e) => warn!("跳过无法读取的自动保存: {} ({})", path.display(), e),
+            }
+        }
+        candidates.sort_by_key(|c| std::cmp::Reverse(c.autosaved_at));
+        candidates
+    }
+
+    /// Call `f` with the raw JSON of every snapshot of every project.
+    pub fn for_each_snapshot(&self, mut f: impl FnMut(&Value)) -> AppResult<()> {
+        for project_id in project::project_dirs_in(&self.root)? {
+            for (_, path) in self.snapshots(&project_id)? {
+                let content = fs::read_to_string(&path).map_err(|e| AppError::io("read_file", Some(&path), e))?;
+                f(&serde_json::from_str(&content)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete every snapshot of a project.
+    pub fn discard(&self, project_id: &str) -> AppResult<()> {
+        let dir = self.project_dir(project_id)?;
+        if dir.exists() {
+            fs::remove_dir_all(&dir).map_err(|e| AppError::io("remove_file", Some(&dir), e))?;
+        }
+        Ok(())
+    }
+
+    /// Write a snapshot for each queued project; failures are logged and the rest continue.
+    pub fn flush(&self, pending: HashMap<String, Project>) -> usize {
+        let at = now_millis();
+        pending
+            .values()
+            .filter(|project| match self.write(project, at, KEEP_SNAPSHOTS) {
+                Ok(_) => true,
+                Err(e) => {
+                    warn!("自动保存失败: {} ({})", project.id, e);
+                    false
+                }
+            })
+            .count()
+    }
+}
+
+/// Start the autosave loop once; later calls are no-ops.
+pub fn start(root: PathBuf) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let store = AutosaveStore::new(root);
+    std::thread::spawn(move || {
+        let mut last_flush = Instant::now();
+        loop {
+            std::thread::sleep(Duration::from_secs(1));
+            let config = config();
+            if !config.enabled || last_flush.elapsed() < Duration::from_secs(config.interval_secs as u64) {
+                continue;
+            }
+            last_flush = Instant::now();
+            let _guard = FLUSH_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            let pending = with_pending(std::mem::take);
+            if !pending.is_empty() {
+                let written = store.flush(pending);
+                info!("自动保存完成: {} 个项目", written);
+            }
+        }
+    });
+}
+
+pub(crate) fn store(app_handle: &AppHandle) -> AppResult<AutosaveStore> {
+    Ok(AutosaveStore::new(autosave_root(app_handle)?))
+}
+
+/// `<app_data>/autosave`.
+pub fn autosave_root(app_handle: &AppHandle) -> AppResult<PathBuf> {
+    Ok(project::app_data_dir(app_handle)?.join(AUTOSAVE_DIR))
+}
+
+/// 推送有未保存修改的项目状态，下一次自动保存时写入
+#[tauri::command]
+pub fn push_autosave_state(project_id: String, content: String) -> AppResult<()> {
+    project::validate_project_id(&project_id)?;
+    let project = project_schema::parse_project(&content)?;
+    if project.id != project_id {
+        return Err(AppError::InvalidProject {
+            path: "id".to_string(),
+            reason: format!("does not match project id {}", project_id),
+        });
+    }
+    with_pending(|pending| pending.insert(project_id, project));
+    Ok(())
+}
+
+/// 列出比最后一次手动保存更新的自动保存，用于启动时的崩溃恢复
+#[tauri::command]
+pub fn get_autosave_recovery(app_handle: AppHandle) -> AppResult<Vec<RecoveryCandidate>> {
+    Ok(store(&app_handle)?.recovery_candidates(&project::projects_dir(&app_handle)?))
+}
+
+/// 用最新的自动保存覆盖项目文件并返回恢复后的项目
+#[tauri::command]
+pub fn restore_autosave(project_id: String, app_handle: AppHandle) -> AppResult<Project> {
+    let project = store(&app_handle)?
+        .latest(&project_id)?
+        .ok_or_else(|| AppError::NotFound { path: format!("{}/{}", AUTOSAVE_DIR, project_id) })?;
+    write_saved(&project::project_file(&app_handle, &project_id)?, &project)?;
+    info!("已从自动保存恢复项目: {}", project_id);
+    Ok(project)
+}
+
+/// 丢弃项目的全部自动保存
+#[tauri::command]
+pub fn discard_autosave(project_id: String, app_handle: AppHandle) -> AppResult<()> {
+    forget_project(&store(&app_handle)?, &project_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use serde_json::json;
+
+    fn project(id: &str, name: &str) -> Project {
+        project_schema::load_value(json!({ "schemaVersion": 1, "id": id, "name": name })).unwrap()
+    }
+
+    #[test]
+    fn snapshots_rotate() {
+        let dir = TempDir::new();
+        let store = AutosaveStore::new(&dir.0);
+        for at in 1..=5 {
+            store.write(&project("p1", &format!("v{}", at)), at, 3).unwrap();
+        }
+        let kept: Vec<u64> = store.snapshots("p1").unwrap().into_iter().map(|(at, _)| at).collect();
+        assert_eq!(kept, vec![5, 4, 3]);
+        assert_eq!(store.latest("p1").unwrap().unwrap().name, "v5");
+        assert_eq!(store.latest("p2").unwrap(), None);
+    }
+
+    #[test]
+    fn recovery_offers_only_autosaves_newer_than_the_project_file() {
+        let dir = TempDir::new();
+        let store = AutosaveStore::new(dir.0.join("autosave"));
+        let projects = dir.0.join("projects");
+        fs::create_dir_all(&projects).unwrap();
+
+        // p1：自动保存晚于手动保存；p2：手动保存更新；p3：从未手动保存
+        fs::write(projects.join("p1.json"), "{}").unwrap();
+        let saved = modified_millis(&projects.join("p1.json")).unwrap();
+        store.write(&project("p1", "未保存的修改"), saved + 10_000, KEEP_SNAPSHOTS).unwrap();
+        fs::write(projects.join("p2.json"), "{}").unwrap();
+        store.write(&project("p2", "旧"), saved - 10_000, KEEP_SNAPSHOTS).unwrap();
+        store.write(&project("p3", "新建"), saved, KEEP_SNAPSHOTS).unwrap();
+
+        let candidates = store.recovery_candidates(&projects);
+        let ids: Vec<&str> = candidates.iter().map(|c| c.project_id.as_str()).collect();
+        assert_eq!(ids, vec!["p1", "p3"]);
+        assert_eq!(candidates[0].project_name, "未保存的修改");
+        assert_eq!(candidates[0].saved_at, Some(saved));
+        assert_eq!(candidates[1].saved_at, None);
+
+        store.discard("p1").unwrap();
+        assert_eq!(store.recovery_candidates(&projects).len(), 1);
+    }
+
+    #[test]
+    fn flush_writes_each_pending_project() {
+        let dir = TempDir::new();
+        let store = AutosaveStore::new(&dir.0);
+        let pending = HashMap::from([
+            ("a".to_string(), project("a", "A")),
+            ("b".to_string(), project("b", "B")),
+        ]);
+        assert_eq!(store.flush(pending), 2);
+        assert_eq!(store.snapshots("a").unwrap().len(), 1);
+        assert_eq!(store.latest("b").unwrap().unwrap().name, "B");
+    }
+
+    #[test]
+    fn pushed_state_must_match_the_project_id() {
+        let content = serde_json::to_string(&project("autosave_push", "x")).unwrap();
+        assert!(matches!(push_autosave_state("other".into(), content.clone()), Err(AppError::InvalidProject { .. })));
+        assert!(matches!(push_autosave_state("../x".into(), content.clone()), Err(AppError::InvalidArgument { .. })));
+        push_autosave_state("autosave_push".into(), content).unwrap();
+        assert!(with_pending(|p| p.contains_key("autosave_push")));
+        forget_pending("autosave_push");
+        assert!(!with_pending(|p| p.contains_key("autosave_push")));
+    }
+
+    #[test]
+    fn explicit_saves_drop_queued_state() {
+        let dir = TempDir::new();
+        let path = dir.0.join("autosave_saved.json");
+        with_pending(|p| p.insert("autosave_saved".into(), project("autosave_saved", "排队中")));
+
+        write_saved(&path, &project("autosave_saved", "已保存")).unwrap();
+        assert!(!with_pending(|p| p.contains_key("autosave_saved")));
+        assert_eq!(project::read_project(&path).unwrap().name, "已保存");
+    }
+
+    #[test]
+    fn forgetting_a_deleted_project_leaves_nothing_to_recover() {
+        let dir = TempDir::new();
+        let store = AutosaveStore::new(dir.0.join("autosave"));
+        let projects = dir.0.join("projects");
+        fs::create_dir_all(&projects).unwrap();
+        store.write(&project("autosave_deleted", "已删除"), 1, KEEP_SNAPSHOTS).unwrap();
+        with_pending(|p| p.insert("autosave_deleted".into(), project("autosave_deleted", "排队中")));
+
+        forget_project(&store, "autosave_deleted").unwrap();
+        assert!(!with_pending(|p| p.contains_key("autosave_deleted")));
+        assert!(store.recovery_candidates(&projects).is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+//! 媒体后端：ffmpeg/ffprobe 调用的抽象
+//!
+//! 媒体与导出逻辑只负责构造参数，再交给 [`MediaBackend`] 执行。正式运行时使用
+//! [`FfmpegBackend`]（真实进程，写入任务日志）；测试中使用 [`RecordingBackend`]，
+//! 只记录每次调用而不启动进程，因此滤镜字符串、编码参数等可以脱离 ffmpeg 验证。
+
+use crate::capabilities::{self, Capabilities};
+use crate::error::{AppError, AppResult};
+use crate::ffmpeg;
+use crate::job_log::JobLog;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+// 被调用的工具
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Tool {
+    Ffmpeg,
+    Ffprobe,
+}
+
+// 一次 ffmpeg/ffprobe 调用
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Invocation {
+    pub tool: Tool,
+    pub stage: String,
+    pub args: Vec<String>,
+}
+
+/// Runs ffmpeg/ffprobe on behalf of media and export code.
+pub trait MediaBackend {
+    /// Run ffmpeg for `stage` and return its stdout.
+    fn ffmpeg(&self, stage: &str, args: &[&str]) -> AppResult<Vec<u8>>;
+
+    /// Run ffprobe with `args` followed by `path` and return its stdout.
+    fn ffprobe(&self, path: &str, args: &[&str]) -> AppResult<Vec<u8>>;
+
+    /// Components available in the ffmpeg build.
+    fn capabilities(&self) -> AppResult<Capabilities>;
+
+    /// ID of the job log errors should point at, if any.
+    fn job_id(&self) -> Option<&str> {
+        None
+    }
+
+    /// Add a free-form line to the job's record.
+    fn note(&self, _message: &str) {}
+}
+
+/// The real backend: spawns the resolved ffmpeg/ffprobe and logs each call to a [`JobLog`].
+pub struct FfmpegBackend {
+    log: JobLog,
+}
+
+impl FfmpegBackend {
+    /// Fails with [`AppError::FfmpegMissing`] when the toolchain cannot be resolved.
+    pub fn new(kind: &str) -> AppResult<FfmpegBackend> {
+        ffmpeg::ensure_ffmpeg()?;
+        Ok(FfmpegBackend { log: JobLog::create(kind) })
+    }
+
+    pub fn log(&self) -> &JobLog {
+        &self.log
+    }
+}
+
+impl MediaBackend for FfmpegBackend {
+    fn ffmpeg(&self, stage: &str, args: &[&str]) -> AppResult<Vec<u8>> {
+        ffmpeg::run_ffmpeg_output(&self.log, stage, args)
+    }
+
+    fn ffprobe(&self, path: &str, args: &[&str]) -> AppResult<Vec<u8>> {
+        ffmpeg::run_ffprobe(&self.log, path, args)
+    }
+
+    fn capabilities(&self) -> AppResult<Capabilities> {
+        capabilities::probe()
+    }
+
+    fn job_id(&self) -> Option<&str> {
+        Some(self.log.job_id())
+    }
+
+    fn note(&self, message: &str) {
+        self.log.note(message);
+    }
+}
+
+/// Capabilities of a typical full ffmpeg build, used as the [`RecordingBackend`] default.
+pub fn full_capabilities() -> Capabilities {
+    let set = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+    Capabilities {
+        ffmpeg_path: "ffmpeg".into(),
+        version: Some("6.1".to_string()),
+        configure_flags: set(&["--enable-gpl", "--enable-libx264", "--enable-libass", "--enable-libvpx"]),
+        encoders: set(&["libx264", "aac", "libvpx-vp9", "libopus", "mjpeg", "png"]),
+        filters: set(&[
+            "scale", "pad", "fps", "format", "fade", "overlay", "xfade", "concat", "volume",
+            "subtitles",
+        ]),
+        muxers: set(&["mp4", "mov", "matroska", "webm", "image2"]),
+        hwaccels: Default::default(),
+    }
+}
+
+/// A backend that records every invocation instead of running it.
+///
+/// By default each call succeeds with empty stdout. [`RecordingBackend::creating_outputs`]
+/// makes ffmpeg calls write an empty file at their last argument so code that commits
+/// outputs can run end to end.
+pub struct RecordingBackend {
+    calls: Mutex<Vec<Invocation>>,
+    caps: Capabilities,
+    probe_output: Vec<u8>,
+    fail_stage: Option<String>,
+    create_outputs: bool,
+}
+
+impl Default for RecordingBackend {
+    fn default() -> Self {
+        RecordingBackend::new()
+    }
+}
+
+impl RecordingBackend {
+    pub fn new() -> RecordingBackend {
+        RecordingBackend {
+            calls: Mutex::new(Vec::new()),
+            caps: full_capabilities(),
+            probe_output: b"{}".to_vec(),
+            fail_stage: None,
+            create_outputs: false,
+        }
+    }
+
+    pub fn with_capabilities(mut self, caps: Capabilities) -> Self {
+        self.caps = caps;
+        self
+    }
+
+    /// Stdout returned by every ffprobe call.
+    pub fn with_probe_output(mut self, output: &str) -> Self {
+        self.probe_output = output.as_bytes().to_vec();
+        self
+    }
+
+    /// Make ffmpeg calls for `stage` fail with [`AppError::EncodeFailed`].
+    pub fn failing_at(mut self, stage: &str) -> Self {
+        self.fail_stage = Some(stage.to_string());
+        self
+    }
+
+    pub fn creating_outputs(mut self) -> Self {
+        self.create_outputs = true;
+        self
+    }
+
+    /// Every invocation so far, in order.
+    pub fn calls(&self) -> Vec<Invocation> {
+        self.calls.lock().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    /// Stage names of every invocation so far, in order.
+    pub fn stages(&self) -> Vec<String> {
+        self.calls().into_iter().map(|c| c.stage).collect()
+    }
+
+    fn push(&self, tool: Tool, stage: &str, args: &[&str]) {
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.push(Invocation {
+                tool,
+                stage: stage.to_string(),
+                args: args.iter().map(|a| a.to_string()).collect(),
+            });
+        }
+    }
+}
+
+impl MediaBackend for RecordingBackend {
+    fn ffmpeg(&self, stage: &str, args: &[&str]) -> AppResult<Vec<u8>> {
+        self.push(Tool::Ffmpeg, stage, args);
+        if self.fail_stage.as_deref() == Some(stage) {
+            return Err(AppError::EncodeFailed {
+                stage: stage.to_string(),
+                exit_code: Some(1),
+                stderr_tail: "simulated failure".to_string(),
+                log_id: None,
+            });
+        }
+        if self.create_outputs {
+            if let Some(output) = args.last() {
+                let output = Path::new(output);
+                fs::write(output, b"").map_err(|e| AppError::io("write_file", Some(output), e))?;
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    fn ffprobe(&self, path: &str, args: &[&str]) -> AppResult<Vec<u8>> {
+        let mut full_args = args.to_vec();
+        full_args.push(path);
+        self.push(Tool::Ffprobe, "probe", &full_args);
+        Ok(self.probe_output.clone())
+    }
+
+    fn capabilities(&self) -> AppResult<Capabilities> {
+        Ok(self.caps.clone())
+    }
+}
@@ -0,0 +1,342 @@
+//! ManGa AI 命令行工具
+//!
+//! 无界面环境（渲染机、CI）下复用与桌面端相同的媒体/导出代码，不启动 webview。
+//! 这里直接调用后端函数而不是 Tauri 命令：路径来自本机用户的命令行，不需要也没有
+//! 桌面端的媒体访问范围。
+//! 所有子命令在 stdout 输出一行 JSON：成功时为 `{"ok":true,"result":...}`，
+//! 失败时为 `{"ok":false,"error":{code,params,message}}`，并以非零退出码结束。
+
+use app_lib::error::{self, AppError, AppResult};
+use app_lib::backend::FfmpegBackend;
+use app_lib::export::{self, AnimaticParams, CutVideoParams};
+use app_lib::{media, project, toolchain};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: manga-ai-cli [global options] <command> [args]
+
+Commands:
+  probe <input>                              Print media metadata
+  thumbnails <input> [--count N] [--out-dir DIR]
+                                             One thumbnail, or N evenly spaced frames
+  cut --params <job.json> [--dry-run]        Multi-segment cut (same params as cut_video)
+  animatic --params <animatic.json>          Render still frames into a video
+  queue run <queue.json> [--fail-fast]       Run a list of cut/animatic/project jobs
+  project export <project.json> --output <file> [--video-id ID] [--dry-run]
+                                             Export a saved project
+
+Global options:
+  --ffmpeg <path>     Use this ffmpeg binary
+  --ffprobe <path>    Use this ffprobe binary
+  --lang <tag>        Language for error messages (zh-CN, en-US)
+  --pretty            Pretty-print JSON output
+  --dry-run           Print the planned ffmpeg commands instead of running them
+  -h, --help          Show this help
+
+Exit codes:
+  0 success, 1 internal error, 2 usage error, 3 ffmpeg missing or lacks a feature,
+  4 invalid input, 5 ffmpeg failed, 6 some queue jobs failed";
+
+/// 不带参数值的开关
+const FLAGS: &[&str] = &["--pretty", "--fail-fast", "--dry-run", "--help", "-h"];
+
+const EXIT_INTERNAL: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_FFMPEG: u8 = 3;
+const EXIT_INPUT: u8 = 4;
+const EXIT_ENCODE: u8 = 5;
+const EXIT_QUEUE_PARTIAL: u8 = 6;
+
+/// Parsed command line: positional words plus `--name value` options and bare flags.
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    fn parse(raw: impl Iterator<Item = String>) -> Result<Args, String> {
+        let mut args = Args { positional: Vec::new(), options: HashMap::new(), flags: Vec::new() };
+        let mut raw = raw.peekable();
+        while let Some(arg) = raw.next() {
+            if FLAGS.contains(&arg.as_str()) {
+                args.flags.push(arg);
+            } else if let Some(name) = arg.strip_prefix("--") {
+                let value = raw.next().ok_or_else(|| format!("missing value for --{}", name))?;
+                args.options.insert(name.to_string(), value);
+            } else {
+                args.positional.push(arg);
+            }
+        }
+        Ok(args)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    fn positional(&self, index: usize, what: &str) -> Result<&str, String> {
+        self.positional.get(index).map(String::as_str).ok_or_else(|| format!("missing {}", what))
+    }
+
+    fn required(&self, name: &str) -> Result<&str, String> {
+        self.option(name).ok_or_else(|| format!("missing --{}", name))
+    }
+}
+
+/// Failure of a CLI command: either bad usage or an error from the backend.
+enum CliError {
+    Usage(String),
+    App(AppError),
+}
+
+impl From<AppError> for CliError {
+    fn from(err: AppError) -> Self {
+        CliError::App(err)
+    }
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::Usage(message)
+    }
+}
+
+fn exit_code_for(err: &AppError) -> u8 {
+    match err {
+        AppError::FfmpegMissing | AppError::MissingCapability { .. } => EXIT_FFMPEG,
+        AppError::InvalidSegment { .. }
+        | AppError::NoSegments
+        | AppError::PathNotAllowed { .. }
+        | AppError::InvalidPath { .. }
+        | AppError::NotFound { .. }
+        | AppError::Serialization { .. }
+        | AppError::InvalidArgument { .. }
+        | AppError::InvalidProject { .. }
+        | AppError::UnsupportedSchemaVersion { .. } => EXIT_INPUT,
+        AppError::ProbeFailed { .. } | AppError::EncodeFailed { .. } => EXIT_ENCODE,
+        _ => EXIT_INTERNAL,
+    }
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &str) -> AppResult<T> {
+    let path_ref = Path::new(path);
+    if !path_ref.is_file() {
+        return Err(AppError::NotFound { path: path.to_string() });
+    }
+    let content = fs::read_to_string(path_ref).map_err(|e| AppError::io("read_file", Some(path_ref), e))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Move generated images out of the temp workspace into `out_dir`.
+fn collect_into(paths: Vec<String>, out_dir: &str, stem: &str) -> AppResult<Vec<String>> {
+    let dir = PathBuf::from(out_dir);
+    fs::create_dir_all(&dir).map_err(|e| AppError::io("create_dir", Some(&dir), e))?;
+    let mut collected = Vec::with_capacity(paths.len());
+    for (i, src) in paths.iter().enumerate() {
+        let src = Path::new(src);
+        let ext = src.extension().and_then(|e| e.to_str()).unwrap_or("jpg");
+        let dest = dir.join(format!("{}_{:03}.{}", stem, i + 1, ext));
+        fs::copy(src, &dest).map_err(|e| AppError::io("write_file", Some(&dest), e))?;
+        let _ = fs::remove_file(src);
+        collected.push(dest.to_string_lossy().to_string());
+    }
+    Ok(collected)
+}
+
+fn cmd_probe(args: &Args) -> Result<Value, CliError> {
+    let input = args.positional(1, "<input>")?;
+    let metadata = media::probe_video(&FfmpegBackend::new("probe")?, input)?;
+    Ok(serde_json::to_value(metadata).map_err(AppError::from)?)
+}
+
+fn cmd_thumbnails(args: &Args) -> Result<Value, CliError> {
+    let input = args.positional(1, "<input>")?;
+    let paths = match args.option("count") {
+        Some(count) => {
+            let count: u32 = count.parse().map_err(|_| format!("invalid --count: {}", count))?;
+            media::key_frames_with(&FfmpegBackend::new("keyframes")?, input, count)?
+        }
+        None => vec![media::thumbnail_with(&FfmpegBackend::new("thumbnail")?, input)?],
+    };
+    let paths = match args.option("out-dir") {
+        Some(out_dir) => {
+            let stem = Path::new(input).file_stem().and_then(|s| s.to_str()).unwrap_or("frame");
+            collect_into(paths, out_dir, stem)?
+        }
+        None => paths,
+    };
+    Ok(json!({ "thumbnails": paths }))
+}
+
+/// Render a cut, or print its execution plan with `--dry-run` / `"dry_run": true`.
+fn run_cut(args: &Args, params: CutVideoParams) -> Result<Value, CliError> {
+    let backend = FfmpegBackend::new("export")?;
+    if args.flag("--dry-run") || params.dry_run.unwrap_or(false) {
+        let plan = export::plan_cut(&backend, &params)?;
+        return Ok(serde_json::to_value(plan).map_err(AppError::from)?);
+    }
+    Ok(json!({ "output": export::render_cut_with(&backend, params)? }))
+}
+
+fn cmd_cut(args: &Args) -> Result<Value, CliError> {
+    let params: CutVideoParams = read_json(args.required("params")?)?;
+    run_cut(args, params)
+}
+
+fn cmd_animatic(args: &Args) -> Result<Value, CliError> {
+    let params: AnimaticParams = read_json(args.required("params")?)?;
+    Ok(json!({ "output": export::render_animatic(params)? }))
+}
+
+fn cmd_project_export(args: &Args) -> Result<Value, CliError> {
+    let path = args.positional(2, "<project.json>")?;
+    let output = args.required("output")?;
+    let project = project::read_project(Path::new(path))?;
+    let params = project::cut_params_from_project(&project, output, args.option("video-id"))?;
+    run_cut(args, params)
+}
+
+// 队列中的单个任务
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum QueueJob {
+    Cut { params: CutVideoParams },
+    Animatic { params: AnimaticParams },
+    Project {
+        project: String,
+        output: String,
+        video_id: Option<String>,
+    },
+}
+
+#[derive(Deserialize)]
+struct QueueEntry {
+    id: Option<String>,
+    #[serde(flatten)]
+    job: QueueJob,
+}
+
+fn run_job(job: QueueJob) -> AppResult<String> {
+    match job {
+        QueueJob::Cut { params } => export::render_cut(params),
+        QueueJob::Animatic { params } => export::render_animatic(params),
+        QueueJob::Project { project: path, output, video_id } => {
+            let project = project::read_project(Path::new(&path))?;
+            export::render_cut(project::cut_params_from_project(&project, &output, video_id.as_deref())?)
+        }
+    }
+}
+
+/// Run every job in order; returns the report and whether any job failed.
+fn cmd_queue_run(args: &Args) -> Result<(Value, bool), CliError> {
+    let path = args.positional(2, "<queue.json>")?;
+    // 队列文件：任务数组，或 `{ "jobs": [...] }`
+    let mut queue: Value = read_json(path)?;
+    let jobs = match queue.get_mut("jobs") {
+        Some(jobs) => jobs.take(),
+        None => queue,
+    };
+    let entries: Vec<QueueEntry> = serde_json::from_value(jobs).map_err(AppError::from)?;
+    let fail_fast = args.flag("--fail-fast");
+
+    let mut results = Vec::with_capacity(entries.len());
+    let mut failed = 0;
+    for (i, entry) in entries.into_iter().enumerate() {
+        let id = entry.id.unwrap_or_else(|| (i + 1).to_string());
+        eprintln!("[{}] 开始任务 {}", i + 1, id);
+        match run_job(entry.job) {
+            Ok(output) => results.push(json!({ "id": id, "ok": true, "output": output })),
+            Err(e) => {
+                eprintln!("[{}] 任务 {} 失败: {}", i + 1, id, e);
+                failed += 1;
+                results.push(json!({ "id": id, "ok": false, "error": e }));
+                if fail_fast {
+                    break;
+                }
+            }
+        }
+    }
+    let succeeded = results.len() - failed;
+    Ok((json!({ "succeeded": succeeded, "failed": failed, "jobs": results }), failed > 0))
+}
+
+fn print_json(value: &Value, pretty: bool) {
+    let text = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    };
+    println!("{}", text.unwrap_or_else(|_| "null".to_string()));
+}
+
+fn main() -> ExitCode {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn"))
+        .target(env_logger::Target::Stderr)
+        .init();
+
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    if args.flag("--help") || args.flag("-h") || args.positional.is_empty() {
+        println!("{}", USAGE);
+        return if args.positional.is_empty() && !args.flag("--help") && !args.flag("-h") {
+            ExitCode::from(EXIT_USAGE)
+        } else {
+            ExitCode::SUCCESS
+        };
+    }
+
+    if let Some(lang) = args.option("lang") {
+        error::set_language(lang);
+    }
+    if args.option("ffmpeg").is_some() || args.option("ffprobe").is_some() {
+        toolchain::configure(args.option("ffmpeg"), args.option("ffprobe"));
+    }
+    let pretty = args.flag("--pretty");
+
+    let command: Vec<&str> = args.positional.iter().take(2).map(String::as_str).collect();
+    let result = match command.as_slice() {
+        ["probe", ..] => cmd_probe(&args),
+        ["thumbnails", ..] => cmd_thumbnails(&args),
+        ["cut", ..] => cmd_cut(&args),
+        ["animatic", ..] => cmd_animatic(&args),
+        ["project", "export"] => cmd_project_export(&args),
+        ["queue", "run"] => match cmd_queue_run(&args) {
+            Ok((report, any_failed)) => {
+                print_json(&json!({ "ok": !any_failed, "result": report }), pretty);
+                return if any_failed { ExitCode::from(EXIT_QUEUE_PARTIAL) } else { ExitCode::SUCCESS };
+            }
+            Err(e) => Err(e),
+        },
+        _ => Err(CliError::Usage(format!("unknown command: {}", args.positional.join(" ")))),
+    };
+
+    match result {
+        Ok(value) => {
+            print_json(&json!({ "ok": true, "result": value }), pretty);
+            ExitCode::SUCCESS
+        }
+        Err(CliError::Usage(message)) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            ExitCode::from(EXIT_USAGE)
+        }
+        Err(CliError::App(err)) => {
+            print_json(&json!({ "ok": false, "error": err }), pretty);
+            ExitCode::from(exit_code_for(&err))
+        }
+    }
+}
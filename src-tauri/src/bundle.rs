@@ -0,0 +1,619 @@
+//! 项目包（`.mangaproj`）导入导出
+//!
+//! 项目包是一个 zip 文件：
+//! - `manifest.json`：格式版本、项目信息，以及包内每个文件的大小与 BLAKE3 校验值
+//! - `project.json`：素材路径改写为包内相对路径（`media/...`）的项目
+//! - `media/`：项目引用的全部媒体文件，可选择有代理的素材只打包代理文件
+//!
+//! 导入时逐个校验文件，全部通过后才把媒体放到 `<app_data>/media/<项目 ID>/`，
+//! 并把素材路径改写为解压后的绝对路径。包内没有的素材路径来自不可信的包，只保留文件名，
+//! 由重新链接功能找回。与已有项目 ID 冲突时按 [`ConflictPolicy`] 处理。
+
+use crate::autosave;
+use crate::error::{AppError, AppResult};
+use crate::fs_utils::{self, new_id, AtomicOutput};
+use crate::media_scope;
+use crate::project;
+use crate::project_schema::{self, Project};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// 当前项目包格式版本
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const PROJECT_ENTRY: &str = "project.json";
+const MEDIA_PREFIX: &str = "media/";
+
+/// 导入的媒体所在的应用数据子目录
+pub const MEDIA_DIR: &str = "media";
+
+/// 清单与项目 JSON 的大小上限，防止解压炸弹
+const MAX_JSON_BYTES: u64 = 64 * 1024 * 1024;
+
+// 包内的单个文件
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleEntry {
+    pub path: String,
+    pub size: u64,
+    pub blake3: String,
+    /// 打包的是代理文件而不是原文件
+    #[serde(default)]
+    pub proxy: bool,
+}
+
+// 项目包清单
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub created_at: u64,
+    pub project_id: String,
+    pub project_name: String,
+    pub schema_version: u32,
+    pub proxies_only: bool,
+    pub files: Vec<BundleEntry>,
+    /// 打包时找不到媒体文件的素材，路径保持原样
+    #[serde(default)]
+    pub missing_assets: Vec<String>,
+}
+
+// 导入前的检查结果
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleInfo {
+    pub manifest: BundleManifest,
+    /// 已存在同 ID 的项目
+    pub conflict: bool,
+}
+
+// 导入时与已有项目 ID 冲突的处理方式
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// 返回 `ProjectExists`
+    #[default]
+    Fail,
+    /// 以新 ID 导入
+    Rename,
+    /// 替换已有项目及其导入的媒体
+    Overwrite,
+}
+
+fn invalid_bundle(path: &str, reason: impl ToString) -> AppError {
+    AppError::InvalidBundle { path: path.to_string(), reason: reason.to_string() }
+}
+
+enum CopyError {
+    Read(io::Error),
+    Write(io::Error),
+}
+
+/// Copy `reader` into `writer`, returning the byte count and BLAKE3 hex digest.
+fn copy_hashed(reader: &mut impl Read, writer: &mut impl Write) -> Result<(u64, String), CopyError> {
+    let mut hasher = blake3::Hasher::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(CopyError::Read(e)),
+        };
+        hasher.update(&buf[..n]);
+        writer.write_all(&buf[..n]).map_err(CopyError::Write)?;
+        size += n as u64;
+    }
+    Ok((size, hasher.finalize().to_hex().to_string()))
+}
+
+/// A flat, printable archive name for a media file.
+fn archive_file_name(source: &Path) -> String {
+    let name = source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_control() || matches!(c, '/' | '\\' | ':') { '_' } else { c })
+        .collect();
+    if name.is_empty() { "media".to_string() } else { name }
+}
+
+/// `media/<name>` with exactly one plain component after the prefix.
+fn is_media_entry(path: &str) -> bool {
+    let Some(name) = path.strip_prefix(MEDIA_PREFIX) else { return false };
+    let mut components = Path::new(name).components();
+    !name.contains('\\')
+        && matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Pack `project` and its media into a bundle at `output`.
+///
+/// Assets whose media cannot be found keep their original path and are listed in
+/// [`BundleManifest::missing_assets`]. Proxy paths are dropped since they would not
+/// resolve on another machine; with `proxies_only`, assets that have a proxy ship it
+/// in place of the original.
+pub fn write_bundle(project: &Project, output: &Path, proxies_only: bool) -> AppResult<BundleManifest> {
+    let mut bundled = project.clone();
+    let mut missing_assets = Vec::new();
+    // 同一文件被多个素材引用时只打包一次
+    let mut packed: HashMap<PathBuf, String> = HashMap::new();
+    let mut sources: Vec<(String, PathBuf, bool)> = Vec::new();
+
+    for asset in &mut bundled.assets {
+        let proxy = asset.proxy_path.take().filter(|p| proxies_only && Path::new(p).is_file());
+        let is_proxy = proxy.is_some();
+        let source = PathBuf::from(proxy.unwrap_or_else(|| asset.path.clone()));
+        if !source.is_file() {
+            missing_assets.push(asset.id.clone());
+            continue;
+        }
+        let key = source.canonicalize().unwrap_or_else(|_| source.clone());
+        asset.path = match packed.get(&key) {
+            Some(name) => name.clone(),
+            None => {
+                let name = format!("{}{:04}-{}", MEDIA_PREFIX, sources.len() + 1, archive_file_name(&source));
+                packed.insert(key, name.clone());
+                sources.push((name.clone(), source, is_proxy));
+                name
+            }
+        };
+    }
+
+    let write_err = |e: &dyn std::fmt::Display| AppError::io("write_file", Some(output), e);
+    let atomic = AtomicOutput::new(output)?;
+    let file = File::create(atomic.temp_path()).map_err(|e| AppError::io("create_file", Some(output), e))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    // 媒体本身已压缩，直接存储即可
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored).large_file(true);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut files = Vec::with_capacity(sources.len() + 1);
+    for (name, source, proxy) in &sources {
+        zip.start_file(name.as_str(), stored).map_err(|e| write_err(&e))?;
+        let mut reader = File::open(source).map_err(|e| AppError::io("read_file", Some(source), e))?;
+        let (size, blake3) = copy_hashed(&mut reader, &mut zip).map_err(|e| match e {
+            CopyError::Read(e) => AppError::io("read_file", Some(source), e),
+            CopyError::Write(e) => write_err(&e),
+        })?;
+        files.push(BundleEntry { path: name.clone(), size, blake3, proxy: *proxy });
+    }
+
+    let project_json = serde_json::to_vec_pretty(&bundled)?;
+    zip.start_file(PROJECT_ENTRY, deflated).map_err(|e| write_err(&e))?;
+    zip.write_all(&project_json).map_err(|e| write_err(&e))?;
+    files.push(BundleEntry {
+        path: PROJECT_ENTRY.to_string(),
+        size: project_json.len() as u64,
+        blake3: blake3::hash(&project_json).to_hex().to_string(),
+        proxy: false,
+    });
+
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: now_secs(),
+        project_id: project.id.clone(),
+        project_name: project.name.clone(),
+        schema_version: project_schema::CURRENT_SCHEMA_VERSION,
+        proxies_only,
+        files,
+        missing_assets,
+    };
+    zip.start_file(MANIFEST_ENTRY, deflated).map_err(|e| write_err(&e))?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest)?).map_err(|e| write_err(&e))?;
+
+    let mut writer = zip.finish().map_err(|e| write_err(&e))?;
+    writer.flush().map_err(|e| write_err(&e))?;
+    drop(writer);
+    atomic.commit()?;
+    Ok(manifest)
+}
+
+fn open_archive(bundle: &Path) -> AppResult<ZipArchive<File>> {
+    let file = File::open(bundle).map_err(|e| AppError::io("read_file", Some(bundle), e))?;
+    ZipArchive::new(file).map_err(|e| invalid_bundle(&bundle.to_string_lossy(), e))
+}
+
+/// Stream `entry` into `writer`, failing unless its size and checksum match the manifest.
+fn extract_verified(archive: &mut ZipArchive<File>, entry: &BundleEntry, writer: &mut impl Write, dest: &Path) -> AppResult<()> {
+    let file = archive.by_name(&entry.path).map_err(|e| invalid_bundle(&entry.path, e))?;
+    // 多读一个字节，超出清单大小即判定不一致
+    let mut limited = file.take(entry.size.saturating_add(1));
+    let (size, blake3) = copy_hashed(&mut limited, writer).map_err(|e| match e {
+        CopyError::Read(e) => invalid_bundle(&entry.path, e),
+        CopyError::Write(e) => AppError::io("write_file", Some(dest), e),
+    })?;
+    if size != entry.size || blake3 != entry.blake3 {
+        return Err(invalid_bundle(&entry.path, "checksum mismatch"));
+    }
+    Ok(())
+}
+
+fn manifest_from(archive: &mut ZipArchive<File>) -> AppResult<BundleManifest> {
+    let file = archive.by_name(MANIFEST_ENTRY).map_err(|e| invalid_bundle(MANIFEST_ENTRY, e))?;
+    let mut bytes = Vec::new();
+    file.take(MAX_JSON_BYTES)
+        .read_to_end(&mut bytes)
+        .map_err(|e| invalid_bundle(MANIFEST_ENTRY, e))?;
+    let manifest: BundleManifest = serde_json::from_slice(&bytes).map_err(|e| invalid_bundle(MANIFEST_ENTRY, e))?;
+
+    if manifest.format_version > BUNDLE_FORMAT_VERSION {
+        return Err(invalid_bundle(
+            MANIFEST_ENTRY,
+            format!("format version {} is newer than supported {}", manifest.format_version, BUNDLE_FORMAT_VERSION),
+        ));
+    }
+    for entry in &manifest.files {
+        if entry.path != PROJECT_ENTRY && !is_media_entry(&entry.path) {
+            return Err(invalid_bundle(&entry.path, "unexpected entry"));
+        }
+    }
+    match manifest.files.iter().find(|e| e.path == PROJECT_ENTRY) {
+        Some(entry) if entry.size <= MAX_JSON_BYTES => Ok(manifest),
+        Some(_) => Err(invalid_bundle(PROJECT_ENTRY, "too large")),
+        None => Err(invalid_bundle(MANIFEST_ENTRY, "project.json is not listed")),
+    }
+}
+
+/// Read and sanity-check a bundle's manifest without extracting anything.
+pub fn read_manifest(bundle: &Path) -> AppResult<BundleManifest> {
+    manifest_from(&mut open_archive(bundle)?)
+}
+
+/// Verify and unpack a bundle.
+///
+/// The project JSON goes to `projects_dir` and media to `media_root/<project id>/`. Media is
+/// extracted into a staging directory first, so a corrupt bundle leaves nothing behind. When
+/// overwriting, the previous media is only deleted once the new project file is written.
+/// Assets not shipped in the bundle are reduced to their file name, which relinking can still
+/// match but which never points anywhere on this machine; proxy paths are dropped.
+pub fn unpack_bundle(
+    bundle: &Path,
+    projects_dir: &Path,
+    media_root: &Path,
+    policy: ConflictPolicy,
+) -> AppResult<Project> {
+    let mut archive = open_archive(bundle)?;
+    let manifest = manifest_from(&mut archive)?;
+
+    let project_entry = manifest.files.iter().find(|e| e.path == PROJECT_ENTRY).expect("checked by manifest_from");
+    let mut project_json = Vec::new();
+    extract_verified(&mut archive, project_entry, &mut project_json, bundle)?;
+    let content = String::from_utf8(project_json).map_err(|e| invalid_bundle(PROJECT_ENTRY, e))?;
+    let mut project = project_schema::parse_project(&content)?;
+    if project.id != manifest.project_id {
+        return Err(invalid_bundle(PROJECT_ENTRY, "project id does not match the manifest"));
+    }
+
+    let mut project_path = project::project_file_in(projects_dir, &project.id)?;
+    if project_path.exists() {
+        match policy {
+            ConflictPolicy::Fail => return Err(AppError::ProjectExists { id: project.id }),
+            ConflictPolicy::Rename => {
+                project.id = new_id();
+                project_path = project::project_file_in(projects_dir, &project.id)?;
+            }
+            ConflictPolicy::Overwrite => {}
+        }
+    }
+
+    fs::create_dir_all(media_root).map_err(|e| AppError::io("create_dir", Some(media_root), e))?;
+    let media_dir = fs_utils::resolve_within(media_root, &project.id)?;
+    let staging = media_root.join(format!(".import_{}", new_id()));
+    fs::create_dir(&staging).map_err(|e| AppError::io("create_dir", Some(&staging), e))?;
+    let extracted = manifest
+        .files
+        .iter()
+        .filter(|entry| entry.path != PROJECT_ENTRY)
+        .try_for_each(|entry| {
+            let dest = fs_utils::resolve_within(&staging, &entry.path[MEDIA_PREFIX.len()..])?;
+            let mut file = File::create(&dest).map_err(|e| AppError::io("create_file", Some(&dest), e))?;
+            extract_verified(&mut archive, entry, &mut file, &dest)
+        });
+    if let Err(e) = extracted {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    // 旧媒体先移到一旁，项目写入成功后才删除，失败时还原
+    let previous = media_dir.exists().then(|| media_root.join(format!(".replaced_{}", new_id())));
+    if let Some(previous) = &previous {
+        if let Err(e) = fs::rename(&media_dir, previous) {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(AppError::io("rename", Some(&media_dir), e));
+        }
+    }
+    if let Err(e) = fs::rename(&staging, &media_dir) {
+        let _ = fs::remove_dir_all(&staging);
+        restore_media(&media_dir, previous.as_deref());
+        return Err(AppError::io("rename", Some(&media_dir), e));
+    }
+
+    for asset in &mut project.assets {
+        asset.proxy_path = None;
+        if manifest.files.iter().any(|e| e.path == asset.path && e.path != PROJECT_ENTRY) {
+            asset.path = media_dir.join(&asset.path[MEDIA_PREFIX.len()..]).to_string_lossy().to_string();
+        } else {
+            asset.path = asset.path.rsplit(['/', '\\']).next().unwrap_or_default().to_string();
+        }
+    }
+
+    if let Err(e) = project::write_project(&project_path, &project) {
+        restore_media(&media_dir, previous.as_deref());
+        return Err(e);
+    }
+    if let Some(previous) = previous {
+        if let Err(e) = fs::remove_dir_all(&previous) {
+            warn!("删除被替换的媒体目录失败: {} ({})", previous.display(), e);
+        }
+    }
+    Ok(project)
+}
+
+/// Replace a half-imported media directory with the one it was meant to replace.
+fn restore_media(media_dir: &Path, previous: Option<&Path>) {
+    let _ = fs::remove_dir_all(media_dir);
+    if let Some(previous) = previous {
+        if let Err(e) = fs::rename(previous, media_dir) {
+            warn!("还原原有媒体目录失败: {} ({})", previous.display(), e);
+        }
+    }
+}
+
+fn media_root(app_handle: &AppHandle) -> AppResult<PathBuf> {
+    Ok(project::app_data_dir(app_handle)?.join(MEDIA_DIR))
+}
+
+/// 将项目打包为 `.mangaproj` 文件
+///
+/// `proxies_only` 为真时，有代理文件的素材只打包代理。
+#[tauri::command]
+pub async fn export_project_bundle(
+    project_id: String,
+    output_path: String,
+    proxies_only: Option<bool>,
+    app_handle: AppHandle,
+) -> AppResult<BundleManifest> {
+    media_scope::check_output(&output_path)?;
+    let project = project::read_project(&project::project_file(&app_handle, &project_id)?)?;
+    let manifest = write_bundle(&project, Path::new(&output_path), proxies_only.unwrap_or(false))?;
+    info!("项目已打包: {} -> {} ({} 个文件)", project_id, output_path, manifest.files.len());
+    Ok(manifest)
+}
+
+/// 读取项目包清单，并检查是否与已有项目冲突
+#[tauri::command]
+pub fn inspect_project_bundle(bundle_path: String, app_handle: AppHandle) -> AppResult<BundleInfo> {
+    media_scope::check(&bundle_path)?;
+    let manifest = read_manifest(Path::new(&bundle_path))?;
+    let conflict = project::project_file(&app_handle, &manifest.project_id)?.exists();
+    Ok(BundleInfo { manifest, conflict })
+}
+
+/// 导入项目包：校验文件、解压媒体并改写素材路径
+#[tauri::command]
+pub async fn import_project_bundle(
+    bundle_path: String,
+    on_conflict: Option<ConflictPolicy>,
+    app_handle: AppHandle,
+) -> AppResult<Project> {
+    media_scope::check(&bundle_path)?;
+    let media_root = media_root(&app_handle)?;
+    let policy = on_conflict.unwrap_or_default();
+    let project = unpack_bundle(Path::new(&bundle_path), &project::projects_dir(&app_handle)?, &media_root, policy)?;
+    if policy == ConflictPolicy::Overwrite {
+        // 被替换项目的自动保存不再对应当前文件
+        if let Err(e) = autosave::store(&app_handle).and_then(|store| autosave::forget_project(&store, &project.id)) {
+            warn!("清理被替换项目的自动保存失败: {} ({})", project.id, e);
+        }
+    }
+    media_scope::allow(&fs_utils::resolve_within(&media_root, &project.id)?);
+    info!("项目包已导入: {} -> {}", bundle_path, project.id);
+    Ok(project)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use serde_json::json;
+
+    fn sample_project(source: &TempDir) -> Project {
+        let video = source.write("clip.mp4", b"video bytes");
+        let proxy = source.write("clip_proxy.mp4", b"proxy");
+        let image = source.write("panel.png", b"image bytes");
+        project_schema::load_value(json!({
+            "schemaVersion": 1,
+            "id": "p1",
+            "name": "第一集",
+            "assets": [
+                { "id": "v1", "kind": "video", "path": video, "proxyPath": proxy },
+                { "id": "i1", "kind": "image", "path": image },
+                { "id": "i2", "kind": "image", "path": image },
+                { "id": "gone", "kind": "audio", "path": "/nonexistent/voice.wav" }
+            ],
+            "timeline": { "duration": 2.0, "clips": [
+                { "id": "c1", "assetId": "v1", "startTime": 0.0, "endTime": 2.0 }
+            ] }
+        }))
+        .unwrap()
+    }
+
+    struct Fixture {
+        _source: TempDir,
+        data: TempDir,
+        bundle: PathBuf,
+    }
+
+    impl Fixture {
+        fn new(proxies_only: bool) -> (Fixture, BundleManifest) {
+            let source = TempDir::new();
+            let data = TempDir::new();
+            let bundle = source.0.join("p1.mangaproj");
+            let manifest = write_bundle(&sample_project(&source), &bundle, proxies_only).unwrap();
+            (Fixture { _source: source, data, bundle }, manifest)
+        }
+
+        fn unpack(&self, policy: ConflictPolicy) -> AppResult<Project> {
+            unpack_bundle(&self.bundle, &self.data.0.join("projects"), &self.data.0.join("media"), policy)
+        }
+    }
+
+    /// Rewrite the bundle, passing every entry through `edit`.
+    fn rewrite_bundle(bundle: &Path, edit: impl Fn(&str, Vec<u8>) -> Vec<u8>) {
+        let mut archive = open_archive(bundle).unwrap();
+        let mut entries = Vec::new();
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i).unwrap();
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes).unwrap();
+            entries.push((file.name().to_string(), bytes));
+        }
+        let mut zip = ZipWriter::new(File::create(bundle).unwrap());
+        for (name, bytes) in entries {
+            zip.start_file(name.as_str(), SimpleFileOptions::default()).unwrap();
+            zip.write_all(&edit(&name, bytes)).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn round_trip_remaps_media_paths() {
+        let (fixture, manifest) = Fixture::new(false);
+        assert_eq!(manifest.files.len(), 3, "shared image is packed once");
+        assert_eq!(manifest.missing_assets, vec!["gone"]);
+        assert_eq!(read_manifest(&fixture.bundle).unwrap(), manifest);
+
+        let project = fixture.unpack(ConflictPolicy::Fail).unwrap();
+        let media_dir = fixture.data.0.join("media").join("p1");
+        let video = &project.assets[0];
+        assert!(Path::new(&video.path).starts_with(&media_dir));
+        assert_eq!(fs::read(&video.path).unwrap(), b"video bytes");
+        assert_eq!(video.proxy_path, None);
+        assert_eq!(project.assets[1].path, project.assets[2].path);
+        assert_eq!(fs::read(&project.assets[1].path).unwrap(), b"image bytes");
+        assert_eq!(project.assets[3].path, "voice.wav", "unshipped paths keep only the file name");
+
+        let saved = project::read_project(&fixture.data.0.join("projects").join("p1.json")).unwrap();
+        assert_eq!(saved, project);
+    }
+
+    #[test]
+    fn proxies_only_ships_the_proxy() {
+        let (fixture, manifest) = Fixture::new(true);
+        assert!(manifest.files.iter().any(|e| e.proxy));
+        let project = fixture.unpack(ConflictPolicy::Fail).unwrap();
+        assert_eq!(fs::read(&project.assets[0].path).unwrap(), b"proxy");
+    }
+
+    #[test]
+    fn collisions_follow_the_policy() {
+        let (fixture, _) = Fixture::new(false);
+        fixture.unpack(ConflictPolicy::Fail).unwrap();
+
+        assert!(matches!(fixture.unpack(ConflictPolicy::Fail), Err(AppError::ProjectExists { id }) if id == "p1"));
+
+        let renamed = fixture.unpack(ConflictPolicy::Rename).unwrap();
+        assert_ne!(renamed.id, "p1");
+        assert!(fixture.data.0.join("projects").join(format!("{}.json", renamed.id)).is_file());
+        assert!(fs::read(&renamed.assets[0].path).is_ok());
+
+        let overwritten = fixture.unpack(ConflictPolicy::Overwrite).unwrap();
+        assert_eq!(overwritten.id, "p1");
+        assert!(fs::read(&overwritten.assets[0].path).is_ok());
+    }
+
+    #[test]
+    fn failed_overwrite_keeps_the_previous_media() {
+        let (fixture, _) = Fixture::new(false);
+        fixture.unpack(ConflictPolicy::Fail).unwrap();
+        let media = fixture.data.0.join("media");
+        fs::write(media.join("p1").join("marker"), b"old").unwrap();
+        // 项目文件位置被目录占用，写入必然失败
+        let project_path = fixture.data.0.join("projects").join("p1.json");
+        fs::remove_file(&project_path).unwrap();
+        fs::create_dir_all(project_path.join("blocker")).unwrap();
+
+        assert!(fixture.unpack(ConflictPolicy::Overwrite).is_err());
+        assert_eq!(fs::read(media.join("p1").join("marker")).unwrap(), b"old");
+        assert_eq!(fs::read_dir(&media).unwrap().count(), 1, "no staging or replaced dirs remain");
+
+        fs::remove_dir_all(&project_path).unwrap();
+        fixture.unpack(ConflictPolicy::Overwrite).unwrap();
+        assert!(!media.join("p1").join("marker").exists());
+        assert_eq!(fs::read_dir(&media).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn tampered_media_is_rejected_without_leftovers() {
+        let (fixture, _) = Fixture::new(false);
+        rewrite_bundle(&fixture.bundle, |name, bytes| {
+            if name.ends_with("clip.mp4") { b"tampered!!!".to_vec() } else { bytes }
+        });
+
+        let err = fixture.unpack(ConflictPolicy::Fail).unwrap_err();
+        assert!(matches!(&err, AppError::InvalidBundle { reason, .. } if reason == "checksum mismatch"), "{:?}", err);
+        assert!(!fixture.data.0.join("projects").join("p1.json").exists());
+        let media = fs::read_dir(fixture.data.0.join("media")).unwrap().count();
+        assert_eq!(media, 0);
+    }
+
+    #[test]
+    fn traversal_entries_in_manifest_are_rejected() {
+        let (fixture, _) = Fixture::new(false);
+        rewrite_bundle(&fixture.bundle, |name, bytes| {
+            if name != MANIFEST_ENTRY {
+                return bytes;
+            }
+            let mut manifest: BundleManifest = serde_json::from_slice(&bytes).unwrap();
+            manifest.files[0].path = "media/../../escape.mp4".to_string();
+            serde_json::to_vec(&manifest).unwrap()
+        });
+        assert!(matches!(read_manifest(&fixture.bundle), Err(AppError::InvalidBundle { .. })));
+
+        for path in ["media/a/b.mp4", "media/", "../x", "/etc/passwd", "media\\..\\x", "media/.."] {
+            assert!(!is_media_entry(path), "{}", path);
+        }
+        assert!(is_media_entry("media/0001-片段.mp4"));
+    }
+
+    #[test]
+    fn bundled_project_id_is_validated() {
+        let (fixture, _) = Fixture::new(false);
+        // 连同清单中的校验值一起改写，确保拦截来自 ID 校验
+        let project_json = std::cell::RefCell::new(Vec::new());
+        rewrite_bundle(&fixture.bundle, |name, bytes| match name {
+            PROJECT_ENTRY => {
+                let mut value: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+                value["id"] = json!("../p1");
+                *project_json.borrow_mut() = serde_json::to_vec(&value).unwrap();
+                project_json.borrow().clone()
+            }
+            MANIFEST_ENTRY => {
+                let mut manifest: BundleManifest = serde_json::from_slice(&bytes).unwrap();
+                let new_json = project_json.borrow();
+                manifest.project_id = "../p1".to_string();
+                let entry = manifest.files.iter_mut().find(|e| e.path == PROJECT_ENTRY).unwrap();
+                entry.size = new_json.len() as u64;
+                entry.blake3 = blake3::hash(&new_json).to_hex().to_string();
+                serde_json::to_vec(&manifest).unwrap()
+            }
+            _ => bytes,
+        });
+        assert!(matches!(fixture.unpack(ConflictPolicy::Fail), Err(AppError::InvalidArgument { .. })));
+        assert!(!fixture.data.0.join("p1.json").exists());
+    }
+}
@@ -0,0 +1,370 @@
+//! ffmpeg 功能探测
+//!
+//! 启动时解析 `ffmpeg -version/-encoders/-filters/-muxers/-hwaccels` 的输出并缓存，
+//! 导出前据此检查所需的编码器、滤镜和封装格式：缺少时优先换用备选编码器，
+//! 无可用替代时在开始处理前直接返回 [`AppError::MissingCapability`]。
+
+use crate::compat;
+use crate::error::{AppError, AppResult};
+use crate::ffmpeg::run_ffmpeg_output;
+use crate::job_log::JobLog;
+use crate::toolchain;
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+// 当前 ffmpeg 支持的组件
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    pub ffmpeg_path: PathBuf,
+    /// `ffmpeg -version` 中的版本字符串
+    pub version: Option<String>,
+    pub configure_flags: BTreeSet<String>,
+    pub encoders: BTreeSet<String>,
+    pub filters: BTreeSet<String>,
+    pub muxers: BTreeSet<String>,
+    pub hwaccels: BTreeSet<String>,
+}
+
+static CAPABILITIES: RwLock<Option<Capabilities>> = RwLock::new(None);
+
+/// Names listed after the `--`/`------` separator of `-encoders` / `-muxers` output.
+/// Each row is `<flags> <name>[,<alias>...] <description>`.
+fn parse_table(output: &str) -> BTreeSet<String> {
+    output
+        .lines()
+        .skip_while(|l| !l.trim_start().starts_with("--"))
+        .skip(1)
+        .filter_map(|l| l.split_whitespace().nth(1))
+        .flat_map(|names| names.split(','))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Filter names from `-filters` output, whose rows look like `T.C xfade  VV->V  Cross fade`.
+fn parse_filters(output: &str) -> BTreeSet<String> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let mut parts = l.split_whitespace();
+            let (_flags, name, io) = (parts.next()?, parts.next()?, parts.next()?);
+            io.contains("->").then(|| name.to_string())
+        })
+        .collect()
+}
+
+/// Method names from `-hwaccels` output (one per line after the header).
+fn parse_hwaccels(output: &str) -> BTreeSet<String> {
+    output
+        .lines()
+        .skip_while(|l| !l.trim_end().ends_with(':'))
+        .skip(1)
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn list(log: &JobLog, what: &str) -> AppResult<String> {
+    let flag = format!("-{}", what);
+    let stdout = run_ffmpeg_output(log, what, &["-hide_banner", &flag])?;
+    Ok(String::from_utf8_lossy(&stdout).to_string())
+}
+
+/// Run `-version` and the four listing commands against the resolved ffmpeg.
+fn detect() -> AppResult<Capabilities> {
+    let toolchain = toolchain::resolve()?;
+    let log = JobLog::create("capabilities");
+    let version_stdout = run_ffmpeg_output(&log, "version", &["-version"])?;
+    let version_output = String::from_utf8_lossy(&version_stdout);
+    let caps = Capabilities {
+        ffmpeg_path: toolchain.ffmpeg.path,
+        version: compat::parse_version_token(&version_output),
+        configure_flags: compat::parse_configure_flags(&version_output),
+        encoders: parse_table(&list(&log, "encoders")?),
+        filters: parse_filters(&list(&log, "filters")?),
+        muxers: parse_table(&list(&log, "muxers")?),
+        hwaccels: parse_hwaccels(&list(&log, "hwaccels")?),
+    };
+    info!(
+        "FFmpeg 功能探测完成: 版本 {}, {} 个编码器, {} 个滤镜, {} 个封装格式, 硬件加速: {:?}",
+        caps.version.as_deref().unwrap_or("unknown"),
+        caps.encoders.len(), caps.filters.len(), caps.muxers.len(), caps.hwaccels
+    );
+    Ok(caps)
+}
+
+/// Return the cached capabilities, probing again if missing or if the ffmpeg binary changed.
+pub fn probe() -> AppResult<Capabilities> {
+    let ffmpeg_path = toolchain::resolve()?.ffmpeg.path;
+    if let Some(caps) = CAPABILITIES.read().ok().and_then(|c| c.clone()) {
+        if caps.ffmpeg_path == ffmpeg_path {
+            return Ok(caps);
+        }
+    }
+    refresh()
+}
+
+/// Probe unconditionally and replace the cache.
+pub fn refresh() -> AppResult<Capabilities> {
+    let caps = detect()?;
+    if let Ok(mut cached) = CAPABILITIES.write() {
+        *cached = Some(caps.clone());
+    }
+    Ok(caps)
+}
+
+/// Probe in the background so the first export does not pay for it.
+pub fn probe_in_background() {
+    std::thread::spawn(|| {
+        if let Err(e) = probe() {
+            warn!("FFmpeg 功能探测失败: {}", e);
+        }
+    });
+}
+
+// 导出任务需要的功能
+#[derive(Debug, Clone)]
+pub struct ExportFeatures<'a> {
+    pub format: &'a str,
+    pub transition: &'a str,
+    pub subtitles: bool,
+    pub volume: bool,
+    pub scale: bool,
+}
+
+// 实际使用的编码器
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportCodecs {
+    pub video: String,
+    pub audio: String,
+    /// 是否因首选编码器缺失而改用了备选编码器
+    pub fallback: bool,
+}
+
+impl ExportCodecs {
+    /// Whether the video encoder understands x264-style `-preset`.
+    pub fn supports_preset(&self) -> bool {
+        self.video == "libx264"
+    }
+}
+
+/// Encoder preference lists per output container, best first.
+fn encoder_candidates(format: &str) -> (&'static [&'static str], &'static [&'static str]) {
+    match format {
+        "webm" => (&["libvpx-vp9", "libvpx"], &["libopus", "libvorbis"]),
+        _ => (
+            &["libx264", "libopenh264", "h264_videotoolbox", "h264_mf", "mpeg4"],
+            &["aac", "libfdk_aac"],
+        ),
+    }
+}
+
+fn muxer_name(format: &str) -> &str {
+    match format {
+        "mkv" => "matroska",
+        other => other,
+    }
+}
+
+fn pick_encoder(caps: &Capabilities, feature: &str, candidates: &[&str]) -> AppResult<(String, bool)> {
+    match candidates.iter().position(|c| caps.encoders.contains(*c)) {
+        Some(i) => Ok((candidates[i].to_string(), i > 0)),
+        None => Err(AppError::MissingCapability {
+            feature: feature.to_string(),
+            kind: "encoder".to_string(),
+            name: candidates[0].to_string(),
+        }),
+    }
+}
+
+fn require_filter(caps: &Capabilities, feature: &str, filter: &str) -> AppResult<()> {
+    if caps.filters.contains(filter) {
+        Ok(())
+    } else {
+        Err(AppError::MissingCapability {
+            feature: feature.to_string(),
+            kind: "filter".to_string(),
+            name: filter.to_string(),
+        })
+    }
+}
+
+/// Check an export against `caps` and choose the encoders to use.
+pub fn plan_export(caps: &Capabilities, features: &ExportFeatures) -> AppResult<ExportCodecs> {
+    let muxer = muxer_name(features.format);
+    if !caps.muxers.contains(muxer) {
+        return Err(AppError::MissingCapability {
+            feature: features.format.to_string(),
+            kind: "muxer".to_string(),
+            name: muxer.to_string(),
+        });
+    }
+
+    let (video_candidates, audio_candidates) = encoder_candidates(features.format);
+    let (video, video_fallback) = pick_encoder(caps, features.format, video_candidates)?;
+    let (audio, audio_fallback) = pick_encoder(caps, features.format, audio_candidates)?;
+
+    if features.scale {
+        require_filter(caps, "scale", "scale")?;
+    }
+    if features.volume {
+        require_filter(caps, "volume", "volume")?;
+    }
+    if features.subtitles {
+        // subtitles 滤镜依赖 --enable-libass
+        require_filter(caps, "subtitles", "subtitles")?;
+    }
+    match features.transition {
+        "none" => {}
+        "fade" => {
+            for filter in ["format", "fade", "overlay"] {
+                require_filter(caps, "fade", filter)?;
+            }
+        }
+        "dissolve" | "wipe" | "slide" => require_filter(caps, features.transition, "xfade")?,
+        _ => require_filter(caps, features.transition, "concat")?,
+    }
+
+    let fallback = video_fallback || audio_fallback;
+    if fallback {
+        warn!("导出 {} 使用备选编码器: video={}, audio={}", features.format, video, audio);
+    }
+    Ok(ExportCodecs { video, audio, fallback })
+}
+
+/// 获取 ffmpeg 支持的编码器、滤镜、封装格式与硬件加速方式；`refresh` 为 true 时重新探测
+#[tauri::command]
+pub fn get_ffmpeg_capabilities(refresh: Option<bool>) -> AppResult<Capabilities> {
+    if refresh.unwrap_or(false) {
+        self::refresh()
+    } else {
+        probe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 以下输出截取自 ffmpeg 6.1 的 `-hide_banner -encoders/-filters/-muxers/-hwaccels`
+    const ENCODERS: &str = "\
+Encoders:
+ V..... = Video
+ A..... = Audio
+ S..... = Subtitle
+ .F.... = Frame-level multithreading
+ ..S... = Slice-level multithreading
+ ...X.. = Codec is experimental
+ ....B. = Supports draw_horiz_band
+ .....D = Supports direct rendering method 1
+ ------
+ V....D libopenh264          OpenH264 H.264 / AVC / MPEG-4 AVC / MPEG-4 part 10 (codec h264)
+ V....D mpeg4                MPEG-4 part 2
+ A....D aac                  AAC (Advanced Audio Coding)
+ A....D libopus              libopus Opus (codec opus)
+ S..... ssa,ass              ASS (Advanced SubStation Alpha) subtitle
+";
+
+    const FILTERS: &str = "\
+Filters:
+  T.. = Timeline support
+  .S. = Slice threading
+  ..C = Command support
+  A = Audio input/output
+  V = Video input/output
+  N = Dynamic number and/or type of input/output
+  | = Source or sink filter
+ ..C concat            N->N       Concatenate audio and video streams.
+ TSC scale             V->V       Scale the input video size and/or convert the image format.
+ .S. xfade             VV->V      Cross fade one video with another video.
+ TSC volume            A->A       Change input volume.
+ ... anullsrc          |->A       Null audio source, return empty audio frames.
+";
+
+    const MUXERS: &str = "\
+ File formats:
+ D. = Demuxing supported
+ .E = Muxing supported
+ --
+  E matroska        Matroska
+  E mp4             MP4 (MPEG-4 Part 14)
+  E webm            WebM
+";
+
+    const HWACCELS: &str = "\
+Hardware acceleration methods:
+vdpau
+cuda
+vaapi
+
+";
+
+    fn captured() -> Capabilities {
+        Capabilities {
+            encoders: parse_table(ENCODERS),
+            filters: parse_filters(FILTERS),
+            muxers: parse_table(MUXERS),
+            hwaccels: parse_hwaccels(HWACCELS),
+            ..Default::default()
+        }
+    }
+
+    fn set(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn tables_skip_the_legend_and_split_aliases() {
+        assert_eq!(
+            parse_table(ENCODERS),
+            set(&["libopenh264", "mpeg4", "aac", "libopus", "ssa", "ass"])
+        );
+        assert_eq!(parse_table(MUXERS), set(&["matroska", "mp4", "webm"]));
+        assert!(parse_table("").is_empty());
+    }
+
+    #[test]
+    fn filters_ignore_the_legend() {
+        assert_eq!(
+            parse_filters(FILTERS),
+            set(&["concat", "scale", "xfade", "volume", "anullsrc"])
+        );
+    }
+
+    #[test]
+    fn hwaccels_are_listed_after_the_header() {
+        assert_eq!(parse_hwaccels(HWACCELS), set(&["vdpau", "cuda", "vaapi"]));
+        assert!(parse_hwaccels("Hardware acceleration methods:\n\n").is_empty());
+    }
+
+    #[test]
+    fn export_plan_uses_fallbacks_and_reports_missing_parts() {
+        let caps = captured();
+        let features = ExportFeatures {
+            format: "mp4",
+            transition: "dissolve",
+            subtitles: false,
+            volume: true,
+            scale: true,
+        };
+        let codecs = plan_export(&caps, &features).unwrap();
+        assert_eq!((codecs.video.as_str(), codecs.audio.as_str()), ("libopenh264", "aac"));
+        assert!(codecs.fallback && !codecs.supports_preset());
+
+        let subtitles = ExportFeatures { subtitles: true, ..features.clone() };
+        assert!(matches!(
+            plan_export(&caps, &subtitles),
+            Err(AppError::MissingCapability { name, .. }) if name == "subtitles"
+        ));
+        let webm = ExportFeatures { format: "webm", ..features };
+        assert!(matches!(
+            plan_export(&caps, &webm),
+            Err(AppError::MissingCapability { kind, .. }) if kind == "encoder"
+        ));
+    }
+}
@@ -0,0 +1,318 @@
+//! 流水线检查点
+//!
+//! 每个（项目, 步骤）的检查点保存为 `<app_data>/checkpoints/<项目 ID>/<步骤 ID>.json`，
+//! 记录完成状态、步骤数据、首次与最近保存时间。与 webview 的 localStorage 不同，
+//! 检查点按项目隔离、不受浏览器配额限制，清理 webview 缓存后仍可从中断处继续。
+
+use crate::error::{AppError, AppResult};
+use crate::fs_utils;
+use crate::project;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+/// 检查点所在的应用数据子目录
+const CHECKPOINTS_DIR: &str = "checkpoints";
+
+// 一个步骤的检查点
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+    pub project_id: String,
+    pub step_id: String,
+    pub completed: bool,
+    pub data: Value,
+    /// 首次保存时间（毫秒时间戳）
+    pub created_at: u64,
+    /// 最近保存时间（毫秒时间戳）
+    pub updated_at: u64,
+}
+
+// 检查点概要，不含步骤数据
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointInfo {
+    pub project_id: String,
+    pub step_id: String,
+    pub completed: bool,
+    pub created_at: u64,
+    pub updated_at: u64,
+    /// 文件大小（字节）
+    #[serde(default)]
+    pub size: u64,
+}
+
+// 检查点列表及总占用
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointList {
+    pub checkpoints: Vec<CheckpointInfo>,
+    pub total_bytes: u64,
+}
+
+// 清理结果
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClearCheckpointsResult {
+    pub removed: u64,
+    pub freed_bytes: u64,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+/// Checkpoints stored as one JSON file per step under a per-project directory.
+pub struct CheckpointStore {
+    root: PathBuf,
+}
+
+impl CheckpointStore {
+    pub fn new(root: impl Into<PathBuf>) -> CheckpointStore {
+        CheckpointStore { root: root.into() }
+    }
+
+    fn project_dir(&self, project_id: &str) -> AppResult<PathBuf> {
+        project::validate_project_id(project_id)?;
+        fs_utils::resolve_within(&self.root, project_id)
+    }
+
+    fn step_path(&self, project_id: &str, step_id: &str) -> AppResult<PathBuf> {
+        project::validate_id("stepId", step_id)?;
+        fs_utils::resolve_within(&self.project_dir(project_id)?, &format!("{}.json", step_id))
+    }
+
+    /// Read a checkpoint's metadata without building its data value.
+    fn read_info(path: &Path) -> Option<CheckpointInfo> {
+        let size = fs::metadata(path).ok()?.len();
+        let content = fs::read(path).ok()?;
+        match serde_json::from_slice::<CheckpointInfo>(&content) {
+            Ok(info) => Some(CheckpointInfo { size, ..info }),
+            Err(e) => {
+                warn!("跳过损坏的检查点: {} ({})", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Save a step's checkpoint, keeping the original creation time when overwriting.
+    pub fn save(&self, project_id: &str, step_id: &str, data: Value, completed: bool) -> AppResult<CheckpointInfo> {
+        let path = self.step_path(project_id, step_id)?;
+        let now = now_millis();
+        let created_at = Self::read_info(&path).map(|info| info.created_at).unwrap_or(now);
+        let checkpoint = Checkpoint {
+            project_id: project_id.to_string(),
+            step_id: step_id.to_string(),
+            completed,
+            data,
+            created_at,
+            updated_at: now,
+        };
+        let content = serde_json::to_vec(&checkpoint)?;
+        fs_utils::atomic_write(&path, &content)?;
+        Ok(CheckpointInfo {
+            project_id: checkpoint.project_id,
+            step_id: checkpoint.step_id,
+            completed,
+            created_at,
+            updated_at: now,
+            size: content.len() as u64,
+        })
+    }
+
+    /// Load a step's checkpoint. Missing or unreadable checkpoints yield `None`.
+    pub fn load(&self, project_id: &str, step_id: &str) -> AppResult<Option<Checkpoint>> {
+        let path = self.step_path(project_id, step_id)?;
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let content = fs::read(&path).map_err(|e| AppError::io("read_file", Some(&path), e))?;
+        match serde_json::from_slice(&content) {
+            Ok(checkpoint) => Ok(Some(checkpoint)),
+            Err(e) => {
+                warn!("检查点已损坏，按不存在处理: {} ({})", path.display(), e);
+                Ok(None)
+            }
+        }
+    }
+
+    fn project_ids(&self) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(&self.root) else { return Vec::new() };
+        entries
+            .flatten()
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+            .filter_map(|e| e.file_name().to_str().map(str::to_string))
+            .filter(|id| project::validate_project_id(id).is_ok())
+            .collect()
+    }
+
+    fn step_files(&self, project_id: &str) -> AppResult<Vec<PathBuf>> {
+        let dir = self.project_dir(project_id)?;
+        let Ok(entries) = fs::read_dir(&dir) else { return Ok(Vec::new()) };
+        Ok(entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "json"))
+            .collect())
+    }
+
+    /// Checkpoints for one project, or for every project, oldest update first.
+    pub fn list(&self, project_id: Option<&str>) -> AppResult<CheckpointList> {
+        let project_ids = match project_id {
+            Some(id) => vec![id.to_string()],
+            None => self.project_ids(),
+        };
+        let mut checkpoints = Vec::new();
+        for id in &project_ids {
+            checkpoints.extend(self.step_files(id)?.iter().filter_map(|p| Self::read_info(p)));
+        }
+        checkpoints.sort_by_key(|c| c.updated_at);
+        Ok(CheckpointList { total_bytes: checkpoints.iter().map(|c| c.size).sum(), checkpoints })
+    }
+
+    /// Remove one step's checkpoint, or all of a project's checkpoints when `step_id` is `None`.
+    pub fn clear(&self, project_id: &str, step_id: Option<&str>) -> AppResult<ClearCheckpointsResult> {
+        let files = match step_id {
+            Some(step_id) => vec![self.step_path(project_id, step_id)?],
+            None => self.step_files(project_id)?,
+        };
+        let mut result = ClearCheckpointsResult::default();
+        for path in files.iter().filter(|p| p.is_file()) {
+            let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            fs::remove_file(path).map_err(|e| AppError::io("remove_file", Some(path), e))?;
+            result.removed += 1;
+            result.freed_bytes += size;
+        }
+        if step_id.is_none() {
+            let _ = fs::remove_dir(self.project_dir(project_id)?);
+        }
+        Ok(result)
+    }
+}
+
+pub(crate) fn store(app_handle: &AppHandle) -> AppResult<CheckpointStore> {
+    Ok(CheckpointStore::new(project::app_data_dir(app_handle)?.join(CHECKPOINTS_DIR)))
+}
+
+/// 保存步骤检查点
+#[tauri::command]
+pub fn save_checkpoint(
+    project_id: String,
+    step_id: String,
+    data: Value,
+    completed: Option<bool>,
+    app_handle: AppHandle,
+) -> AppResult<CheckpointInfo> {
+    let info = store(&app_handle)?.save(&project_id, &step_id, data, completed.unwrap_or(true))?;
+    info!("检查点已保存: {}/{} ({} 字节)", project_id, step_id, info.size);
+    Ok(info)
+}
+
+/// 读取步骤检查点，不存在时返回空
+#[tauri::command]
+pub fn load_checkpoint(project_id: String, step_id: String, app_handle: AppHandle) -> AppResult<Option<Checkpoint>> {
+    store(&app_handle)?.load(&project_id, &step_id)
+}
+
+/// 列出检查点及磁盘占用，`project_id` 为空时列出所有项目
+#[tauri::command]
+pub fn list_checkpoints(project_id: Option<String>, app_handle: AppHandle) -> AppResult<CheckpointList> {
+    store(&app_handle)?.list(project_id.as_deref())
+}
+
+/// 清除检查点，`step_id` 为空时清除项目的全部检查点
+#[tauri::command]
+pub fn clear_checkpoints(
+    project_id: String,
+    step_id: Option<String>,
+    app_handle: AppHandle,
+) -> AppResult<ClearCheckpointsResult> {
+    let result = store(&app_handle)?.clear(&project_id, step_id.as_deref())?;
+    info!("已清除 {} 个检查点: {}", result.removed, project_id);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use serde_json::json;
+
+    #[test]
+    fn save_and_load_per_project_and_step() {
+        let dir = TempDir::new();
+        let store = CheckpointStore::new(&dir.0);
+        assert_eq!(store.load("p1", "script").unwrap(), None);
+
+        let first = store.save("p1", "script", json!({ "chapters": 3 }), true).unwrap();
+        store.save("p2", "script", json!({ "chapters": 9 }), false).unwrap();
+
+        let loaded = store.load("p1", "script").unwrap().unwrap();
+        assert_eq!(loaded.data, json!({ "chapters": 3 }));
+        assert!(loaded.completed);
+        assert_eq!(loaded.created_at, first.created_at);
+        assert!(!store.load("p2", "script").unwrap().unwrap().completed);
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = store.save("p1", "script", json!({ "chapters": 4 }), true).unwrap();
+        assert_eq!(second.created_at, first.created_at);
+        assert!(second.updated_at > first.updated_at);
+    }
+
+    #[test]
+    fn list_reports_sizes_and_skips_corrupt_files() {
+        let dir = TempDir::new();
+        let store = CheckpointStore::new(&dir.0);
+        store.save("p1", "script", json!("a"), true).unwrap();
+        store.save("p1", "voice", json!([1, 2, 3]), true).unwrap();
+        store.save("p2", "render", json!(null), false).unwrap();
+        fs::write(dir.0.join("p1").join("broken.json"), "{not json").unwrap();
+
+        let p1 = store.list(Some("p1")).unwrap();
+        assert_eq!(p1.checkpoints.len(), 2);
+        let on_disk: u64 = ["script", "voice"]
+            .iter()
+            .map(|s| fs::metadata(dir.0.join("p1").join(format!("{}.json", s))).unwrap().len())
+            .sum();
+        assert_eq!(p1.total_bytes, on_disk);
+
+        assert_eq!(store.list(None).unwrap().checkpoints.len(), 3);
+        assert_eq!(store.load("p1", "broken").unwrap(), None);
+    }
+
+    #[test]
+    fn clear_one_step_or_whole_project() {
+        let dir = TempDir::new();
+        let store = CheckpointStore::new(&dir.0);
+        store.save("p1", "script", json!(1), true).unwrap();
+        store.save("p1", "voice", json!(2), true).unwrap();
+
+        let one = store.clear("p1", Some("script")).unwrap();
+        assert_eq!(one.removed, 1);
+        assert!(one.freed_bytes > 0);
+        assert_eq!(store.load("p1", "script").unwrap(), None);
+        assert!(store.load("p1", "voice").unwrap().is_some());
+
+        assert_eq!(store.clear("p1", None).unwrap().removed, 1);
+        assert!(!dir.0.join("p1").exists());
+        assert_eq!(store.clear("p1", None).unwrap(), ClearCheckpointsResult::default());
+    }
+
+    #[test]
+    fn ids_cannot_escape_the_store() {
+        let dir = TempDir::new();
+        let store = CheckpointStore::new(dir.0.join("store"));
+        for (project_id, step_id) in [("..", "x"), ("p1", ".."), ("p1", "../../x"), ("/tmp", "x"), ("p1", "a/b")] {
+            assert!(
+                matches!(store.save(project_id, step_id, json!(1), true), Err(AppError::InvalidArgument { .. })),
+                "{}/{}",
+                project_id,
+                step_id
+            );
+        }
+    }
+}
@@ -0,0 +1,349 @@
+//! ffmpeg 版本与编译配置兼容性检查
+//!
+//! 从 `ffmpeg -version` 解析版本号和 `--enable-*` 编译选项，与下方按功能列出的
+//! 最低要求逐项比对，生成首次运行向导可直接展示的兼容性报告。
+
+use crate::capabilities::{self, Capabilities};
+use crate::error::AppResult;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt;
+
+// 解析后的 ffmpeg 版本号
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FfmpegVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl FfmpegVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> FfmpegVersion {
+        FfmpegVersion { major, minor, patch }
+    }
+
+    /// Parse the version token of `ffmpeg -version`, e.g. `6.1.1-3ubuntu5`, `n7.0.2` or `4.4`.
+    /// Git snapshots (`N-112345-g…`, `2024-03-11-git-…`) have no release number and yield `None`.
+    pub fn parse(token: &str) -> Option<FfmpegVersion> {
+        let token = token.strip_prefix('n').unwrap_or(token);
+        let numeric: String = token
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .collect();
+        let mut parts = numeric.split('.').map(|p| p.parse::<u32>().ok());
+        let major = parts.next()??;
+        // 日期形式的快照版本（如 2024-03-11）不是发布版本号
+        if major > 100 {
+            return None;
+        }
+        let minor = parts.next().flatten().unwrap_or(0);
+        let patch = parts.next().flatten().unwrap_or(0);
+        Some(FfmpegVersion::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for FfmpegVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// The version token from the first line of `ffmpeg -version` (`ffmpeg version <token> Copyright …`).
+pub fn parse_version_token(output: &str) -> Option<String> {
+    let first = output.lines().next()?;
+    let mut words = first.split_whitespace();
+    words.find(|w| *w == "version")?;
+    words.next().map(str::to_string)
+}
+
+/// `--enable-*` flags from the `configuration:` line of `ffmpeg -version`.
+pub fn parse_configure_flags(output: &str) -> BTreeSet<String> {
+    output
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("configuration:"))
+        .map(|config| {
+            config
+                .split_whitespace()
+                .filter(|f| f.starts_with("--enable-"))
+                .map(|f| f.split('=').next().unwrap_or(f).to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Minimum requirements for one user-facing feature.
+struct Requirement {
+    feature: &'static str,
+    /// 缺少时应用无法正常导出
+    required: bool,
+    min_version: Option<FfmpegVersion>,
+    configure_flags: &'static [&'static str],
+    filters: &'static [&'static str],
+    /// 每组至少需要其中一个编码器
+    encoders: &'static [&'static [&'static str]],
+}
+
+const REQUIREMENTS: &[Requirement] = &[
+    Requirement {
+        feature: "h264_export",
+        required: true,
+        min_version: None,
+        configure_flags: &[],
+        filters: &["scale"],
+        encoders: &[
+            &["libx264", "libopenh264", "h264_videotoolbox", "h264_mf", "mpeg4"],
+            &["aac", "libfdk_aac"],
+        ],
+    },
+    Requirement {
+        feature: "webm_export",
+        required: false,
+        min_version: None,
+        configure_flags: &["--enable-libvpx"],
+        filters: &[],
+        encoders: &[&["libvpx-vp9", "libvpx"], &["libopus", "libvorbis"]],
+    },
+    Requirement {
+        feature: "subtitles",
+        required: false,
+        min_version: None,
+        configure_flags: &["--enable-libass"],
+        filters: &["subtitles"],
+        encoders: &[],
+    },
+    Requirement {
+        feature: "xfade_transitions",
+        required: false,
+        min_version: Some(FfmpegVersion::new(4, 3, 0)),
+        configure_flags: &[],
+        filters: &["xfade"],
+        encoders: &[],
+    },
+    Requirement {
+        feature: "scene_detection",
+        required: false,
+        min_version: Some(FfmpegVersion::new(4, 4, 0)),
+        configure_flags: &[],
+        filters: &["scdet"],
+        encoders: &[],
+    },
+    Requirement {
+        feature: "loudnorm_json",
+        required: false,
+        min_version: Some(FfmpegVersion::new(3, 1, 0)),
+        configure_flags: &[],
+        filters: &["loudnorm"],
+        encoders: &[],
+    },
+];
+
+// 单项不满足的原因
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CompatIssue {
+    #[serde(rename_all = "camelCase")]
+    VersionTooOld { required: String, found: String },
+    #[serde(rename_all = "camelCase")]
+    MissingConfigureFlag { flag: String },
+    #[serde(rename_all = "camelCase")]
+    MissingFilter { name: String },
+    #[serde(rename_all = "camelCase")]
+    MissingEncoder { candidates: Vec<String> },
+}
+
+// 单项功能的检查结果
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureStatus {
+    pub feature: String,
+    pub required: bool,
+    pub supported: bool,
+    pub min_version: Option<String>,
+    pub issues: Vec<CompatIssue>,
+}
+
+// 兼容性报告
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CompatibilityReport {
+    /// `ffmpeg -version` 中的原始版本字符串
+    pub version_string: Option<String>,
+    /// 解析出的发布版本；git 快照为 `None`，此时跳过版本检查
+    pub version: Option<FfmpegVersion>,
+    pub configure_flags: Vec<String>,
+    /// 所有必需功能均可用
+    pub ok: bool,
+    pub features: Vec<FeatureStatus>,
+}
+
+fn check(req: &Requirement, caps: &Capabilities, version: Option<FfmpegVersion>) -> FeatureStatus {
+    let mut issues = Vec::new();
+
+    if let (Some(required), Some(found)) = (req.min_version, version) {
+        if found < required {
+            issues.push(CompatIssue::VersionTooOld {
+                required: required.to_string(),
+                found: found.to_string(),
+            });
+        }
+    }
+    // 部分发行版/静态构建不输出 configuration 行，此时仅依据实际组件判断
+    if !caps.configure_flags.is_empty() {
+        for flag in req.configure_flags {
+            if !caps.configure_flags.contains(*flag) {
+                issues.push(CompatIssue::MissingConfigureFlag { flag: flag.to_string() });
+            }
+        }
+    }
+    for filter in req.filters {
+        if !caps.filters.contains(*filter) {
+            issues.push(CompatIssue::MissingFilter { name: filter.to_string() });
+        }
+    }
+    for group in req.encoders {
+        if !group.iter().any(|e| caps.encoders.contains(*e)) {
+            issues.push(CompatIssue::MissingEncoder {
+                candidates: group.iter().map(|e| e.to_string()).collect(),
+            });
+        }
+    }
+
+    FeatureStatus {
+        feature: req.feature.to_string(),
+        required: req.required,
+        supported: issues.is_empty(),
+        min_version: req.min_version.map(|v| v.to_string()),
+        issues,
+    }
+}
+
+/// Compare the probed ffmpeg against the requirement table.
+pub fn evaluate(caps: &Capabilities) -> CompatibilityReport {
+    let version = caps.version.as_deref().and_then(FfmpegVersion::parse);
+    let features: Vec<FeatureStatus> = REQUIREMENTS.iter().map(|r| check(r, caps, version)).collect();
+    CompatibilityReport {
+        version_string: caps.version.clone(),
+        version,
+        configure_flags: caps.configure_flags.iter().cloned().collect(),
+        ok: features.iter().all(|f| f.supported || !f.required),
+        features,
+    }
+}
+
+/// Build the report for the currently resolved ffmpeg.
+pub fn report() -> AppResult<CompatibilityReport> {
+    Ok(evaluate(&capabilities::probe()?))
+}
+
+/// 获取 ffmpeg 兼容性报告（版本、编译选项与各功能的可用性）
+#[tauri::command]
+pub fn get_ffmpeg_compatibility(refresh: Option<bool>) -> AppResult<CompatibilityReport> {
+    let caps = if refresh.unwrap_or(false) {
+        capabilities::refresh()?
+    } else {
+        capabilities::probe()?
+    };
+    Ok(evaluate(&caps))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERSION_OUTPUT: &str = "\
+ffmpeg version 4.4.2-0ubuntu0.22.04.1 Copyright (c) 2000-2021 the FFmpeg developers
+built with gcc 11 (Ubuntu 11.2.0-19ubuntu1)
+configuration: --prefix=/usr --extra-version=0ubuntu0.22.04.1 --toolchain=hardened --enable-gpl --enable-libass --enable-libvpx --enable-libx264 --enable-opencl=auto --disable-stripping
+libavutil      56. 70.100 / 56. 70.100
+";
+
+    fn version(token: &str) -> Option<FfmpegVersion> {
+        FfmpegVersion::parse(token)
+    }
+
+    /// Capabilities of `version` that have every filter and encoder the table mentions.
+    fn full_caps(version: &str) -> Capabilities {
+        let names = |f: fn(&Requirement) -> Vec<&'static str>| {
+            REQUIREMENTS.iter().flat_map(f).map(str::to_string).collect()
+        };
+        Capabilities {
+            version: Some(version.to_string()),
+            filters: names(|r| r.filters.to_vec()),
+            encoders: names(|r| r.encoders.concat()),
+            ..Default::default()
+        }
+    }
+
+    fn status<'a>(report: &'a CompatibilityReport, feature: &str) -> &'a FeatureStatus {
+        report.features.iter().find(|f| f.feature == feature).unwrap()
+    }
+
+    #[test]
+    fn parses_release_and_distro_versions() {
+        assert_eq!(version("n6.1"), Some(FfmpegVersion::new(6, 1, 0)));
+        assert_eq!(version("4.4.2-0ubuntu0.22.04.1"), Some(FfmpegVersion::new(4, 4, 2)));
+        assert_eq!(version("7.0.2-static"), Some(FfmpegVersion::new(7, 0, 2)));
+        assert_eq!(version("5"), Some(FfmpegVersion::new(5, 0, 0)));
+    }
+
+    #[test]
+    fn git_snapshots_have_no_version() {
+        assert_eq!(version("N-112345-g1b2c3d4e5f"), None);
+        assert_eq!(version("2024-03-11-git-abc123"), None);
+        assert_eq!(version(""), None);
+    }
+
+    #[test]
+    fn parses_version_token_and_configure_flags() {
+        assert_eq!(parse_version_token(VERSION_OUTPUT).as_deref(), Some("4.4.2-0ubuntu0.22.04.1"));
+        assert_eq!(parse_version_token("garbage"), None);
+
+        let flags: Vec<String> = parse_configure_flags(VERSION_OUTPUT).into_iter().collect();
+        assert_eq!(
+            flags,
+            ["--enable-gpl", "--enable-libass", "--enable-libvpx", "--enable-libx264", "--enable-opencl"]
+        );
+        assert!(parse_configure_flags("ffmpeg version n6.1\n").is_empty());
+    }
+
+    #[test]
+    fn minimum_versions_gate_features() {
+        let cases = [
+            ("xfade_transitions", "4.2.9", "4.3"),
+            ("scene_detection", "4.3.6", "n4.4"),
+            ("loudnorm_json", "3.0.2", "3.1.0"),
+        ];
+        for (feature, too_old, enough) in cases {
+            let old = evaluate(&full_caps(too_old));
+            assert!(
+                matches!(status(&old, feature).issues.as_slice(), [CompatIssue::VersionTooOld { .. }]),
+                "{} on {}",
+                feature,
+                too_old
+            );
+            assert!(status(&evaluate(&full_caps(enough)), feature).supported, "{} on {}", feature, enough);
+        }
+        // 快照版本无法比较，只看实际组件
+        let snapshot = evaluate(&full_caps("N-112345-g1b2c3d4e5f"));
+        assert!(snapshot.features.iter().all(|f| f.supported));
+        assert!(snapshot.ok);
+    }
+
+    #[test]
+    fn configure_flags_are_only_checked_when_reported() {
+        let mut caps = full_caps("6.1");
+        assert!(status(&evaluate(&caps), "subtitles").supported);
+
+        caps.configure_flags = parse_configure_flags(VERSION_OUTPUT);
+        caps.configure_flags.remove("--enable-libass");
+        let report = evaluate(&caps);
+        assert_eq!(
+            status(&report, "subtitles").issues,
+            [CompatIssue::MissingConfigureFlag { flag: "--enable-libass".to_string() }]
+        );
+        assert!(report.ok, "subtitles are optional");
+
+        caps.encoders.clear();
+        assert!(!evaluate(&caps).ok);
+    }
+}
@@ -0,0 +1,359 @@
+//! 统一错误类型
+//!
+//! 所有命令返回 [`AppError`]。序列化给前端的结构为
+//! `{ code, params, message }`：`code` 是稳定的错误码，前端据此分支处理；
+//! `params` 为结构化参数；`message` 按 `AppSettings.language` 本地化渲染。
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
+use std::fmt;
+use std::sync::RwLock;
+
+/// 错误消息使用的语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    ZhCn,
+    EnUs,
+}
+
+impl Language {
+    /// Map an `AppSettings.language` value to a supported language, defaulting to Chinese.
+    pub fn from_tag(tag: &str) -> Language {
+        if tag.to_ascii_lowercase().starts_with("en") {
+            Language::EnUs
+        } else {
+            Language::ZhCn
+        }
+    }
+}
+
+static CURRENT_LANGUAGE: RwLock<Language> = RwLock::new(Language::ZhCn);
+
+/// Set the language used when rendering error messages.
+pub fn set_language(tag: &str) {
+    if let Ok(mut lang) = CURRENT_LANGUAGE.write() {
+        *lang = Language::from_tag(tag);
+    }
+}
+
+pub fn current_language() -> Language {
+    CURRENT_LANGUAGE
+        .read()
+        .map(|l| *l)
+        .unwrap_or(Language::ZhCn)
+}
+
+/// Localized label for the `kind` key of [`AppError::MissingCapability`].
+fn capability_kind_label(kind: &str, lang: Language) -> &str {
+    let (zh, en) = match kind {
+        "encoder" => ("编码器", "encoder"),
+        "filter" => ("滤镜", "filter"),
+        "muxer" => ("封装格式", "muxer"),
+        _ => return kind,
+    };
+    match lang {
+        Language::ZhCn => zh,
+        Language::EnUs => en,
+    }
+}
+
+/// Localized label for the `op` key of [`AppError::Io`].
+fn io_op_label(op: &str, lang: Language) -> &str {
+    let (zh, en) = match op {
+        "create_dir" => ("创建目录", "Creating directory"),
+        "read_dir" => ("读取目录", "Reading directory"),
+        "create_file" => ("创建文件", "Creating file"),
+        "read_file" => ("读取文件", "Reading file"),
+        "write_file" => ("写入文件", "Writing file"),
+        "remove_file" => ("删除文件", "Removing file"),
+        "rename" => ("重命名文件", "Renaming file"),
+        "sync" => ("同步文件", "Syncing file"),
+        "spawn" => ("启动进程", "Starting process"),
+        "open" => ("打开文件", "Opening file"),
+        "resolve_dir" => ("获取应用目录", "Resolving app directory"),
+        _ => return op,
+    };
+    match lang {
+        Language::ZhCn => zh,
+        Language::EnUs => en,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppError {
+    /// ffmpeg/ffprobe 不可用
+    FfmpegMissing,
+    /// ffprobe 无法读取媒体信息，`log_id` 指向完整的任务日志
+    ProbeFailed { path: String, reason: String, log_id: Option<String> },
+    /// 片段时间范围无效
+    InvalidSegment { index: Option<usize>, start: f64, end: f64 },
+    /// 没有可处理的片段
+    NoSegments,
+    /// 路径不在允许的目录内
+    PathNotAllowed { path: String },
+    /// 路径不存在或无法解析
+    InvalidPath { path: String, reason: String },
+    /// 文件或资源不存在
+    NotFound { path: String },
+    /// 编码/处理阶段失败，`log_id` 指向完整的任务日志
+    EncodeFailed {
+        stage: String,
+        exit_code: Option<i32>,
+        stderr_tail: String,
+        log_id: Option<String>,
+    },
+    /// 当前 ffmpeg 缺少 `feature` 所需的组件，`kind` 为 `encoder`/`filter`/`muxer`
+    MissingCapability { feature: String, kind: String, name: String },
+    /// 任务被取消
+    Cancelled,
+    /// 文件系统错误，`op` 为稳定的操作键（如 `write_file`）
+    Io { op: String, path: Option<String>, reason: String },
+    /// JSON 等数据格式错误
+    Serialization { reason: String },
+    /// 参数不合法
+    InvalidArgument { field: String, reason: String },
+    /// 窗口不存在
+    WindowNotFound { label: String },
+    /// 快捷键 ID 已存在
+    ShortcutExists { id: String },
+    /// 快捷键按键已被占用，`holder` 为占用它的快捷键 ID；被其他程序占用时为空
+    ShortcutConflict { key: String, holder: Option<String> },
+    /// 项目文件不符合结构定义，`path` 为出错位置的 JSON 路径（如 `assets[2].duration`）
+    InvalidProject { path: String, reason: String },
+    /// 项目文件由更新版本的应用创建
+    UnsupportedSchemaVersion { found: u64, supported: u32 },
+    /// 项目包损坏或校验失败，`path` 为包内出错的条目
+    InvalidBundle { path: String, reason: String },
+    /// 同 ID 的项目已存在
+    ProjectExists { id: String },
+    /// 其他内部错误
+    Internal { reason: String },
+}
+
+impl AppError {
+    pub fn io(op: &str, path: Option<&std::path::Path>, err: impl fmt::Display) -> AppError {
+        AppError::Io {
+            op: op.to_string(),
+            path: path.map(|p| p.to_string_lossy().to_string()),
+            reason: err.to_string(),
+        }
+    }
+
+    pub fn invalid_argument(field: &str, reason: impl Into<String>) -> AppError {
+        AppError::InvalidArgument {
+            field: field.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn internal(reason: impl fmt::Display) -> AppError {
+        AppError::Internal {
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Stable error code sent to the frontend.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::FfmpegMissing => "FfmpegMissing",
+            AppError::ProbeFailed { .. } => "ProbeFailed",
+            AppError::InvalidSegment { .. } => "InvalidSegment",
+            AppError::NoSegments => "NoSegments",
+            AppError::PathNotAllowed { .. } => "PathNotAllowed",
+            AppError::InvalidPath { .. } => "InvalidPath",
+            AppError::NotFound { .. } => "NotFound",
+            AppError::EncodeFailed { .. } => "EncodeFailed",
+            AppError::MissingCapability { .. } => "MissingCapability",
+            AppError::Cancelled => "Cancelled",
+            AppError::Io { .. } => "Io",
+            AppError::Serialization { .. } => "Serialization",
+            AppError::InvalidArgument { .. } => "InvalidArgument",
+            AppError::WindowNotFound { .. } => "WindowNotFound",
+            AppError::ShortcutExists { .. } => "ShortcutExists",
+            AppError::ShortcutConflict { .. } => "ShortcutConflict",
+            AppError::InvalidProject { .. } => "InvalidProject",
+            AppError::UnsupportedSchemaVersion { .. } => "UnsupportedSchemaVersion",
+            AppError::InvalidBundle { .. } => "InvalidBundle",
+            AppError::ProjectExists { .. } => "ProjectExists",
+            AppError::Internal { .. } => "Internal",
+        }
+    }
+
+    /// Structured parameters for the error, used by the frontend for its own messages.
+    pub fn params(&self) -> Value {
+        match self {
+            AppError::FfmpegMissing | AppError::NoSegments | AppError::Cancelled => json!({}),
+            AppError::ProbeFailed { path, reason, log_id } => {
+                json!({ "path": path, "reason": reason, "logId": log_id })
+            }
+            AppError::InvalidSegment { index, start, end } => {
+                json!({ "index": index, "start": start, "end": end })
+            }
+            AppError::PathNotAllowed { path } | AppError::NotFound { path } => json!({ "path": path }),
+            AppError::InvalidPath { path, reason } => json!({ "path": path, "reason": reason }),
+            AppError::EncodeFailed { stage, exit_code, stderr_tail, log_id } => json!({
+                "stage": stage,
+                "exitCode": exit_code,
+                "stderrTail": stderr_tail,
+                "logId": log_id,
+            }),
+            AppError::MissingCapability { feature, kind, name } => {
+                json!({ "feature": feature, "kind": kind, "name": name })
+            }
+            AppError::Io { op, path, reason } => json!({ "op": op, "path": path, "reason": reason }),
+            AppError::Serialization { reason } | AppError::Internal { reason } => {
+                json!({ "reason": reason })
+            }
+            AppError::InvalidArgument { field, reason } => json!({ "field": field, "reason": reason }),
+            AppError::WindowNotFound { label } => json!({ "label": label }),
+            AppError::ShortcutExists { id } | AppError::ProjectExists { id } => json!({ "id": id }),
+            AppError::ShortcutConflict { key, holder } => json!({ "key": key, "holder": holder }),
+            AppError::InvalidProject { path, reason } | AppError::InvalidBundle { path, reason } => {
+                json!({ "path": path, "reason": reason })
+            }
+            AppError::UnsupportedSchemaVersion { found, supported } => {
+                json!({ "found": found, "supported": supported })
+            }
+        }
+    }
+
+    /// Render a human-readable message in the given language.
+    pub fn message(&self, lang: Language) -> String {
+        match lang {
+            Language::ZhCn => self.message_zh(),
+            Language::EnUs => self.message_en(),
+        }
+    }
+
+    fn message_zh(&self) -> String {
+        match self {
+            AppError::FfmpegMissing => "未安装FFmpeg，请先安装FFmpeg后再试".to_string(),
+            AppError::ProbeFailed { path, reason, .. } => format!("无法读取媒体信息 {}: {}", path, reason),
+            AppError::InvalidSegment { index: Some(i), start, end } => {
+                format!("第 {} 个片段时间范围无效: {} - {}", i + 1, start, end)
+            }
+            AppError::InvalidSegment { index: None, start, end } => {
+                format!("无效的片段时间范围: {} - {}", start, end)
+            }
+            AppError::NoSegments => "没有提供有效的片段信息".to_string(),
+            AppError::PathNotAllowed { path } => format!("不允许访问该路径: {}", path),
+            AppError::InvalidPath { path, reason } => format!("路径无效 {}: {}", path, reason),
+            AppError::NotFound { path } => format!("文件不存在: {}", path),
+            AppError::EncodeFailed { stage, exit_code, stderr_tail, .. } => match exit_code {
+                Some(code) => format!("FFmpeg 在 {} 阶段失败 (退出码 {}): {}", stage, code, stderr_tail),
+                None => format!("FFmpeg 在 {} 阶段失败: {}", stage, stderr_tail),
+            },
+            AppError::MissingCapability { feature, kind, name } => format!(
+                "当前 FFmpeg 不支持 {}：缺少{} {}",
+                feature, capability_kind_label(kind, Language::ZhCn), name
+            ),
+            AppError::Cancelled => "任务已取消".to_string(),
+            AppError::Io { op, path: Some(path), reason } => {
+                format!("{}失败 {}: {}", io_op_label(op, Language::ZhCn), path, reason)
+            }
+            AppError::Io { op, path: None, reason } => {
+                format!("{}失败: {}", io_op_label(op, Language::ZhCn), reason)
+            }
+            AppError::Serialization { reason } => format!("数据格式错误: {}", reason),
+            AppError::InvalidArgument { field, reason } => format!("参数 {} 无效: {}", field, reason),
+            AppError::WindowNotFound { label } => format!("未找到窗口: {}", label),
+            AppError::ShortcutExists { id } => format!("快捷键 ID {} 已存在", id),
+            AppError::ShortcutConflict { key, holder: Some(holder) } => {
+                format!("快捷键 {} 已被 {} 使用", key, holder)
+            }
+            AppError::ShortcutConflict { key, holder: None } => format!("快捷键 {} 已被其他程序占用", key),
+            AppError::InvalidProject { path, reason } => format!("项目文件无效（{}）: {}", path, reason),
+            AppError::UnsupportedSchemaVersion { found, supported } => format!(
+                "项目文件版本 {} 高于当前支持的版本 {}，请升级应用后再打开", found, supported
+            ),
+            AppError::InvalidBundle { path, reason } => format!("项目包无效（{}）: {}", path, reason),
+            AppError::ProjectExists { id } => format!("项目 {} 已存在", id),
+            AppError::Internal { reason } => format!("内部错误: {}", reason),
+        }
+    }
+
+    fn message_en(&self) -> String {
+        match self {
+            AppError::FfmpegMissing => "FFmpeg is not installed. Please install FFmpeg and try again".to_string(),
+            AppError::ProbeFailed { path, reason, .. } => format!("Failed to read media info for {}: {}", path, reason),
+            AppError::InvalidSegment { index: Some(i), start, end } => {
+                format!("Segment {} has an invalid time range: {} - {}", i + 1, start, end)
+            }
+            AppError::InvalidSegment { index: None, start, end } => {
+                format!("Invalid segment time range: {} - {}", start, end)
+            }
+            AppError::NoSegments => "No valid segments were provided".to_string(),
+            AppError::PathNotAllowed { path } => format!("Access to this path is not allowed: {}", path),
+            AppError::InvalidPath { path, reason } => format!("Invalid path {}: {}", path, reason),
+            AppError::NotFound { path } => format!("File not found: {}", path),
+            AppError::EncodeFailed { stage, exit_code, stderr_tail, .. } => match exit_code {
+                Some(code) => format!("FFmpeg failed during {} (exit code {}): {}", stage, code, stderr_tail),
+                None => format!("FFmpeg failed during {}: {}", stage, stderr_tail),
+            },
+            AppError::MissingCapability { feature, kind, name } => format!(
+                "The installed FFmpeg cannot do {}: missing {} {}",
+                feature, capability_kind_label(kind, Language::EnUs), name
+            ),
+            AppError::Cancelled => "The job was cancelled".to_string(),
+            AppError::Io { op, path: Some(path), reason } => {
+                format!("{} failed for {}: {}", io_op_label(op, Language::EnUs), path, reason)
+            }
+            AppError::Io { op, path: None, reason } => {
+                format!("{} failed: {}", io_op_label(op, Language::EnUs), reason)
+            }
+            AppError::Serialization { reason } => format!("Malformed data: {}", reason),
+            AppError::InvalidArgument { field, reason } => format!("Invalid {}: {}", field, reason),
+            AppError::WindowNotFound { label } => format!("Window not found: {}", label),
+            AppError::ShortcutExists { id } => format!("Shortcut ID {} already exists", id),
+            AppError::ShortcutConflict { key, holder: Some(holder) } => {
+                format!("Shortcut {} is already used by {}", key, holder)
+            }
+            AppError::ShortcutConflict { key, holder: None } => {
+                format!("Shortcut {} is already taken by another application", key)
+            }
+            AppError::InvalidProject { path, reason } => format!("Invalid project file at {}: {}", path, reason),
+            AppError::UnsupportedSchemaVersion { found, supported } => format!(
+                "Project schema version {} is newer than the supported version {}; please update the app",
+                found, supported
+            ),
+            AppError::InvalidBundle { path, reason } => format!("Invalid project bundle at {}: {}", path, reason),
+            AppError::ProjectExists { id } => format!("A project with ID {} already exists", id),
+            AppError::Internal { reason } => format!("Internal error: {}", reason),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message(current_language()))
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("params", &self.params())?;
+        state.serialize_field("message", &self.message(current_language()))?;
+        state.end()
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::Serialization {
+            reason: err.to_string(),
+        }
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(err: tauri::Error) -> Self {
+        AppError::internal(err)
+    }
+}
+
+pub type AppResult<T> = Result<T, AppError>;
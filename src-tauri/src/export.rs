@@ -0,0 +1,927 @@
+//! 导出命令：多片段剪辑、转场与片段预览
+//!
+//! 参数构造（画质档位、滤镜链、字幕文件、片段校验）都是纯函数；实际执行通过
+//! [`MediaBackend`]，命令使用 [`FfmpegBackend`]，测试使用记录调用的模拟后端。
+
+use crate::backend::{FfmpegBackend, MediaBackend};
+use crate::capabilities::{self, Capabilities, ExportCodecs};
+use crate::error::{AppError, AppResult};
+use crate::fs_utils::AtomicOutput;
+use crate::job_log;
+use crate::media_scope;
+use crate::temp_workspace::{self, TempSession};
+use crate::toolchain;
+use crate::tray;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// 视频剪辑片段结构
+#[derive(Deserialize, Debug, Clone)]
+pub struct VideoSegment {
+    pub start: f64,
+    pub end: f64,
+    #[serde(rename = "type")]
+    pub segment_type: Option<String>,
+    pub content: Option<String>,
+}
+
+// 视频剪辑参数
+#[derive(Deserialize, Debug, Clone)]
+pub struct CutVideoParams {
+    pub input_path: String,
+    pub output_path: String,
+    pub segments: Vec<VideoSegment>,
+    pub quality: Option<String>,
+    pub format: Option<String>,
+    pub transition: Option<String>,
+    pub transition_duration: Option<f64>,
+    pub volume: Option<f64>,
+    pub add_subtitles: Option<bool>,
+    /// 只返回执行计划，不运行 ffmpeg（由 `cut_video` 与命令行处理）
+    pub dry_run: Option<bool>,
+}
+
+// 预览片段参数
+#[derive(Deserialize, Debug)]
+pub struct PreviewParams {
+    pub input_path: String,
+    pub segment: VideoSegment,
+    pub transition: Option<String>,
+    pub transition_duration: Option<f64>,
+    pub volume: Option<f64>,
+    pub add_subtitles: Option<bool>,
+}
+
+// 画质档位对应的编码参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct QualityPreset {
+    /// 缩放目标，`None` 表示保持原分辨率
+    scale: Option<&'static str>,
+    bitrate: &'static str,
+    /// x264 的 `-preset`，其他编码器不使用
+    preset: Option<&'static str>,
+}
+
+/// Encoding settings for an output format and quality level; unknown qualities mean `medium`.
+fn quality_preset(format: &str, quality: &str) -> QualityPreset {
+    let quality = match quality {
+        "low" | "medium" | "high" | "ultra" => quality,
+        _ => "medium",
+    };
+    let scale = match quality {
+        "low" => Some("1280:720"),
+        "medium" => Some("1920:1080"),
+        _ => None,
+    };
+    let bitrate = match (format, quality) {
+        ("webm", "low") => "1M",
+        ("webm", "medium") => "3M",
+        ("webm", "high") => "6M",
+        ("webm", _) => "10M",
+        (_, "low") => "1.5M",
+        (_, "medium") => "4M",
+        (_, "high") => "8M",
+        _ => "15M",
+    };
+    let preset = match (format, quality) {
+        ("mp4" | "mov", "low" | "medium") => Some("fast"),
+        ("mp4" | "mov", _) => Some("slow"),
+        _ => None,
+    };
+    QualityPreset { scale, bitrate, preset }
+}
+
+/// Bitrate, preset and codec arguments for one encode.
+fn encode_args(preset: &QualityPreset, codecs: &ExportCodecs) -> Vec<String> {
+    let mut args = vec!["-b:v".to_string(), preset.bitrate.to_string()];
+    if let Some(p) = preset.preset.filter(|_| codecs.supports_preset()) {
+        args.extend(["-preset".to_string(), p.to_string()]);
+    }
+    args.extend(["-c:v".to_string(), codecs.video.clone()]);
+    args
+}
+
+/// Indices of the segments that can be cut.
+///
+/// Reversed, empty, negative or non-finite ranges are skipped; it is an error if none remain.
+fn usable_segments(segments: &[VideoSegment]) -> AppResult<Vec<usize>> {
+    let usable: Vec<usize> = segments
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| {
+            let ok = s.start.is_finite() && s.end.is_finite() && s.start >= 0.0 && s.end > s.start;
+            if !ok {
+                info!("忽略无效片段: {:?}", s);
+            }
+            ok
+        })
+        .map(|(i, _)| i)
+        .collect();
+    if usable.is_empty() {
+        return Err(AppError::NoSegments);
+    }
+    Ok(usable)
+}
+
+/// Format seconds as an SRT timestamp (`HH:MM:SS,mmm`).
+fn srt_timestamp(seconds: f64) -> String {
+    let ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!("{:02}:{:02}:{:02},{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000)
+}
+
+/// A single-cue SRT file showing `text` for the whole clip.
+fn subtitle_srt(duration: f64, text: &str) -> String {
+    format!("1\n{} --> {}\n{}\n", srt_timestamp(0.0), srt_timestamp(duration), text)
+}
+
+/// Quote a file path for use as a filter option (e.g. `subtitles=`).
+/// Backslashes become `/` and `:` is escaped so Windows drive letters survive.
+fn filter_path(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/").replace(':', "\\:");
+    format!("'{}'", path)
+}
+
+/// Write the subtitle file for a clip and return the `subtitles=` filter that burns it in.
+fn write_subtitle(file: &Path, duration: f64, text: &str) -> AppResult<String> {
+    fs::write(file, subtitle_srt(duration, text)).map_err(|e| AppError::io("write_file", Some(file), e))?;
+    Ok(format!("subtitles={}", filter_path(file)))
+}
+
+/// Join video filters, returning `None` when there are none.
+fn filter_chain(filters: &[Option<String>]) -> Option<String> {
+    let chain: Vec<&str> = filters.iter().flatten().map(String::as_str).collect();
+    (!chain.is_empty()).then(|| chain.join(","))
+}
+
+/// `volume=` audio filter, or `None` when the gain is effectively 1.
+fn volume_filter(volume: f64) -> Option<String> {
+    ((volume - 1.0).abs() > 0.01).then(|| format!("volume={}", volume))
+}
+
+/// `filter_complex` joining two clips, starting the transition `offset` seconds into the first.
+fn transition_filter(kind: &str, duration: f64, offset: f64) -> String {
+    let xfade = |name: &str| {
+        format!("[0:v][1:v]xfade=transition={}:duration={}:offset={}[outv]", name, duration, offset)
+    };
+    match kind {
+        "fade" => format!(
+            "[0:v]format=pix_fmts=yuva420p,fade=t=out:st={}:d={}:alpha=1[fv1];[1:v]format=pix_fmts=yuva420p,fade=t=in:st=0:d={}:alpha=1[fv2];[fv1][fv2]overlay=format=yuv420[outv]",
+            offset, duration, duration
+        ),
+        "dissolve" => xfade("fade"),
+        "wipe" => xfade("wiperight"),
+        "slide" => xfade("slideleft"),
+        _ => "[0:v][1:v]concat=n=2:v=1:a=0[outv]".to_string(),
+    }
+}
+
+fn as_args(args: &[String]) -> Vec<&str> {
+    args.iter().map(String::as_str).collect()
+}
+
+/// Audio bitrate assumed when estimating output size (ffmpeg's AAC default).
+const ESTIMATED_AUDIO_BITRATE: f64 = 128_000.0;
+
+/// Parse a bitrate such as `4M` or `800k` into bits per second.
+fn bitrate_bps(bitrate: &str) -> f64 {
+    let (number, scale) = match bitrate.chars().last() {
+        Some('M') | Some('m') => (&bitrate[..bitrate.len() - 1], 1_000_000.0),
+        Some('K') | Some('k') => (&bitrate[..bitrate.len() - 1], 1_000.0),
+        _ => (bitrate, 1.0),
+    };
+    number.parse::<f64>().unwrap_or(0.0) * scale
+}
+
+// 执行计划中的一次 ffmpeg 调用
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedCommand {
+    pub stage: String,
+    pub program: String,
+    pub args: Vec<String>,
+    /// 可直接复制到终端重放的完整命令行
+    pub command_line: String,
+}
+
+impl PlannedCommand {
+    fn new(program: &str, stage: &str, args: Vec<String>) -> PlannedCommand {
+        PlannedCommand {
+            stage: stage.to_string(),
+            program: program.to_string(),
+            command_line: job_log::command_line(program, &as_args(&args)),
+            args,
+        }
+    }
+}
+
+// 执行计划中涉及的中间文件
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedFile {
+    pub path: String,
+    /// subtitle / segment / transition / concat_list
+    pub kind: String,
+    /// 由后端直接写入的文件内容；ffmpeg 生成的文件为 `None`
+    pub content: Option<String>,
+}
+
+impl PlannedFile {
+    fn new(path: &str, kind: &str, content: Option<String>) -> PlannedFile {
+        PlannedFile { path: path.to_string(), kind: kind.to_string(), content }
+    }
+}
+
+// 导出的完整执行计划，按顺序列出所有 ffmpeg 调用
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionPlan {
+    pub output_path: String,
+    pub codecs: ExportCodecs,
+    pub commands: Vec<PlannedCommand>,
+    pub intermediate_files: Vec<PlannedFile>,
+    /// 预计输出时长（秒）
+    pub estimated_duration: f64,
+    /// 按目标码率估算的输出大小（字节）
+    pub estimated_size: u64,
+}
+
+// cut_video 的返回值：输出路径，或 dry_run 时的执行计划
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum CutVideoResult {
+    Output(String),
+    Plan(ExecutionPlan),
+}
+
+/// Work out every intermediate file and ffmpeg call of a cut without touching the disk.
+///
+/// Intermediates are placed in `work_dir`; the final concat writes to `output`.
+fn build_cut_plan(
+    params: &CutVideoParams,
+    caps: &Capabilities,
+    program: &str,
+    work_dir: &Path,
+    output: &str,
+) -> AppResult<ExecutionPlan> {
+    let format = params.format.as_deref().unwrap_or("mp4");
+    let quality = params.quality.as_deref().unwrap_or("medium");
+    let preset = quality_preset(format, quality);
+
+    let transition_type = params.transition.as_deref().unwrap_or("none");
+    let transition_duration = params.transition_duration.unwrap_or(1.0);
+    let volume = params.volume.unwrap_or(1.0);
+    let add_subtitles = params.add_subtitles.unwrap_or(false);
+
+    let usable = usable_segments(&params.segments)?;
+
+    // 开始处理前检查 ffmpeg 是否具备所需组件，必要时换用备选编码器
+    let codecs = capabilities::plan_export(caps, &capabilities::ExportFeatures {
+        format,
+        transition: if usable.len() > 1 { transition_type } else { "none" },
+        subtitles: add_subtitles && usable.iter().any(|&i| params.segments[i].content.is_some()),
+        volume: volume_filter(volume).is_some(),
+        scale: preset.scale.is_some(),
+    })?;
+
+    let work_path = |name: &str| work_dir.join(name).to_string_lossy().to_string();
+    let mut commands = Vec::new();
+    let mut files = Vec::new();
+    // (路径, 时长)
+    let mut clips: Vec<(String, f64)> = Vec::new();
+
+    for &i in &usable {
+        let segment = &params.segments[i];
+        let duration = segment.end - segment.start;
+        let segment_path = work_path(&format!("segment_{}.{}", i, format));
+
+        let subtitle = match (&segment.content, add_subtitles) {
+            (Some(content), true) => {
+                let subtitle_file = work_dir.join(format!("subtitle_{}.srt", i));
+                files.push(PlannedFile::new(
+                    &subtitle_file.to_string_lossy(),
+                    "subtitle",
+                    Some(subtitle_srt(duration, content)),
+                ));
+                Some(format!("subtitles={}", filter_path(&subtitle_file)))
+            }
+            _ => None,
+        };
+        let video_filters = filter_chain(&[preset.scale.map(|s| format!("scale={}", s)), subtitle]);
+
+        let mut ffmpeg_args = vec![
+            "-y".to_string(),
+            "-ss".to_string(), segment.start.to_string(),
+            "-i".to_string(), params.input_path.clone(),
+            "-t".to_string(), duration.to_string(),
+        ];
+        ffmpeg_args.extend(encode_args(&preset, &codecs));
+        if let Some(filters) = video_filters {
+            ffmpeg_args.extend(["-vf".to_string(), filters]);
+        }
+        if let Some(filter) = volume_filter(volume) {
+            ffmpeg_args.extend(["-af".to_string(), filter]);
+        }
+        ffmpeg_args.extend([
+            "-c:a".to_string(), codecs.audio.clone(),
+            "-strict".to_string(), "experimental".to_string(),
+            segment_path.clone(),
+        ]);
+
+        commands.push(PlannedCommand::new(program, "segment", ffmpeg_args));
+        files.push(PlannedFile::new(&segment_path, "segment", None));
+        clips.push((segment_path, duration));
+    }
+
+    // 处理转场效果
+    if transition_type != "none" && clips.len() > 1 {
+        let mut transition_clips = Vec::new();
+
+        for (i, pair) in clips.windows(2).enumerate() {
+            let ((file1, duration1), (file2, duration2)) = (&pair[0], &pair[1]);
+            let transition_path = work_path(&format!("transition_{}_{}.{}", i, i+1, format));
+
+            let offset = (duration1 - transition_duration).max(0.0);
+            let filter_complex = transition_filter(transition_type, transition_duration, offset);
+
+            let args = [
+                "-y", "-i", file1, "-i", file2,
+                "-filter_complex", &filter_complex,
+                "-map", "[outv]",
+                &transition_path,
+            ];
+            commands.push(PlannedCommand::new(program, "transition", args.map(str::to_string).to_vec()));
+            files.push(PlannedFile::new(&transition_path, "transition", None));
+            transition_clips.push((transition_path, offset + duration2));
+        }
+
+        clips = transition_clips;
+    }
+
+    let list_path = work_path("segments.txt");
+    let list: String = clips.iter().map(|(path, _)| format!("file {}\n", concat_quote(path))).collect();
+    files.push(PlannedFile::new(&list_path, "concat_list", Some(list)));
+
+    let concat_args = [
+        "-y",
+        "-f", "concat",
+        "-safe", "0",
+        "-i", &list_path,
+        "-c:v", &codecs.video,
+        "-c:a", &codecs.audio,
+        "-strict", "-2",
+        output,
+    ];
+    commands.push(PlannedCommand::new(program, "concat", concat_args.map(str::to_string).to_vec()));
+
+    let estimated_duration: f64 = clips.iter().map(|(_, duration)| duration).sum();
+    let estimated_size =
+        ((bitrate_bps(preset.bitrate) + ESTIMATED_AUDIO_BITRATE) * estimated_duration / 8.0) as u64;
+
+    Ok(ExecutionPlan {
+        output_path: params.output_path.clone(),
+        codecs,
+        commands,
+        intermediate_files: files,
+        estimated_duration,
+        estimated_size,
+    })
+}
+
+/// 剪辑视频 - 支持多段剪辑和转场效果
+///
+/// `dry_run` 为真时不执行任何编码，返回完整的执行计划。
+#[tauri::command]
+pub async fn cut_video(params: CutVideoParams) -> AppResult<CutVideoResult> {
+    media_scope::check(&params.input_path)?;
+    media_scope::check_output(&params.output_path)?;
+    let backend = FfmpegBackend::new("export")?;
+    if params.dry_run.unwrap_or(false) {
+        return plan_cut(&backend, &params).map(CutVideoResult::Plan);
+    }
+    let output = render_cut_with(&backend, params)?;
+    tray::set_last_output(&output);
+    Ok(CutVideoResult::Output(output))
+}
+
+/// Plan a cut without encoding anything.
+///
+/// Intermediates point into a fresh session directory that is not created, and the
+/// final command writes straight to the output path so the commands can be replayed.
+pub fn plan_cut(backend: &dyn MediaBackend, params: &CutVideoParams) -> AppResult<ExecutionPlan> {
+    let program = toolchain::resolve()
+        .map(|t| t.ffmpeg.path.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "ffmpeg".to_string());
+    let work_dir = temp_workspace::workspace_root().join(temp_workspace::unique_name("export", ""));
+    build_cut_plan(params, &backend.capabilities()?, &program, &work_dir, &params.output_path)
+}
+
+/// Run a multi-segment cut outside of any window, returning the output path.
+/// Shared by the `cut_video` command and the headless CLI.
+pub fn render_cut(params: CutVideoParams) -> AppResult<String> {
+    render_cut_with(&FfmpegBackend::new("export")?, params)
+}
+
+/// [`render_cut`] against an arbitrary backend.
+pub fn render_cut_with(backend: &dyn MediaBackend, params: CutVideoParams) -> AppResult<String> {
+    info!("开始剪辑视频: {:?}", params);
+
+    let caps = backend.capabilities()?;
+    // 中间文件在任何退出路径上都随会话一起删除
+    let session = TempSession::scratch("export")?;
+    // 先写入同目录临时文件，成功后再原子替换最终输出
+    let output = AtomicOutput::new(&params.output_path)?;
+    let plan = build_cut_plan(&params, &caps, "ffmpeg", session.dir(), &output.temp_path_str())?;
+    if plan.codecs.fallback {
+        backend.note(&format!("using fallback encoders: video={}, audio={}", plan.codecs.video, plan.codecs.audio));
+    }
+
+    for file in &plan.intermediate_files {
+        if let Some(content) = &file.content {
+            let path = Path::new(&file.path);
+            fs::write(path, content).map_err(|e| AppError::io("write_file", Some(path), e))?;
+        }
+    }
+    let progress = tray::JobProgress::start("export", plan.commands.len());
+    for (i, command) in plan.commands.iter().enumerate() {
+        info!("执行FFmpeg命令 [{}]: {:?}", command.stage, command.args);
+        backend.ffmpeg(&command.stage, &as_args(&command.args))?;
+        progress.set_done(i + 1);
+    }
+    output.commit()?;
+
+    info!("视频剪辑完成: {}", params.output_path);
+    Ok(params.output_path)
+}
+
+/// 生成片段预览视频
+#[tauri::command]
+pub async fn generate_preview(params: PreviewParams) -> AppResult<String> {
+    media_scope::check(&params.input_path)?;
+    preview_with(&FfmpegBackend::new("preview")?, params)
+}
+
+/// Render a low-resolution preview of one segment against an arbitrary backend.
+pub fn preview_with(backend: &dyn MediaBackend, params: PreviewParams) -> AppResult<String> {
+    info!("生成预览片段: {:?}", params);
+
+    let segment = &params.segment;
+    if usable_segments(std::slice::from_ref(segment)).is_err() {
+        return Err(AppError::InvalidSegment {
+            index: None,
+            start: segment.start,
+            end: segment.end,
+        });
+    }
+
+    let session = TempSession::create("preview")?;
+    let preview_file = session.unique_file("preview", "mp4");
+    let preview_path = preview_file.to_string_lossy().to_string();
+
+    let duration = segment.end - segment.start;
+
+    let subtitle = match (&segment.content, params.add_subtitles.unwrap_or(false)) {
+        (Some(content), true) => Some(write_subtitle(&session.unique_file("subtitle", "srt"), duration, content)?),
+        _ => None,
+    };
+    let video_filters = filter_chain(&[Some("scale=1280:720".to_string()), subtitle]).unwrap_or_default();
+
+    let output = AtomicOutput::new(&preview_file)?;
+    let preview_path_str = output.temp_path_str();
+
+    info!("执行预览命令: start={}, input={}, duration={}, filters={}",
+          segment.start, params.input_path, duration, video_filters);
+    let start_str = segment.start.to_string();
+    let duration_str = duration.to_string();
+    let mut args = vec![
+        "-y",
+        "-ss", &start_str,
+        "-i", &params.input_path,
+        "-t", &duration_str,
+        "-vf", &video_filters,
+    ];
+    let audio_filter = volume_filter(params.volume.unwrap_or(1.0));
+    if let Some(filter) = &audio_filter {
+        args.extend(["-af", filter]);
+    }
+    args.extend([
+        "-c:v", "libx264",
+        "-c:a", "aac",
+        "-strict", "experimental",
+        &preview_path_str,
+    ]);
+    let progress = tray::JobProgress::start("preview", 1);
+    backend.ffmpeg("preview", &args)?;
+    progress.set_done(1);
+    output.commit()?;
+
+    Ok(preview_path)
+}
+
+// 分镜动态预览（animatic）中的单帧
+#[derive(Deserialize, Debug, Clone)]
+pub struct AnimaticFrame {
+    pub image: String,
+    /// 该帧停留的秒数
+    pub duration: f64,
+}
+
+// 分镜动态预览参数
+#[derive(Deserialize, Debug, Clone)]
+pub struct AnimaticParams {
+    pub frames: Vec<AnimaticFrame>,
+    pub output_path: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fps: Option<u32>,
+    pub format: Option<String>,
+    /// 可选的配音/配乐，按画面总时长截断
+    pub audio_path: Option<String>,
+}
+
+/// Quote a path for an ffmpeg concat list (`file '...'`).
+fn concat_quote(path: &str) -> String {
+    format!("'{}'", path.replace('\'', "'\\''"))
+}
+
+/// Render still storyboard frames into a video, each held for its own duration.
+pub fn render_animatic(params: AnimaticParams) -> AppResult<String> {
+    render_animatic_with(&FfmpegBackend::new("animatic")?, params)
+}
+
+/// [`render_animatic`] against an arbitrary backend.
+pub fn render_animatic_with(backend: &dyn MediaBackend, params: AnimaticParams) -> AppResult<String> {
+    info!("生成分镜动态预览: {} 帧 -> {}", params.frames.len(), params.output_path);
+
+    if params.frames.is_empty() {
+        return Err(AppError::NoSegments);
+    }
+    for (i, frame) in params.frames.iter().enumerate() {
+        if !frame.duration.is_finite() || frame.duration <= 0.0 {
+            return Err(AppError::InvalidSegment { index: Some(i), start: 0.0, end: frame.duration });
+        }
+    }
+
+    let format = params.format.clone().unwrap_or_else(|| "mp4".to_string());
+    let width = params.width.unwrap_or(1920);
+    let height = params.height.unwrap_or(1080);
+    let fps = params.fps.unwrap_or(24);
+
+    let caps = backend.capabilities()?;
+    let codecs = capabilities::plan_export(&caps, &capabilities::ExportFeatures {
+        format: &format,
+        transition: "none",
+        subtitles: false,
+        volume: false,
+        scale: true,
+    })?;
+
+    let session = TempSession::scratch("animatic")?;
+
+    // concat 分离器中最后一帧的 duration 会被忽略，需要再写一次该帧
+    let list_file = session.path("frames.txt");
+    let mut list = String::new();
+    for frame in &params.frames {
+        list.push_str(&format!("file {}\nduration {}\n", concat_quote(&frame.image), frame.duration));
+    }
+    if let Some(last) = params.frames.last() {
+        list.push_str(&format!("file {}\n", concat_quote(&last.image)));
+    }
+    fs::write(&list_file, list).map_err(|e| AppError::io("write_file", Some(&list_file), e))?;
+
+    let list_file_str = list_file.to_string_lossy().to_string();
+    let video_filters = format!(
+        "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,fps={fps},format=yuv420p",
+        w = width, h = height, fps = fps
+    );
+    let output = AtomicOutput::new(&params.output_path)?;
+    let output_path_str = output.temp_path_str();
+
+    let mut args = vec!["-y", "-f", "concat", "-safe", "0", "-i", &list_file_str];
+    if let Some(audio) = &params.audio_path {
+        args.extend(["-i", audio, "-c:a", &codecs.audio, "-shortest"]);
+    }
+    args.extend(["-vf", &video_filters, "-c:v", &codecs.video, &output_path_str]);
+
+    backend.ffmpeg("animatic", &args)?;
+    output.commit()?;
+
+    info!("分镜动态预览完成: {}", params.output_path);
+    Ok(params.output_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{full_capabilities, RecordingBackend};
+    use crate::fs_utils::new_id;
+    use std::path::PathBuf;
+
+    fn segment(start: f64, end: f64, content: Option<&str>) -> VideoSegment {
+        VideoSegment { start, end, segment_type: None, content: content.map(str::to_string) }
+    }
+
+    fn cut_params(segments: Vec<VideoSegment>, output: &Path) -> CutVideoParams {
+        CutVideoParams {
+            input_path: "in.mp4".to_string(),
+            output_path: output.to_string_lossy().to_string(),
+            segments,
+            quality: None,
+            format: None,
+            transition: None,
+            transition_duration: None,
+            volume: None,
+            add_subtitles: None,
+            dry_run: None,
+        }
+    }
+
+    fn output_path(ext: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("export_test_{}.{}", new_id(), ext))
+    }
+
+    /// Value following `flag` in an argument list.
+    fn arg_after<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+        args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1)).map(String::as_str)
+    }
+
+    #[test]
+    fn quality_matrix() {
+        assert_eq!(
+            quality_preset("mp4", "low"),
+            QualityPreset { scale: Some("1280:720"), bitrate: "1.5M", preset: Some("fast") }
+        );
+        assert_eq!(
+            quality_preset("mov", "ultra"),
+            QualityPreset { scale: None, bitrate: "15M", preset: Some("slow") }
+        );
+        assert_eq!(
+            quality_preset("webm", "high"),
+            QualityPreset { scale: None, bitrate: "6M", preset: None }
+        );
+        assert_eq!(
+            quality_preset("mkv", "medium"),
+            QualityPreset { scale: Some("1920:1080"), bitrate: "4M", preset: None }
+        );
+        assert_eq!(quality_preset("mp4", "bogus"), quality_preset("mp4", "medium"));
+    }
+
+    #[test]
+    fn preset_only_for_x264() {
+        let preset = quality_preset("mp4", "high");
+        let x264 = ExportCodecs { video: "libx264".into(), audio: "aac".into(), fallback: false };
+        let openh264 = ExportCodecs { video: "libopenh264".into(), ..x264.clone() };
+        assert_eq!(encode_args(&preset, &x264), ["-b:v", "8M", "-preset", "slow", "-c:v", "libx264"]);
+        assert_eq!(encode_args(&preset, &openh264), ["-b:v", "8M", "-c:v", "libopenh264"]);
+    }
+
+    #[test]
+    fn srt_timestamps() {
+        assert_eq!(srt_timestamp(0.0), "00:00:00,000");
+        assert_eq!(srt_timestamp(2.5), "00:00:02,500");
+        assert_eq!(srt_timestamp(61.0), "00:01:01,000");
+        assert_eq!(srt_timestamp(3725.042), "01:02:05,042");
+        assert_eq!(srt_timestamp(-1.0), "00:00:00,000");
+        assert_eq!(subtitle_srt(4.25, "你好"), "1\n00:00:00,000 --> 00:00:04,250\n你好\n");
+    }
+
+    #[test]
+    fn segment_validation() {
+        let segments = vec![
+            segment(0.0, 2.0, None),
+            segment(3.0, 3.0, None),
+            segment(5.0, 4.0, None),
+            segment(f64::NAN, 1.0, None),
+            segment(-1.0, 1.0, None),
+            segment(6.0, f64::INFINITY, None),
+            segment(7.0, 9.5, None),
+        ];
+        assert_eq!(usable_segments(&segments).unwrap(), vec![0, 6]);
+        assert_eq!(usable_segments(&[]), Err(AppError::NoSegments));
+        assert_eq!(usable_segments(&[segment(2.0, 1.0, None)]), Err(AppError::NoSegments));
+    }
+
+    #[test]
+    fn filter_strings() {
+        assert_eq!(filter_chain(&[None, None]), None);
+        assert_eq!(
+            filter_chain(&[Some("scale=1280:720".into()), Some("subtitles='a.srt'".into())]).as_deref(),
+            Some("scale=1280:720,subtitles='a.srt'")
+        );
+        assert_eq!(volume_filter(1.0), None);
+        assert_eq!(volume_filter(1.005), None);
+        assert_eq!(volume_filter(0.5).as_deref(), Some("volume=0.5"));
+        assert_eq!(filter_path(Path::new(r"C:\tmp\sub.srt")), r"'C\:/tmp/sub.srt'");
+        assert_eq!(
+            transition_filter("dissolve", 1.0, 4.0),
+            "[0:v][1:v]xfade=transition=fade:duration=1:offset=4[outv]"
+        );
+        assert_eq!(
+            transition_filter("wipe", 0.5, 2.5),
+            "[0:v][1:v]xfade=transition=wiperight:duration=0.5:offset=2.5[outv]"
+        );
+        assert!(transition_filter("fade", 1.0, 3.0).contains("fade=t=out:st=3:d=1"));
+        assert_eq!(transition_filter("unknown", 1.0, 0.0), "[0:v][1:v]concat=n=2:v=1:a=0[outv]");
+    }
+
+    #[test]
+    fn cut_runs_one_encode_per_usable_segment_then_concat() {
+        let backend = RecordingBackend::new().creating_outputs();
+        let output = output_path("mp4");
+        let mut params = cut_params(
+            vec![segment(1.0, 3.0, Some("第一句")), segment(5.0, 4.0, None), segment(10.0, 12.5, None)],
+            &output,
+        );
+        params.add_subtitles = Some(true);
+        params.volume = Some(0.8);
+
+        let result = render_cut_with(&backend, params).unwrap();
+        assert_eq!(result, output.to_string_lossy());
+        assert!(output.exists());
+        let _ = fs::remove_file(&output);
+
+        assert_eq!(backend.stages(), ["segment", "segment", "concat"]);
+        let calls = backend.calls();
+        let first = &calls[0].args;
+        assert_eq!(arg_after(first, "-ss"), Some("1"));
+        assert_eq!(arg_after(first, "-t"), Some("2"));
+        assert_eq!(arg_after(first, "-c:v"), Some("libx264"));
+        assert_eq!(arg_after(first, "-preset"), Some("fast"));
+        assert_eq!(arg_after(first, "-af"), Some("volume=0.8"));
+        let vf = arg_after(first, "-vf").unwrap();
+        assert!(vf.starts_with("scale=1920:1080,subtitles='"), "{}", vf);
+        assert!(vf.ends_with("subtitle_0.srt'"), "{}", vf);
+
+        let second = &calls[1].args;
+        assert_eq!(arg_after(second, "-ss"), Some("10"));
+        assert_eq!(arg_after(second, "-vf"), Some("scale=1920:1080"));
+        assert!(second.last().unwrap().ends_with("segment_2.mp4"));
+    }
+
+    #[test]
+    fn cut_transitions_use_clip_durations_for_offsets() {
+        let backend = RecordingBackend::new().creating_outputs();
+        let output = output_path("mp4");
+        let mut params = cut_params(vec![segment(0.0, 4.0, None), segment(10.0, 13.0, None)], &output);
+        params.transition = Some("slide".to_string());
+        params.transition_duration = Some(0.5);
+
+        render_cut_with(&backend, params).unwrap();
+        let _ = fs::remove_file(&output);
+
+        assert_eq!(backend.stages(), ["segment", "segment", "transition", "concat"]);
+        let transition = &backend.calls()[2].args;
+        assert_eq!(
+            arg_after(transition, "-filter_complex"),
+            Some("[0:v][1:v]xfade=transition=slideleft:duration=0.5:offset=3.5[outv]")
+        );
+    }
+
+    #[test]
+    fn cut_falls_back_when_x264_is_missing() {
+        let mut caps = full_capabilities();
+        caps.encoders.remove("libx264");
+        caps.encoders.insert("libopenh264".to_string());
+        let backend = RecordingBackend::new().with_capabilities(caps).creating_outputs();
+        let output = output_path("mp4");
+
+        render_cut_with(&backend, cut_params(vec![segment(0.0, 1.0, None)], &output)).unwrap();
+        let _ = fs::remove_file(&output);
+
+        let args = &backend.calls()[0].args;
+        assert_eq!(arg_after(args, "-c:v"), Some("libopenh264"));
+        assert_eq!(arg_after(args, "-preset"), None);
+    }
+
+    #[test]
+    fn cut_rejects_before_running_anything() {
+        let backend = RecordingBackend::new();
+        let output = output_path("mp4");
+        let err = render_cut_with(&backend, cut_params(vec![segment(3.0, 1.0, None)], &output)).unwrap_err();
+        assert_eq!(err, AppError::NoSegments);
+
+        let mut caps = full_capabilities();
+        caps.filters.remove("xfade");
+        let backend = RecordingBackend::new().with_capabilities(caps);
+        let mut params = cut_params(vec![segment(0.0, 1.0, None), segment(2.0, 3.0, None)], &output);
+        params.transition = Some("dissolve".to_string());
+        let err = render_cut_with(&backend, params).unwrap_err();
+        assert!(matches!(err, AppError::MissingCapability { ref name, .. } if name == "xfade"));
+        assert!(backend.calls().is_empty());
+    }
+
+    #[test]
+    fn cut_failure_leaves_no_output() {
+        let backend = RecordingBackend::new().creating_outputs().failing_at("concat");
+        let output = output_path("mp4");
+        let err = render_cut_with(&backend, cut_params(vec![segment(0.0, 1.0, None)], &output)).unwrap_err();
+        assert!(matches!(err, AppError::EncodeFailed { ref stage, .. } if stage == "concat"));
+        assert!(!output.exists());
+        let segment = backend.calls()[0].args.last().unwrap().clone();
+        assert!(!Path::new(&segment).parent().unwrap().exists(), "session dir is removed on failure");
+    }
+
+    #[test]
+    fn concat_list_quotes_paths() {
+        let output = output_path("mp4");
+        let params = cut_params(vec![segment(0.0, 1.0, None)], &output);
+        let work_dir = Path::new("/tmp/it's here");
+        let plan = build_cut_plan(&params, &full_capabilities(), "ffmpeg", work_dir, "out.mp4").unwrap();
+        let list = plan.intermediate_files.last().unwrap().content.clone().unwrap();
+        assert!(list.starts_with("file '/tmp/it'\\''s here"), "{}", list);
+        assert!(list.ends_with("segment_0.mp4'\n"), "{}", list);
+    }
+
+    #[test]
+    fn bitrates() {
+        assert_eq!(bitrate_bps("4M"), 4_000_000.0);
+        assert_eq!(bitrate_bps("1.5M"), 1_500_000.0);
+        assert_eq!(bitrate_bps("800k"), 800_000.0);
+        assert_eq!(bitrate_bps("64000"), 64_000.0);
+    }
+
+    #[test]
+    fn dry_run_plans_without_running() {
+        let backend = RecordingBackend::new();
+        let output = output_path("mp4");
+        let mut params = cut_params(
+            vec![segment(0.0, 4.0, Some("台词")), segment(10.0, 13.0, None)],
+            &output,
+        );
+        params.add_subtitles = Some(true);
+        params.transition = Some("dissolve".to_string());
+
+        let plan = plan_cut(&backend, &params).unwrap();
+        assert!(backend.calls().is_empty());
+        assert!(!output.exists());
+
+        let stages: Vec<&str> = plan.commands.iter().map(|c| c.stage.as_str()).collect();
+        assert_eq!(stages, ["segment", "segment", "transition", "concat"]);
+        let concat = plan.commands.last().unwrap();
+        assert_eq!(concat.args.last(), Some(&params.output_path));
+        assert!(concat.command_line.contains(" -f concat -safe 0 -i "));
+
+        let kinds: Vec<&str> = plan.intermediate_files.iter().map(|f| f.kind.as_str()).collect();
+        assert_eq!(kinds, ["subtitle", "segment", "segment", "transition", "concat_list"]);
+        assert_eq!(
+            plan.intermediate_files[0].content.as_deref(),
+            Some("1\n00:00:00,000 --> 00:00:04,000\n台词\n")
+        );
+        assert!(plan.intermediate_files[4].content.as_deref().unwrap().contains("transition_0_1.mp4"));
+        let work_dir = Path::new(&plan.intermediate_files[0].path).parent().unwrap();
+        assert!(!work_dir.exists());
+
+        // 转场从第一段的 3 秒处开始，输出为 3 + 3 秒
+        assert_eq!(plan.estimated_duration, 6.0);
+        assert_eq!(plan.estimated_size, ((4_000_000.0 + 128_000.0) * 6.0 / 8.0) as u64);
+    }
+
+    #[test]
+    fn preview_burns_segment_content_as_subtitle() {
+        let backend = RecordingBackend::new().creating_outputs();
+        let params = PreviewParams {
+            input_path: "in.mp4".to_string(),
+            segment: VideoSegment {
+                start: 2.0,
+                end: 5.0,
+                segment_type: Some("narration".to_string()),
+                content: Some("旁白".to_string()),
+            },
+            transition: None,
+            transition_duration: None,
+            volume: Some(1.5),
+            add_subtitles: Some(true),
+        };
+        let preview = preview_with(&backend, params).unwrap();
+        let _ = fs::remove_file(&preview);
+
+        let args = &backend.calls()[0].args;
+        assert_eq!(arg_after(args, "-t"), Some("3"));
+        assert_eq!(arg_after(args, "-af"), Some("volume=1.5"));
+        assert!(arg_after(args, "-vf").unwrap().starts_with("scale=1280:720,subtitles='"));
+    }
+
+    #[test]
+    fn animatic_validates_frames() {
+        let backend = RecordingBackend::new();
+        let params = |duration: f64| AnimaticParams {
+            frames: vec![
+                AnimaticFrame { image: "a.png".to_string(), duration: 1.0 },
+                AnimaticFrame { image: "b.png".to_string(), duration },
+            ],
+            output_path: "out.mp4".to_string(),
+            width: None,
+            height: None,
+            fps: None,
+            format: None,
+            audio_path: None,
+        };
+        assert_eq!(
+            render_animatic_with(&backend, params(0.0)),
+            Err(AppError::InvalidSegment { index: Some(1), start: 0.0, end: 0.0 })
+        );
+        assert!(render_animatic_with(&backend, params(f64::NAN)).is_err());
+        assert!(backend.calls().is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+//! ffmpeg/ffprobe 进程调用
+//!
+//! 可执行文件由 [`toolchain`](crate::toolchain) 定位，每次调用都会写入所属任务的 [`JobLog`]。
+
+use crate::error::{AppError, AppResult};
+use crate::job_log::JobLog;
+use crate::toolchain;
+use std::process::{Command, Output};
+use std::time::Instant;
+
+/// 错误信息中保留的 stderr 末尾行数
+pub const STDERR_TAIL_LINES: usize = 12;
+
+/// Keep only the last `lines` non-empty lines of a process's stderr.
+pub fn stderr_tail(stderr: &[u8], lines: usize) -> String {
+    let text = String::from_utf8_lossy(stderr);
+    let all: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    all[all.len().saturating_sub(lines)..].join("\n")
+}
+
+/// Run `command` with `args`, recording the invocation in `log`.
+fn run_logged(log: &JobLog, mut command: Command, stage: &str, args: &[&str]) -> AppResult<Output> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let started = Instant::now();
+    let result = command.args(args).output();
+    match result {
+        Ok(output) => {
+            log.record(&program, stage, args, started.elapsed(), Some(output.status), &output.stderr);
+            Ok(output)
+        }
+        Err(e) => {
+            log.record(&program, stage, args, started.elapsed(), None, e.to_string().as_bytes());
+            Err(AppError::io("spawn", None, e))
+        }
+    }
+}
+
+/// Execute ffmpeg directly without shell to prevent command injection.
+/// Each arg is passed as a separate argument — no shell interpretation.
+/// `stage` names the processing step and is reported in [`AppError::EncodeFailed`].
+pub fn run_ffmpeg(log: &JobLog, stage: &str, args: &[&str]) -> AppResult<()> {
+    run_ffmpeg_output(log, stage, args).map(|_| ())
+}
+
+/// Like [`run_ffmpeg`], but return ffmpeg's stdout.
+pub fn run_ffmpeg_output(log: &JobLog, stage: &str, args: &[&str]) -> AppResult<Vec<u8>> {
+    let output = run_logged(log, toolchain::ffmpeg_command()?, stage, args)?;
+    if !output.status.success() {
+        return Err(AppError::EncodeFailed {
+            stage: stage.to_string(),
+            exit_code: output.status.code(),
+            stderr_tail: stderr_tail(&output.stderr, STDERR_TAIL_LINES),
+            log_id: Some(log.job_id().to_string()),
+        });
+    }
+    Ok(output.stdout)
+}
+
+/// Run ffprobe on `path` and return its stdout.
+pub fn run_ffprobe(log: &JobLog, path: &str, args: &[&str]) -> AppResult<Vec<u8>> {
+    let mut full_args = args.to_vec();
+    full_args.push(path);
+    let output = run_logged(log, toolchain::ffprobe_command()?, "probe", &full_args)?;
+    if !output.status.success() {
+        return Err(AppError::ProbeFailed {
+            path: path.to_string(),
+            reason: stderr_tail(&output.stderr, STDERR_TAIL_LINES),
+            log_id: Some(log.job_id().to_string()),
+        });
+    }
+    Ok(output.stdout)
+}
+
+/// 检查 ffmpeg 与 ffprobe 是否可用
+pub fn is_ffmpeg_installed() -> bool {
+    toolchain::resolve().is_ok()
+}
+
+/// Fail with [`AppError::FfmpegMissing`] unless ffmpeg and ffprobe can be resolved.
+pub fn ensure_ffmpeg() -> AppResult<()> {
+    toolchain::resolve().map(|_| ())
+}
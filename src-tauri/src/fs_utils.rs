@@ -0,0 +1,221 @@
+//! 文件工具：唯一 ID、原子写入与目录约束
+//!
+//! 所有生成的产物都使用 UUIDv7 命名（按时间有序、不会碰撞）。渲染输出与项目文件
+//! 先写入同目录下的临时文件，fsync 后再原子重命名到最终路径，崩溃时不会留下
+//! 看似完整的截断文件。来自前端的相对路径通过 [`resolve_within`] 限定在指定目录内。
+
+use crate::error::{AppError, AppResult};
+use log::warn;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+/// Generate a collision-free, time-ordered ID for generated artifacts.
+pub fn new_id() -> String {
+    uuid::Uuid::now_v7().simple().to_string()
+}
+
+/// Flush a file's contents to disk.
+fn sync_file(path: &Path) -> AppResult<()> {
+    File::open(path)
+        .and_then(|f| f.sync_all())
+        .map_err(|e| AppError::io("sync", Some(path), e))
+}
+
+/// Flush directory entries so a completed rename survives a crash.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) {
+    if let Err(e) = File::open(dir).and_then(|d| d.sync_all()) {
+        warn!("同步目录失败: {} ({})", dir.display(), e);
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) {}
+
+/// An output file that is written to a hidden temp sibling and renamed into place on commit.
+///
+/// The temp path keeps the original extension so tools like ffmpeg still pick the right
+/// muxer. If the value is dropped without [`AtomicOutput::commit`], the temp file is removed.
+pub struct AtomicOutput {
+    final_path: PathBuf,
+    temp_path: PathBuf,
+    committed: bool,
+}
+
+impl AtomicOutput {
+    pub fn new(final_path: impl Into<PathBuf>) -> AppResult<AtomicOutput> {
+        let final_path = final_path.into();
+        let file_name = final_path
+            .file_name()
+            .ok_or_else(|| AppError::InvalidPath {
+                path: final_path.to_string_lossy().to_string(),
+                reason: "missing file name".to_string(),
+            })?
+            .to_string_lossy()
+            .to_string();
+        let parent = match final_path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        fs::create_dir_all(&parent).map_err(|e| AppError::io("create_dir", Some(&parent), e))?;
+
+        let temp_name = match final_path.extension() {
+            Some(ext) => format!(".{}.{}.partial.{}", file_name, new_id(), ext.to_string_lossy()),
+            None => format!(".{}.{}.partial", file_name, new_id()),
+        };
+
+        Ok(AtomicOutput {
+            temp_path: parent.join(temp_name),
+            final_path,
+            committed: false,
+        })
+    }
+
+    /// Where the producer should write.
+    pub fn temp_path(&self) -> &Path {
+        &self.temp_path
+    }
+
+    pub fn temp_path_str(&self) -> String {
+        self.temp_path.to_string_lossy().to_string()
+    }
+
+    pub fn final_path(&self) -> &Path {
+        &self.final_path
+    }
+
+    /// fsync the temp file and atomically move it over the final path.
+    pub fn commit(mut self) -> AppResult<PathBuf> {
+        sync_file(&self.temp_path)?;
+        fs::rename(&self.temp_path, &self.final_path)
+            .map_err(|e| AppError::io("rename", Some(&self.final_path), e))?;
+        if let Some(parent) = self.final_path.parent() {
+            sync_dir(parent);
+        }
+        self.committed = true;
+        Ok(self.final_path.clone())
+    }
+}
+
+impl Drop for AtomicOutput {
+    fn drop(&mut self) {
+        if !self.committed && self.temp_path.exists() {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+/// Atomically replace `path` with `contents`.
+pub fn atomic_write(path: &Path, contents: &[u8]) -> AppResult<()> {
+    let output = AtomicOutput::new(path)?;
+    let mut file = File::create(output.temp_path())
+        .map_err(|e| AppError::io("create_file", Some(path), e))?;
+    file.write_all(contents)
+        .map_err(|e| AppError::io("write_file", Some(path), e))?;
+    file.sync_all()
+        .map_err(|e| AppError::io("sync", Some(path), e))?;
+    drop(file);
+    output.commit()?;
+    Ok(())
+}
+
+/// BLAKE3 digest of a file's contents, as lowercase hex.
+pub fn hash_file(path: &Path) -> AppResult<String> {
+    let file = File::open(path).map_err(|e| AppError::io("read_file", Some(path), e))?;
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(file).map_err(|e| AppError::io("read_file", Some(path), e))?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Resolve `relative` under `base`, rejecting anything that could end up outside it.
+///
+/// `relative` may only contain plain path components: `..`, roots and drive prefixes are
+/// refused outright. The target (or its nearest existing ancestor) is then canonicalized
+/// and must still lie inside the canonical `base`, which catches symlinks pointing elsewhere.
+/// An empty `relative` resolves to `base` itself.
+pub fn resolve_within(base: &Path, relative: &str) -> AppResult<PathBuf> {
+    let not_allowed = || AppError::PathNotAllowed { path: relative.to_string() };
+    if relative.contains('\0') {
+        return Err(not_allowed());
+    }
+    if !Path::new(relative).components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+        return Err(not_allowed());
+    }
+
+    let target = base.join(relative);
+    let Ok(canonical_base) = base.canonicalize() else {
+        // 根目录尚不存在时其下也不可能有符号链接
+        return Ok(target);
+    };
+    let existing = target.ancestors().find(|p| p.exists() || p.symlink_metadata().is_ok());
+    if let Some(existing) = existing {
+        let canonical = existing.canonicalize().map_err(|_| not_allowed())?;
+        if !canonical.starts_with(&canonical_base) {
+            return Err(not_allowed());
+        }
+    }
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn rejected(base: &Path, relative: &str) -> bool {
+        matches!(resolve_within(base, relative), Err(AppError::PathNotAllowed { .. }))
+    }
+
+    #[test]
+    fn plain_relative_paths_resolve_inside_base() {
+        let base = TempDir::new();
+        assert_eq!(resolve_within(&base.0, "blazecut").unwrap(), base.0.join("blazecut"));
+        assert_eq!(resolve_within(&base.0, "a/b/c.json").unwrap(), base.0.join("a/b/c.json"));
+        assert_eq!(resolve_within(&base.0, "").unwrap(), base.0);
+    }
+
+    #[test]
+    fn parent_components_are_rejected() {
+        let base = TempDir::new();
+        for attempt in ["..", "../x", "a/../../x", "a/..", "./../x", "blazecut/../../etc"] {
+            assert!(rejected(&base.0, attempt), "{}", attempt);
+        }
+    }
+
+    #[test]
+    fn absolute_paths_are_rejected() {
+        let base = TempDir::new();
+        assert!(rejected(&base.0, "/etc/passwd"));
+        assert!(rejected(&base.0, &base.0.join("inside").to_string_lossy()));
+        #[cfg(windows)]
+        {
+            assert!(rejected(&base.0, r"C:\Windows"));
+            assert!(rejected(&base.0, r"C:relative"));
+            assert!(rejected(&base.0, r"\\server\share"));
+        }
+        assert!(rejected(&base.0, "a\0b"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_leaving_base_are_rejected() {
+        let base = TempDir::new();
+        let outside = TempDir::new();
+        fs::write(outside.0.join("secret.json"), "{}").unwrap();
+        std::os::unix::fs::symlink(&outside.0, base.0.join("link_dir")).unwrap();
+        std::os::unix::fs::symlink(outside.0.join("secret.json"), base.0.join("link.json")).unwrap();
+        std::os::unix::fs::symlink("/nonexistent/target", base.0.join("dangling.json")).unwrap();
+
+        assert!(rejected(&base.0, "link_dir"));
+        assert!(rejected(&base.0, "link_dir/secret.json"));
+        assert!(rejected(&base.0, "link_dir/new_file.json"));
+        assert!(rejected(&base.0, "link.json"));
+        assert!(rejected(&base.0, "dangling.json"));
+
+        // 指向目录内部的符号链接仍然允许
+        fs::create_dir(base.0.join("real")).unwrap();
+        std::os::unix::fs::symlink(base.0.join("real"), base.0.join("alias")).unwrap();
+        assert!(resolve_within(&base.0, "alias/file.json").is_ok());
+    }
+}
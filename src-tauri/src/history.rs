@@ -0,0 +1,577 @@
+//! 项目版本历史
+//!
+//! 每次保存项目都会在 `<app_data>/history/<项目 ID>/<版本号>.json` 记录一个版本：作者、
+//! 说明、时间，以及相对上一版本的 JSON 差异。每 [`KEYFRAME_INTERVAL`] 个版本保存一份
+//! 完整快照，还原任意版本最多只需重放这么多份差异；清理历史时最早保留的版本会被
+//! 改写为完整快照。
+
+use crate::autosave;
+use crate::error::{AppError, AppResult};
+use crate::fs_utils::{self, new_id};
+use crate::project;
+use crate::project_schema::{self, Project};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+/// 版本历史所在的应用数据子目录
+const HISTORY_DIR: &str = "history";
+
+/// 完整快照的间隔（版本数）
+pub const KEYFRAME_INTERVAL: u64 = 20;
+
+// 记录与清理都是"读最新版本再写"，需要串行
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+// 差异操作，语义与 JSON Patch 的 add / remove / replace 相同
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeOp {
+    Add,
+    Remove,
+    Replace,
+}
+
+// 单处差异，`path` 为 JSON Pointer（如 `/assets/2/duration`）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Change {
+    pub op: ChangeOp,
+    pub path: String,
+    /// 修改前的值；存盘的差异不含此项，只在比较两个版本时返回
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+// 版本的来源
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum VersionOrigin {
+    Save,
+    #[serde(rename_all = "camelCase")]
+    Restore { version: u64 },
+    #[serde(rename_all = "camelCase")]
+    Branch { project_id: String, version: u64 },
+}
+
+// 存盘的版本记录：完整快照或相对上一版本的差异二者之一
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionRecord {
+    pub version: u64,
+    /// 创建时间（毫秒时间戳）
+    pub created_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub origin: VersionOrigin,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<Change>,
+}
+
+// 版本概要
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionInfo {
+    pub version: u64,
+    pub created_at: u64,
+    pub author: Option<String>,
+    pub message: Option<String>,
+    pub origin: VersionOrigin,
+    /// 相对上一版本的改动数；完整快照为 0
+    pub change_count: usize,
+}
+
+impl From<&VersionRecord> for VersionInfo {
+    fn from(record: &VersionRecord) -> VersionInfo {
+        VersionInfo {
+            version: record.version,
+            created_at: record.created_at,
+            author: record.author.clone(),
+            message: record.message.clone(),
+            origin: record.origin.clone(),
+            change_count: record.changes.len(),
+        }
+    }
+}
+
+// 清理结果
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneHistoryResult {
+    pub removed: u64,
+    pub remaining: u64,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+fn escape_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape_token(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// Structural diff turning `from` into `to`; with `with_old` each change also carries the value it replaces.
+pub fn diff(from: &Value, to: &Value, with_old: bool) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_into(from, to, "", with_old, &mut changes);
+    changes
+}
+
+fn diff_into(from: &Value, to: &Value, path: &str, with_old: bool, out: &mut Vec<Change>) {
+    let old = |value: &Value| with_old.then(|| value.clone());
+    match (from, to) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, value) in a {
+                let child = format!("{}/{}", path, escape_token(key));
+                match b.get(key) {
+                    Some(next) => diff_into(value, next, &child, with_old, out),
+                    None => out.push(Change { op: ChangeOp::Remove, path: child, old: old(value), value: None }),
+                }
+            }
+            for (key, value) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+                let child = format!("{}/{}", path, escape_token(key));
+                out.push(Change { op: ChangeOp::Add, path: child, old: None, value: Some(value.clone()) });
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for (i, (value, next)) in a.iter().zip(b).enumerate() {
+                diff_into(value, next, &format!("{}/{}", path, i), with_old, out);
+            }
+            // 从末尾删除，保证前面的下标仍然有效
+            for i in (b.len()..a.len()).rev() {
+                out.push(Change { op: ChangeOp::Remove, path: format!("{}/{}", path, i), old: old(&a[i]), value: None });
+            }
+            for (i, value) in b.iter().enumerate().skip(a.len()) {
+                let child = format!("{}/{}", path, i);
+                out.push(Change { op: ChangeOp::Add, path: child, old: None, value: Some(value.clone()) });
+            }
+        }
+        _ if from != to => out.push(Change {
+            op: ChangeOp::Replace,
+            path: path.to_string(),
+            old: old(from),
+            value: Some(to.clone()),
+        }),
+        _ => {}
+    }
+}
+
+/// Apply `changes` produced by [`diff`] to `target` in order.
+pub fn apply(target: &mut Value, changes: &[Change]) -> AppResult<()> {
+    for change in changes {
+        apply_one(target, change)
+            .ok_or_else(|| AppError::internal(format!("cannot apply {:?} at {}", change.op, change.path)))?;
+    }
+    Ok(())
+}
+
+fn apply_one(target: &mut Value, change: &Change) -> Option<()> {
+    if change.path.is_empty() {
+        *target = change.value.clone()?;
+        return Some(());
+    }
+    let (parent_path, last) = change.path.rsplit_once('/')?;
+    let mut parent = target;
+    for token in parent_path.split('/').skip(1) {
+        let token = unescape_token(token);
+        parent = match parent {
+            Value::Object(map) => map.get_mut(&token)?,
+            Value::Array(items) => items.get_mut(token.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    let last = unescape_token(last);
+    match (parent, change.op) {
+        (Value::Object(map), ChangeOp::Remove) => map.remove(&last).map(|_| ()),
+        (Value::Object(map), _) => {
+            map.insert(last, change.value.clone()?);
+            Some(())
+        }
+        (Value::Array(items), op) => {
+            let index = last.parse::<usize>().ok()?;
+            match op {
+                ChangeOp::Add if index <= items.len() => items.insert(index, change.value.clone()?),
+                ChangeOp::Remove if index < items.len() => {
+                    items.remove(index);
+                }
+                ChangeOp::Replace if index < items.len() => items[index] = change.value.clone()?,
+                _ => return None,
+            }
+            Some(())
+        }
+        _ => None,
+    }
+}
+
+/// Version records stored as one JSON file per version under a per-project directory.
+pub struct HistoryStore {
+    root: PathBuf,
+}
+
+impl HistoryStore {
+    pub fn new(root: impl Into<PathBuf>) -> HistoryStore {
+        HistoryStore { root: root.into() }
+    }
+
+    fn project_dir(&self, project_id: &str) -> AppResult<PathBuf> {
+        project::validate_project_id(project_id)?;
+        fs_utils::resolve_within(&self.root, project_id)
+    }
+
+    /// Record files of a project as `(version, path)`, oldest first.
+    fn entries(&self, project_id: &str) -> AppResult<Vec<(u64, PathBuf)>> {
+        let Ok(entries) = fs::read_dir(self.project_dir(project_id)?) else { return Ok(Vec::new()) };
+        let mut versions: Vec<(u64, PathBuf)> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|p| Some((p.file_stem()?.to_str()?.parse().ok()?, p)))
+            .collect();
+        versions.sort_by_key(|(version, _)| *version);
+        Ok(versions)
+    }
+
+    fn read_record(path: &Path) -> AppResult<VersionRecord> {
+        let content = fs::read_to_string(path).map_err(|e| AppError::io("read_file", Some(path), e))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    fn write_record(&self, project_id: &str, record: &VersionRecord) -> AppResult<()> {
+        let path = self.project_dir(project_id)?.join(format!("{}.json", record.version));
+        fs_utils::atomic_write(&path, serde_json::to_string(record)?.as_bytes())
+    }
+
+    /// All versions of a project, newest first.
+    pub fn list(&self, project_id: &str) -> AppResult<Vec<VersionInfo>> {
+        let mut versions = Vec::new();
+        for (_, path) in self.entries(project_id)?.iter().rev() {
+            versions.push(VersionInfo::from(&Self::read_record(path)?));
+        }
+        Ok(versions)
+    }
+
+    /// Rebuild the project JSON of `version` from the nearest snapshot at or before it.
+    pub fn content(&self, project_id: &str, version: u64) -> AppResult<Value> {
+        let entries = self.entries(project_id)?;
+        let end = entries.iter().position(|(v, _)| *v == version).ok_or_else(|| AppError::NotFound {
+            path: format!("{}/{}/{}", HISTORY_DIR, project_id, version),
+        })?;
+        let mut pending: Vec<VersionRecord> = Vec::new();
+        for (_, path) in entries[..=end].iter().rev() {
+            let record = Self::read_record(path)?;
+            if let Some(mut content) = record.snapshot {
+                for record in pending.iter().rev() {
+                    apply(&mut content, &record.changes)?;
+                }
+                return Ok(content);
+            }
+            pending.push(record);
+        }
+        Err(AppError::internal(format!("history of {} has no snapshot before version {}", project_id, version)))
+    }
+
+    /// Call `f` with the content of every recorded version of every project.
+    pub fn for_each_version(&self, mut f: impl FnMut(&Value)) -> AppResult<()> {
+        for project_id in project::project_dirs_in(&self.root)? {
+            let mut content: Option<Value> = None;
+            for (version, path) in self.entries(&project_id)? {
+                let record = Self::read_record(&path)?;
+                match (record.snapshot, content.as_mut()) {
+                    (Some(snapshot), _) => content = Some(snapshot),
+                    (None, Some(current)) => apply(current, &record.changes)?,
+                    (None, None) => {
+                        return Err(AppError::internal(format!(
+                            "history of {} has no snapshot before version {}",
+                            project_id, version
+                        )))
+                    }
+                }
+                if let Some(content) = &content {
+                    f(content);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The project as it was at `version`, migrated to the current schema.
+    pub fn project(&self, project_id: &str, version: u64) -> AppResult<Project> {
+        project_schema::load_value(self.content(project_id, version)?)
+    }
+
+    /// Differences between two versions, with the replaced values included.
+    pub fn diff(&self, project_id: &str, from: u64, to: u64) -> AppResult<Vec<Change>> {
+        Ok(diff(&self.content(project_id, from)?, &self.content(project_id, to)?, true))
+    }
+
+    /// Record `project` as a new version; a plain save without changes records nothing.
+    pub fn record(
+        &self,
+        project: &Project,
+        author: Option<String>,
+        message: Option<String>,
+        origin: VersionOrigin,
+        at: u64,
+    ) -> AppResult<Option<VersionInfo>> {
+        let _guard = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let content = serde_json::to_value(project)?;
+        let latest = self.entries(&project.id)?.last().map(|(v, _)| *v);
+        let version = latest.map_or(1, |v| v + 1);
+        let mut record = VersionRecord {
+            version,
+            created_at: at,
+            author,
+            message,
+            origin,
+            snapshot: None,
+            changes: Vec::new(),
+        };
+        match latest {
+            Some(previous) if version % KEYFRAME_INTERVAL != 0 => {
+                record.changes = diff(&self.content(&project.id, previous)?, &content, false);
+                if record.changes.is_empty() && record.origin == VersionOrigin::Save {
+                    return Ok(None);
+                }
+            }
+            Some(previous) if record.origin == VersionOrigin::Save => {
+                if self.content(&project.id, previous)? == content {
+                    return Ok(None);
+                }
+                record.snapshot = Some(content);
+            }
+            _ => record.snapshot = Some(content),
+        }
+        self.write_record(&project.id, &record)?;
+        Ok(Some(VersionInfo::from(&record)))
+    }
+
+    /// Drop versions beyond the newest `keep_last` or older than `max_age_ms`; the newest version is always kept.
+    pub fn prune(
+        &self,
+        project_id: &str,
+        keep_last: Option<usize>,
+        max_age_ms: Option<u64>,
+        now: u64,
+    ) -> AppResult<PruneHistoryResult> {
+        let _guard = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let entries = self.entries(project_id)?;
+        let Some((newest, _)) = entries.last() else { return Ok(PruneHistoryResult::default()) };
+        let mut keep_from = entries.len() - keep_last.unwrap_or(entries.len()).clamp(1, entries.len());
+        if let Some(max_age) = max_age_ms {
+            let cutoff = now.saturating_sub(max_age);
+            while keep_from < entries.len() - 1
+                && Self::read_record(&entries[keep_from].1)?.created_at < cutoff
+            {
+                keep_from += 1;
+            }
+        }
+        if keep_from == 0 {
+            return Ok(PruneHistoryResult { removed: 0, remaining: entries.len() as u64 });
+        }
+
+        // 先把新的最早版本改写为完整快照，再删除更早的记录
+        let (oldest, oldest_path) = &entries[keep_from];
+        let mut record = Self::read_record(oldest_path)?;
+        if record.snapshot.is_none() {
+            record.snapshot = Some(self.content(project_id, *oldest)?);
+            record.changes.clear();
+            self.write_record(project_id, &record)?;
+        }
+        for (_, path) in &entries[..keep_from] {
+            fs::remove_file(path).map_err(|e| AppError::io("remove_file", Some(path), e))?;
+        }
+        info!("已清理项目历史: {} 删除 {} 个版本，最新版本 {}", project_id, keep_from, newest);
+        Ok(PruneHistoryResult { removed: keep_from as u64, remaining: (entries.len() - keep_from) as u64 })
+    }
+}
+
+pub(crate) fn store(app_handle: &AppHandle) -> AppResult<HistoryStore> {
+    Ok(HistoryStore::new(project::app_data_dir(app_handle)?.join(HISTORY_DIR)))
+}
+
+/// Record an explicit save; failures are logged so they never fail the save itself.
+pub fn record_save(app_handle: &AppHandle, project: &Project, author: Option<String>, message: Option<String>) {
+    let result = store(app_handle)
+        .and_then(|store| store.record(project, author, message, VersionOrigin::Save, now_millis()));
+    if let Err(e) = result {
+        warn!("记录项目版本失败: {} ({})", project.id, e);
+    }
+}
+
+/// 列出项目的全部版本，最新的在前
+#[tauri::command]
+pub fn list_project_versions(project_id: String, app_handle: AppHandle) -> AppResult<Vec<VersionInfo>> {
+    store(&app_handle)?.list(&project_id)
+}
+
+/// 读取某个版本的项目内容
+#[tauri::command]
+pub fn get_project_version(project_id: String, version: u64, app_handle: AppHandle) -> AppResult<Project> {
+    store(&app_handle)?.project(&project_id, version)
+}
+
+/// 比较两个版本，返回从 `from` 到 `to` 的改动
+#[tauri::command]
+pub fn diff_project_versions(
+    project_id: String,
+    from: u64,
+    to: u64,
+    app_handle: AppHandle,
+) -> AppResult<Vec<Change>> {
+    store(&app_handle)?.diff(&project_id, from, to)
+}
+
+/// 把项目还原到指定版本，并记录为一个新版本
+#[tauri::command]
+pub fn restore_project_version(project_id: String, version: u64, app_handle: AppHandle) -> AppResult<Project> {
+    let store = store(&app_handle)?;
+    let project = store.project(&project_id, version)?;
+    autosave::write_saved(&project::project_file(&app_handle, &project_id)?, &project)?;
+    store.record(&project, None, None, VersionOrigin::Restore { version }, now_millis())?;
+    info!("项目已还原到版本 {}: {}", version, project_id);
+    Ok(project)
+}
+
+/// 以指定版本为起点创建新项目，`name` 为空时沿用原名
+#[tauri::command]
+pub fn branch_project_version(
+    project_id: String,
+    version: u64,
+    name: Option<String>,
+    app_handle: AppHandle,
+) -> AppResult<Project> {
+    let store = store(&app_handle)?;
+    let mut project = store.project(&project_id, version)?;
+    project.id = new_id();
+    if let Some(name) = name {
+        project.name = name;
+    }
+    let projects_dir = project::projects_dir(&app_handle)?;
+    project::write_project(&project::project_file_in(&projects_dir, &project.id)?, &project)?;
+    let origin = VersionOrigin::Branch { project_id: project_id.clone(), version };
+    store.record(&project, None, None, origin, now_millis())?;
+    info!("已从项目 {} 的版本 {} 创建分支: {}", project_id, version, project.id);
+    Ok(project)
+}
+
+/// 清理项目历史：只保留最近 `keep_last` 个版本和/或 `max_age_days` 天内的版本
+#[tauri::command]
+pub fn prune_project_history(
+    project_id: String,
+    keep_last: Option<usize>,
+    max_age_days: Option<u64>,
+    app_handle: AppHandle,
+) -> AppResult<PruneHistoryResult> {
+    let max_age_ms = max_age_days.map(|days| days.saturating_mul(24 * 60 * 60 * 1000));
+    store(&app_handle)?.prune(&project_id, keep_last, max_age_ms, now_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use serde_json::json;
+
+    fn project(name: &str) -> Project {
+        project_schema::load_value(json!({ "schemaVersion": 1, "id": "p1", "name": name })).unwrap()
+    }
+
+    fn save(store: &HistoryStore, name: &str, at: u64) -> Option<VersionInfo> {
+        store.record(&project(name), Some("me".into()), None, VersionOrigin::Save, at).unwrap()
+    }
+
+    #[test]
+    fn diff_round_trips_objects_and_arrays() {
+        let from = json!({ "a": 1, "a/b": { "x": [1, 2, 3] }, "gone": true, "list": [{ "k": 1 }] });
+        let to = json!({ "a": 2, "a/b": { "x": [1] }, "new": null, "list": [{ "k": 1 }, { "k": 2 }, 3] });
+        let changes = diff(&from, &to, false);
+        assert!(changes.iter().any(|c| c.path == "/a~1b/x/2" && c.op == ChangeOp::Remove));
+        let mut patched = from.clone();
+        apply(&mut patched, &changes).unwrap();
+        assert_eq!(patched, to);
+
+        let mut root = json!(1);
+        apply(&mut root, &diff(&json!(1), &json!({ "b": 2 }), false)).unwrap();
+        assert_eq!(root, json!({ "b": 2 }));
+        assert!(apply(&mut json!({}), &diff(&json!({ "a": [1] }), &json!({ "a": [] }), false)).is_err());
+    }
+
+    #[test]
+    fn versions_rebuild_across_snapshots() {
+        let dir = TempDir::new();
+        let store = HistoryStore::new(&dir.0);
+        for i in 1..=KEYFRAME_INTERVAL + 5 {
+            assert!(save(&store, &format!("v{}", i), i).is_some());
+        }
+        assert_eq!(save(&store, &format!("v{}", KEYFRAME_INTERVAL + 5), 100), None);
+
+        let versions = store.list("p1").unwrap();
+        assert_eq!(versions.len() as u64, KEYFRAME_INTERVAL + 5);
+        assert_eq!(versions[0].version, KEYFRAME_INTERVAL + 5);
+        assert_eq!(versions[0].author.as_deref(), Some("me"));
+        for version in [1, 7, KEYFRAME_INTERVAL, KEYFRAME_INTERVAL + 3] {
+            assert_eq!(store.project("p1", version).unwrap().name, format!("v{}", version));
+        }
+
+        let changes = store.diff("p1", 1, 2).unwrap();
+        assert_eq!(changes, vec![Change {
+            op: ChangeOp::Replace,
+            path: "/name".into(),
+            old: Some(json!("v1")),
+            value: Some(json!("v2")),
+        }]);
+        assert!(matches!(store.project("p1", 999), Err(AppError::NotFound { .. })));
+
+        let mut names = Vec::new();
+        store.for_each_version(|content| names.push(content["name"].as_str().unwrap().to_string())).unwrap();
+        assert_eq!(names, (1..=KEYFRAME_INTERVAL + 5).map(|i| format!("v{}", i)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn prune_keeps_newest_and_rebases_oldest() {
+        let dir = TempDir::new();
+        let store = HistoryStore::new(&dir.0);
+        for i in 1..=6 {
+            save(&store, &format!("v{}", i), i * 1000);
+        }
+
+        let result = store.prune("p1", Some(4), None, 10_000).unwrap();
+        assert_eq!(result, PruneHistoryResult { removed: 2, remaining: 4 });
+        assert_eq!(store.project("p1", 3).unwrap().name, "v3");
+        assert_eq!(store.project("p1", 6).unwrap().name, "v6");
+
+        let result = store.prune("p1", None, Some(1_500), 6_000).unwrap();
+        assert_eq!(result, PruneHistoryResult { removed: 2, remaining: 2 });
+        assert_eq!(store.list("p1").unwrap().iter().map(|v| v.version).collect::<Vec<_>>(), vec![6, 5]);
+
+        let result = store.prune("p1", Some(0), Some(0), 1_000_000).unwrap();
+        assert_eq!(result.remaining, 1);
+        assert_eq!(store.project("p1", 6).unwrap().name, "v6");
+    }
+
+    #[test]
+    fn restore_is_recorded_even_without_changes() {
+        let dir = TempDir::new();
+        let store = HistoryStore::new(&dir.0);
+        save(&store, "v1", 1);
+        let restored = store.record(&project("v1"), None, None, VersionOrigin::Restore { version: 1 }, 2).unwrap();
+        assert_eq!(restored.unwrap().origin, VersionOrigin::Restore { version: 1 });
+        let origin = serde_json::to_value(VersionOrigin::Branch { project_id: "p1".into(), version: 1 }).unwrap();
+        assert_eq!(origin, json!({ "kind": "branch", "projectId": "p1", "version": 1 }));
+    }
+}
@@ -0,0 +1,193 @@
+//! 任务日志
+//!
+//! 每个任务（剪辑、预览、缩略图等）拥有一个日志文件 `<log_dir>/jobs/<job_id>.log`，
+//! 记录该任务中每次 ffmpeg/ffprobe 调用的完整命令行、耗时、退出状态和 stderr。
+//! 返回给前端的错误只包含 stderr 末尾几行与 `job_id`，完整内容通过 `get_job_log` 获取。
+
+use crate::error::{AppError, AppResult};
+use crate::fs_utils::new_id;
+use log::warn;
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 保留的任务日志数量上限
+const MAX_JOB_LOGS: usize = 200;
+
+static LOG_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+// 任务日志内容
+#[derive(Serialize, Debug, Clone)]
+pub struct JobLogContent {
+    pub job_id: String,
+    pub path: String,
+    pub content: String,
+}
+
+/// Set the directory job logs are written to (the app log dir) and prune old logs.
+pub fn init(log_dir: &Path) {
+    let dir = log_dir.join("jobs");
+    if let Err(e) = fs::create_dir_all(&dir) {
+        warn!("创建任务日志目录失败: {} ({})", dir.display(), e);
+    }
+    prune(&dir);
+    if let Ok(mut current) = LOG_DIR.write() {
+        *current = Some(dir);
+    }
+}
+
+/// Directory holding job logs; falls back to the system temp dir before [`init`] is called.
+pub fn log_dir() -> PathBuf {
+    LOG_DIR
+        .read()
+        .ok()
+        .and_then(|d| d.clone())
+        .unwrap_or_else(|| std::env::temp_dir().join("mangaai-logs").join("jobs"))
+}
+
+fn prune(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    let mut logs: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "log"))
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect();
+    if logs.len() <= MAX_JOB_LOGS {
+        return;
+    }
+    logs.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    for (_, path) in logs.into_iter().skip(MAX_JOB_LOGS) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+fn is_valid_job_id(job_id: &str) -> bool {
+    !job_id.is_empty()
+        && job_id.len() <= 128
+        && job_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Quote an argument for display so the logged command line can be copied into a shell.
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=,%+@".contains(c))
+    {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// A shell-quoted command line that can be copied and replayed.
+pub fn command_line(program: &str, args: &[&str]) -> String {
+    std::iter::once(shell_quote(program))
+        .chain(args.iter().map(|a| shell_quote(a)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The log file for a single job.
+#[derive(Debug, Clone)]
+pub struct JobLog {
+    job_id: String,
+    path: PathBuf,
+}
+
+impl JobLog {
+    pub fn create(kind: &str) -> JobLog {
+        let job_id = format!("{}_{}", kind, new_id());
+        let dir = log_dir();
+        let _ = fs::create_dir_all(&dir);
+        let path = dir.join(format!("{}.log", job_id));
+        JobLog { job_id, path }
+    }
+
+    pub fn job_id(&self) -> &str {
+        &self.job_id
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a free-form line to the log.
+    pub fn note(&self, message: &str) {
+        self.append(&format!("# {}\n", message));
+    }
+
+    /// Append one process invocation with its timing, exit status and full stderr.
+    pub fn record(
+        &self,
+        program: &str,
+        stage: &str,
+        args: &[&str],
+        elapsed: Duration,
+        status: Option<ExitStatus>,
+        stderr: &[u8],
+    ) {
+        let started = SystemTime::now()
+            .checked_sub(elapsed)
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let command_line = command_line(program, args);
+        let status = match status {
+            Some(s) => match s.code() {
+                Some(code) => code.to_string(),
+                None => "terminated by signal".to_string(),
+            },
+            None => "failed to start".to_string(),
+        };
+
+        let mut entry = String::new();
+        entry.push_str(&format!("=== {} [{}] started_at={}\n", program, stage, started));
+        entry.push_str(&format!("$ {}\n", command_line));
+        entry.push_str(&format!("exit: {}  duration: {:.3}s\n", status, elapsed.as_secs_f64()));
+        if !stderr.is_empty() {
+            entry.push_str("--- stderr ---\n");
+            entry.push_str(&String::from_utf8_lossy(stderr));
+            if !entry.ends_with('\n') {
+                entry.push('\n');
+            }
+        }
+        entry.push('\n');
+        self.append(&entry);
+    }
+
+    fn append(&self, text: &str) {
+        let result = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut f| f.write_all(text.as_bytes()));
+        if let Err(e) = result {
+            warn!("写入任务日志失败: {} ({})", self.path.display(), e);
+        }
+    }
+}
+
+/// 获取任务的完整 ffmpeg 日志
+#[tauri::command]
+pub fn get_job_log(job_id: String) -> AppResult<JobLogContent> {
+    if !is_valid_job_id(&job_id) {
+        return Err(AppError::invalid_argument("job_id", "malformed job id"));
+    }
+    let path = log_dir().join(format!("{}.log", job_id));
+    if !path.exists() {
+        return Err(AppError::NotFound {
+            path: path.to_string_lossy().to_string(),
+        });
+    }
+    let content = fs::read_to_string(&path).map_err(|e| AppError::io("read_file", Some(&path), e))?;
+    Ok(JobLogContent {
+        job_id,
+        path: path.to_string_lossy().to_string(),
+        content,
+    })
+}
@@ -0,0 +1,281 @@
+//! 媒体分析命令：元数据、关键帧、缩略图与 FFmpeg 检测
+
+use crate::backend::{FfmpegBackend, MediaBackend};
+use crate::compat;
+use crate::error::{AppError, AppResult};
+use crate::ffmpeg::is_ffmpeg_installed;
+use crate::fs_utils::AtomicOutput;
+use crate::media_scope;
+use crate::temp_workspace::TempSession;
+use crate::toolchain;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// 视频元数据
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VideoMetadata {
+    pub duration: f64,
+    pub width: u32,
+    pub height: u32,
+    pub fps: f64,
+    pub codec: String,
+    pub bitrate: u32,
+}
+
+/// 分析视频文件获取元数据
+#[tauri::command]
+pub fn analyze_video(path: String) -> AppResult<VideoMetadata> {
+    info!("分析视频: {}", path);
+    media_scope::check(&path)?;
+
+    probe_video(&FfmpegBackend::new("probe")?, &path)
+}
+
+// 工具函数: 使用ffprobe读取视频元数据
+pub fn probe_video(backend: &dyn MediaBackend, path: &str) -> AppResult<VideoMetadata> {
+    let stdout = backend.ffprobe(path, &[
+        "-v", "quiet",
+        "-print_format", "json",
+        "-show_format",
+        "-show_streams",
+    ])?;
+
+    let json_value: serde_json::Value = serde_json::from_slice(&stdout)?;
+
+    let probe_failed = |reason: &str| AppError::ProbeFailed {
+        path: path.to_string(),
+        reason: reason.to_string(),
+        log_id: backend.job_id().map(str::to_string),
+    };
+    let streams = json_value["streams"].as_array().ok_or_else(|| probe_failed("no streams"))?;
+    let video_stream = streams.iter()
+        .find(|s| s["codec_type"].as_str().unwrap_or("") == "video")
+        .ok_or_else(|| probe_failed("no video stream"))?;
+
+    let width = video_stream["width"].as_u64().unwrap_or(0) as u32;
+    let height = video_stream["height"].as_u64().unwrap_or(0) as u32;
+
+    let fps_str = video_stream["r_frame_rate"].as_str().unwrap_or("0/1");
+    let fps = parse_fps(fps_str);
+
+    let codec = video_stream["codec_name"].as_str().unwrap_or("unknown").to_string();
+
+    let format = &json_value["format"];
+    let duration = format["duration"].as_str().unwrap_or("0")
+        .parse::<f64>().unwrap_or(0.0);
+
+    let bitrate = format["bit_rate"].as_str().unwrap_or("0")
+        .parse::<u32>().unwrap_or(0);
+
+    Ok(VideoMetadata {
+        duration,
+        width,
+        height,
+        fps,
+        codec,
+        bitrate,
+    })
+}
+
+/// Container duration in seconds; unlike [`probe_video`] this works for audio-only files.
+pub fn probe_duration(backend: &dyn MediaBackend, path: &str) -> AppResult<f64> {
+    let stdout = backend.ffprobe(path, &["-v", "quiet", "-print_format", "json", "-show_format"])?;
+    let json_value: serde_json::Value = serde_json::from_slice(&stdout)?;
+    json_value["format"]["duration"]
+        .as_str()
+        .and_then(|d| d.parse::<f64>().ok())
+        .ok_or_else(|| AppError::ProbeFailed {
+            path: path.to_string(),
+            reason: "no duration".to_string(),
+            log_id: backend.job_id().map(str::to_string),
+        })
+}
+
+/// 从视频中提取关键帧
+#[tauri::command]
+pub fn extract_key_frames(path: String, count: u32) -> AppResult<Vec<String>> {
+    media_scope::check(&path)?;
+    key_frames_with(&FfmpegBackend::new("keyframes")?, &path, count)
+}
+
+/// Grab `count` evenly spaced frames from `path` against an arbitrary backend.
+pub fn key_frames_with(backend: &dyn MediaBackend, path: &str, count: u32) -> AppResult<Vec<String>> {
+    info!("提取关键帧: {}, 数量: {}", path, count);
+
+    let metadata = probe_video(backend, path)?;
+    let duration = metadata.duration;
+
+    let session = TempSession::create("keyframes")?;
+
+    let mut frame_positions = Vec::new();
+    let segment = duration / (count as f64 + 1.0);
+
+    for i in 1..=count {
+        let position = segment * (i as f64);
+        frame_positions.push(position);
+    }
+
+    let mut frame_paths = Vec::new();
+
+    for (i, &position) in frame_positions.iter().enumerate() {
+        let output_path = session.path(&format!("frame_{}.jpg", i+1));
+        let output = AtomicOutput::new(&output_path)?;
+        let output_str = output.temp_path_str();
+
+        backend.ffmpeg("keyframe", &[
+            "-ss", &format!("{}", position),
+            "-i", path,
+            "-vframes", "1",
+            "-q:v", "2",
+            "-f", "image2",
+            &output_str
+        ])?;
+        output.commit()?;
+
+        frame_paths.push(output_path.to_string_lossy().to_string());
+    }
+
+    Ok(frame_paths)
+}
+
+/// 生成视频缩略图
+#[tauri::command]
+pub fn generate_thumbnail(path: String) -> AppResult<String> {
+    media_scope::check(&path)?;
+    thumbnail_with(&FfmpegBackend::new("thumbnail")?, &path)
+}
+
+/// Render a 320px-wide thumbnail of `path` against an arbitrary backend.
+pub fn thumbnail_with(backend: &dyn MediaBackend, path: &str) -> AppResult<String> {
+    info!("生成缩略图: {}", path);
+
+    // 取 15% 处的画面，避开片头黑场
+    let position = probe_video(backend, path)?.duration * 0.15;
+    let session = TempSession::create("thumbnail")?;
+    let thumbnail_path = session.unique_file("thumb", "jpg");
+    let output = AtomicOutput::new(&thumbnail_path)?;
+    let thumbnail_str = output.temp_path_str();
+
+    backend.ffmpeg("thumbnail", &[
+        "-ss", &position.to_string(),
+        "-i", path,
+        "-vframes", "1",
+        "-vf", "scale=320:-1",
+        "-q:v", "2",
+        "-f", "image2",
+        &thumbnail_str
+    ])?;
+    output.commit()?;
+
+    Ok(thumbnail_path.to_string_lossy().to_string())
+}
+
+/// 检查FFmpeg是否已安装
+#[tauri::command]
+pub fn check_ffmpeg() -> AppResult<HashMap<String, serde_json::Value>> {
+    let mut result = HashMap::new();
+
+    let is_installed = is_ffmpeg_installed();
+    result.insert("installed".to_string(), serde_json::Value::Bool(is_installed));
+
+    if is_installed {
+        if let Ok(toolchain) = toolchain::resolve() {
+            result.insert("ffmpegPath".to_string(), serde_json::Value::String(toolchain.ffmpeg.path.to_string_lossy().to_string()));
+            result.insert("ffprobePath".to_string(), serde_json::Value::String(toolchain.ffprobe.path.to_string_lossy().to_string()));
+        }
+        if let Ok(output) = toolchain::ffmpeg_command().and_then(|mut cmd| {
+            cmd.arg("-version").output().map_err(|e| AppError::io("spawn", None, e))
+        }) {
+            if output.status.success() {
+                let version_str = String::from_utf8_lossy(&output.stdout);
+                let first_line = version_str.lines().next().unwrap_or("");
+                result.insert("version".to_string(), serde_json::Value::String(first_line.to_string()));
+            }
+        }
+        // 版本号、编译选项与各功能的兼容性，供首次运行向导展示
+        match compat::report() {
+            Ok(report) => {
+                result.insert("compatibility".to_string(), serde_json::to_value(report)?);
+            }
+            Err(e) => warn!("生成 FFmpeg 兼容性报告失败: {}", e),
+        }
+    }
+
+    Ok(result)
+}
+
+// 工具函数: 解析FFmpeg帧率字符串
+fn parse_fps(fps_str: &str) -> f64 {
+    let parts: Vec<&str> = fps_str.split('/').collect();
+    if parts.len() == 2 {
+        let numerator = parts[0].parse::<f64>().unwrap_or(0.0);
+        let denominator = parts[1].parse::<f64>().unwrap_or(1.0);
+        if denominator > 0.0 {
+            return numerator / denominator;
+        }
+    }
+    0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{RecordingBackend, Tool};
+
+    const PROBE_OUTPUT: &str = r#"{
+        "streams": [
+            {"codec_type": "audio", "codec_name": "aac"},
+            {"codec_type": "video", "codec_name": "h264", "width": 1280, "height": 720, "r_frame_rate": "30000/1001"}
+        ],
+        "format": {"duration": "12.500000", "bit_rate": "2500000"}
+    }"#;
+
+    #[test]
+    fn fps_strings() {
+        assert_eq!(parse_fps("25/1"), 25.0);
+        assert!((parse_fps("30000/1001") - 29.97).abs() < 0.01);
+        assert_eq!(parse_fps("0/0"), 0.0);
+        assert_eq!(parse_fps("garbage"), 0.0);
+    }
+
+    #[test]
+    fn probe_reads_video_stream_and_format() {
+        let backend = RecordingBackend::new().with_probe_output(PROBE_OUTPUT);
+        let meta = probe_video(&backend, "clip.mp4").unwrap();
+        assert_eq!((meta.width, meta.height), (1280, 720));
+        assert_eq!(meta.codec, "h264");
+        assert_eq!(meta.duration, 12.5);
+        assert_eq!(meta.bitrate, 2_500_000);
+
+        let calls = backend.calls();
+        assert_eq!(calls[0].tool, Tool::Ffprobe);
+        assert_eq!(calls[0].args.last().map(String::as_str), Some("clip.mp4"));
+    }
+
+    #[test]
+    fn probe_without_video_stream_fails() {
+        let backend = RecordingBackend::new()
+            .with_probe_output(r#"{"streams": [{"codec_type": "audio"}], "format": {}}"#);
+        let err = probe_video(&backend, "song.mp3").unwrap_err();
+        assert!(matches!(err, AppError::ProbeFailed { ref reason, .. } if reason == "no video stream"));
+    }
+
+    #[test]
+    fn key_frames_are_evenly_spaced() {
+        let backend = RecordingBackend::new().with_probe_output(PROBE_OUTPUT).creating_outputs();
+        let frames = key_frames_with(&backend, "clip.mp4", 4).unwrap();
+        assert_eq!(frames.len(), 4);
+        if let Some(dir) = std::path::Path::new(&frames[0]).parent() {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+
+        let positions: Vec<String> = backend
+            .calls()
+            .into_iter()
+            .filter(|c| c.stage == "keyframe")
+            .map(|c| c.args[1].clone())
+            .collect();
+        assert_eq!(positions, ["2.5", "5", "7.5", "10"]);
+    }
+}
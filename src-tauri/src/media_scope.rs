@@ -0,0 +1,355 @@
+//! 媒体访问范围
+//!
+//! 来自前端的媒体路径只有落在以下范围内才会交给 ffmpeg 或系统程序处理：
+//! - 用户通过对话框插件选择的文件与目录（监听 fs 作用域的 `PathAllowed` 事件记录）
+//! - 拖放到窗口上的文件与目录
+//! - 后端自己创建的媒体目录（素材库对象目录、导入项目包解压出的媒体）
+//! - 临时工作区
+//!
+//! 选择与拖放加入的路径保存在应用配置目录的 `media_scope.json` 中，重启后恢复，
+//! 恢复时同样经过下面的范围检查。
+//!
+//! 项目 JSON 由前端写入，不可信，其中的素材路径从不用于扩大范围。文件系统根目录与
+//! 用户主目录（及其上级）即使被选中也不会加入范围。`open_file` 另外只允许打开已知的
+//! 输出文件类型。
+
+use crate::asset_store::{self, AssetStore};
+use crate::bundle;
+use crate::error::{AppError, AppResult};
+use crate::fs_utils;
+use crate::project;
+use crate::temp_workspace;
+use log::{info, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::scope::fs::Event;
+use tauri::{AppHandle, DragDropEvent, Manager, WindowEvent};
+use tauri_plugin_fs::FsExt;
+
+/// `open_file` 允许交给系统默认程序打开的扩展名（导出视频、图片、字幕与文档）
+const OPENABLE_EXTENSIONS: &[&str] = &[
+    "mp4", "mov", "mkv", "webm", "gif", "mp3", "wav", "m4a", "aac", "png", "jpg", "jpeg",
+    "webp", "srt", "ass", "vtt", "pdf", "txt",
+];
+
+/// 保存已允许路径的文件名（位于应用配置目录）
+const SCOPE_FILE: &str = "media_scope.json";
+
+// 允许访问的路径
+static SCOPE: Mutex<MediaScope> = Mutex::new(MediaScope { roots: Vec::new() });
+
+// 范围变化时写入的文件，启动恢复后设置
+static SCOPE_FILE_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Canonical files and directories media commands may touch.
+#[derive(Debug, Default)]
+pub struct MediaScope {
+    roots: Vec<PathBuf>,
+}
+
+impl MediaScope {
+    /// Allow `path` (a file, or a directory and everything below it).
+    /// Returns false when it was already covered, cannot be resolved or is too broad to allow.
+    pub fn allow(&mut self, path: &Path) -> bool {
+        let Some(canonical) = canonicalize_lenient(path) else { return false };
+        if too_broad(&canonical) {
+            warn!("拒绝将过大的目录加入媒体访问范围: {}", canonical.display());
+            return false;
+        }
+        if self.roots.iter().any(|root| canonical.starts_with(root)) {
+            return false;
+        }
+        self.roots.push(canonical);
+        true
+    }
+
+    /// Whether the already canonical `path` lies inside an allowed root or the temp workspace.
+    pub fn permits(&self, path: &Path) -> bool {
+        let temp_root = temp_workspace::workspace_root();
+        let temp_root = temp_root.canonicalize().unwrap_or(temp_root);
+        path.starts_with(&temp_root) || self.roots.iter().any(|root| path.starts_with(root))
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    std::env::var_os(var).and_then(|home| PathBuf::from(home).canonicalize().ok())
+}
+
+/// Whether the canonical `path` is a filesystem root, the home directory or one of its ancestors.
+fn too_broad(path: &Path) -> bool {
+    path.parent().is_none() || home_dir().is_some_and(|home| home.starts_with(path))
+}
+
+/// Canonicalize `path`, or its parent when the file does not exist yet (output paths).
+fn canonicalize_lenient(path: &Path) -> Option<PathBuf> {
+    if let Ok(canonical) = path.canonicalize() {
+        return Some(canonical);
+    }
+    let parent = path.parent()?.canonicalize().ok()?;
+    Some(parent.join(path.file_name()?))
+}
+
+fn with_scope<T>(f: impl FnOnce(&mut MediaScope) -> T) -> T {
+    let mut scope = SCOPE.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut scope)
+}
+
+/// Read saved roots; a missing or unreadable file means none.
+fn read_roots(file: &Path) -> Vec<PathBuf> {
+    let Ok(content) = fs::read_to_string(file) else { return Vec::new() };
+    serde_json::from_str(&content).unwrap_or_else(|e| {
+        warn!("媒体访问范围文件无法解析，已忽略: {} ({})", file.display(), e);
+        Vec::new()
+    })
+}
+
+fn write_roots(file: &Path, roots: &[PathBuf]) -> AppResult<()> {
+    fs_utils::atomic_write(file, serde_json::to_string_pretty(roots)?.as_bytes())
+}
+
+/// Allow every root saved in `file`, re-checking each one. Returns how many were allowed.
+fn restore_in(scope: &mut MediaScope, file: &Path) -> usize {
+    read_roots(file).iter().filter(|root| scope.allow(root)).count()
+}
+
+/// Allow `path` and save the scope so it survives a restart.
+pub fn allow(path: &Path) {
+    let allowed = with_scope(|scope| {
+        if !scope.allow(path) {
+            return false;
+        }
+        // 在持有锁时写入，保证文件内容与最新范围一致
+        if let Some(file) = SCOPE_FILE_PATH.lock().unwrap_or_else(|e| e.into_inner()).as_deref() {
+            if let Err(e) = write_roots(file, &scope.roots) {
+                warn!("保存媒体访问范围失败: {}", e);
+            }
+        }
+        true
+    });
+    if allowed {
+        info!("已允许访问媒体路径: {}", path.display());
+    }
+}
+
+/// Restore the scope at startup: roots saved by earlier runs, plus the media directories
+/// the backend creates under app data, so saved and relinked projects keep working.
+pub fn allow_backend_dirs(app_handle: &AppHandle) {
+    match app_handle.path().app_config_dir() {
+        Ok(config_dir) => {
+            let file = config_dir.join(SCOPE_FILE);
+            let restored = with_scope(|scope| restore_in(scope, &file));
+            info!("已恢复 {} 个媒体访问路径", restored);
+            *SCOPE_FILE_PATH.lock().unwrap_or_else(|e| e.into_inner()) = Some(file);
+        }
+        Err(e) => warn!("无法定位应用配置目录，媒体访问范围不会保存: {}", e),
+    }
+    let Ok(data_dir) = project::app_data_dir(app_handle) else { return };
+    let objects = AssetStore::new(data_dir.join(asset_store::STORE_DIR)).objects_dir();
+    for dir in [objects, data_dir.join(bundle::MEDIA_DIR)] {
+        if dir.is_dir() {
+            allow(&dir);
+        }
+    }
+}
+
+/// Record files and folders the user picks through the dialog plugin.
+///
+/// The dialog plugin adds every pick to the fs plugin's scope, so listening for
+/// `PathAllowed` catches open and save dialogs alike.
+pub fn watch_dialog_picks(app_handle: &AppHandle) {
+    app_handle.fs_scope().listen(|event| {
+        if let Event::PathAllowed(path) = event {
+            allow(path);
+        }
+    });
+}
+
+/// Allow files and folders dropped onto a window; the frontend passes them straight to media commands.
+pub fn handle_window_event(event: &WindowEvent) {
+    if let WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) = event {
+        for path in paths {
+            allow(path);
+        }
+    }
+}
+
+fn check_in(scope: &MediaScope, path: &str) -> AppResult<PathBuf> {
+    let canonical = PathBuf::from(path).canonicalize().map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            AppError::NotFound { path: path.to_string() }
+        } else {
+            AppError::InvalidPath { path: path.to_string(), reason: e.to_string() }
+        }
+    })?;
+    if !scope.permits(&canonical) {
+        return Err(AppError::PathNotAllowed { path: path.to_string() });
+    }
+    Ok(canonical)
+}
+
+fn check_output_in(scope: &MediaScope, path: &str) -> AppResult<PathBuf> {
+    let canonical = canonicalize_lenient(Path::new(path)).ok_or_else(|| AppError::InvalidPath {
+        path: path.to_string(),
+        reason: "parent directory does not exist".to_string(),
+    })?;
+    if !scope.permits(&canonical) {
+        return Err(AppError::PathNotAllowed { path: path.to_string() });
+    }
+    Ok(canonical)
+}
+
+fn check_openable_in(scope: &MediaScope, path: &str) -> AppResult<PathBuf> {
+    let canonical = check_in(scope, path)?;
+    let extension = canonical
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    if !OPENABLE_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(AppError::invalid_argument("path", format!("cannot open .{} files", extension)));
+    }
+    Ok(canonical)
+}
+
+/// Check that an existing media file or directory is in scope.
+pub fn check(path: &str) -> AppResult<PathBuf> {
+    with_scope(|scope| check_in(scope, path))
+}
+
+/// Check that an output path, which may not exist yet, is in scope.
+pub fn check_output(path: &str) -> AppResult<PathBuf> {
+    with_scope(|scope| check_output_in(scope, path))
+}
+
+/// Check that `path` is in scope and of a type `open_file` may launch.
+pub fn check_openable(path: &str) -> AppResult<PathBuf> {
+    with_scope(|scope| check_openable_in(scope, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn not_allowed(result: AppResult<PathBuf>) -> bool {
+        matches!(result, Err(AppError::PathNotAllowed { .. }))
+    }
+
+    #[test]
+    fn picked_file_allows_only_that_file() {
+        let dir = TempDir::new();
+        let picked = dir.write("picked.mp4", b"");
+        let sibling = dir.write("other.mp4", b"");
+        let mut scope = MediaScope::default();
+        assert!(not_allowed(check_in(&scope, &picked)));
+
+        scope.allow(Path::new(&picked));
+        assert!(check_in(&scope, &picked).is_ok());
+        assert!(not_allowed(check_in(&scope, &sibling)));
+    }
+
+    #[test]
+    fn allowed_directory_covers_descendants_but_not_traversal() {
+        let dir = TempDir::new();
+        fs::create_dir(dir.0.join("assets")).unwrap();
+        let inside = dir.write("assets/clip.mp4", b"");
+        let outside = dir.write("secret.mp4", b"");
+        let mut scope = MediaScope::default();
+        scope.allow(&dir.0.join("assets"));
+
+        assert!(check_in(&scope, &inside).is_ok());
+        assert!(not_allowed(check_in(&scope, &outside)));
+        let sneaky = dir.0.join("assets/../secret.mp4");
+        assert!(not_allowed(check_in(&scope, &sneaky.to_string_lossy())));
+    }
+
+    #[test]
+    fn outputs_may_not_exist_yet() {
+        let dir = TempDir::new();
+        let mut scope = MediaScope::default();
+        let output = dir.0.join("export.mp4").to_string_lossy().to_string();
+        assert!(not_allowed(check_output_in(&scope, &output)));
+
+        scope.allow(Path::new(&output));
+        assert!(check_output_in(&scope, &output).is_ok());
+        assert!(matches!(
+            check_output_in(&scope, &dir.0.join("missing/export.mp4").to_string_lossy()),
+            Err(AppError::InvalidPath { .. })
+        ));
+    }
+
+    #[test]
+    fn temp_workspace_is_always_allowed() {
+        let session = temp_workspace::TempSession::create("scope_test").unwrap();
+        let frame = session.path("frame.jpg");
+        fs::write(&frame, b"").unwrap();
+        assert!(check_in(&MediaScope::default(), &frame.to_string_lossy()).is_ok());
+        session.cleanup();
+    }
+
+    #[test]
+    fn open_is_limited_to_output_types() {
+        let dir = TempDir::new();
+        let mut scope = MediaScope::default();
+        scope.allow(&dir.0);
+
+        for name in ["out.mp4", "thumb.JPG", "subs.srt", "report.pdf"] {
+            assert!(check_openable_in(&scope, &dir.write(name, b"")).is_ok(), "{}", name);
+        }
+        for name in ["run.sh", "tool.exe", "page.html", "noext"] {
+            assert!(
+                matches!(check_openable_in(&scope, &dir.write(name, b"")), Err(AppError::InvalidArgument { .. })),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn roots_and_home_are_never_allowed() {
+        let mut scope = MediaScope::default();
+        let root = Path::new("/").canonicalize().unwrap();
+        assert!(!scope.allow(&root));
+        if let Some(home) = home_dir() {
+            assert!(!scope.allow(&home));
+            assert!(!scope.allow(home.parent().unwrap_or(&home)));
+        }
+        assert!(scope.roots.is_empty());
+    }
+
+    #[test]
+    fn saved_roots_are_rechecked_on_restore() {
+        let dir = TempDir::new();
+        fs::create_dir(dir.0.join("media")).unwrap();
+        let clip = dir.write("media/clip.mp4", b"");
+        let file = dir.0.join("config/media_scope.json");
+        let mut roots = vec![dir.0.join("media"), Path::new("/").canonicalize().unwrap()];
+        roots.extend(home_dir());
+        write_roots(&file, &roots).unwrap();
+
+        let mut scope = MediaScope::default();
+        assert_eq!(restore_in(&mut scope, &file), 1);
+        assert!(check_in(&scope, &clip).is_ok());
+        assert_eq!(scope.roots, [dir.0.join("media")]);
+
+        fs::write(&file, b"not json").unwrap();
+        assert_eq!(restore_in(&mut MediaScope::default(), &file), 0);
+        assert_eq!(restore_in(&mut MediaScope::default(), &dir.0.join("missing.json")), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_out_of_scope_is_rejected() {
+        let dir = TempDir::new();
+        let outside = TempDir::new();
+        let target = outside.write("private.mp4", b"");
+        fs::create_dir(dir.0.join("assets")).unwrap();
+        std::os::unix::fs::symlink(&target, dir.0.join("assets/link.mp4")).unwrap();
+        let mut scope = MediaScope::default();
+        scope.allow(&dir.0.join("assets"));
+
+        assert!(not_allowed(check_in(&scope, &dir.0.join("assets/link.mp4").to_string_lossy())));
+    }
+}
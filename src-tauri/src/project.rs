@@ -0,0 +1,446 @@
+//! 项目文件命令：应用数据目录下的项目读写与文件定位
+//!
+//! 项目 ID 只允许字母、数字、`_` 与 `-`；前端传入的目录经 [`fs_utils::resolve_within`]
+//! 限定在应用数据目录内，`..`、绝对路径以及指向目录外的符号链接都会被拒绝。
+
+use crate::autosave;
+use crate::checkpoint;
+use crate::error::{AppError, AppResult};
+use crate::export::{CutVideoParams, VideoSegment};
+use crate::fs_utils;
+use crate::history;
+use crate::media_scope;
+use crate::project_index;
+use crate::project_schema::{self, AssetKind, Project};
+use log::{info, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::{AppHandle, Manager};
+
+/// 项目文件所在的子目录（沿用旧版本目录名以兼容已有数据）
+const PROJECTS_DIR: &str = "blazecut";
+
+/// 项目 ID 等文件名键的最大长度
+const MAX_PROJECT_ID_LEN: usize = 128;
+
+pub(crate) fn app_data_dir(app_handle: &AppHandle) -> AppResult<PathBuf> {
+    app_handle.path().app_data_dir().map_err(|e| AppError::io("resolve_dir", None, e))
+}
+
+/// `<app_data>/blazecut`, where project JSON files are stored.
+pub(crate) fn projects_dir(app_handle: &AppHandle) -> AppResult<PathBuf> {
+    Ok(app_data_dir(app_handle)?.join(PROJECTS_DIR))
+}
+
+/// Reject IDs used as file names that are empty, too long, or contain anything but `[A-Za-z0-9_-]`.
+pub fn validate_id(field: &str, id: &str) -> AppResult<()> {
+    if id.is_empty() || id.len() > MAX_PROJECT_ID_LEN {
+        return Err(AppError::invalid_argument(field, format!("must be 1-{} characters", MAX_PROJECT_ID_LEN)));
+    }
+    if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(AppError::invalid_argument(field, "may only contain letters, digits, '_' and '-'"));
+    }
+    Ok(())
+}
+
+pub fn validate_project_id(project_id: &str) -> AppResult<()> {
+    validate_id("projectId", project_id)
+}
+
+/// Path of the JSON file for `project_id` inside `dir`, after validating the ID.
+pub fn project_file_in(dir: &Path, project_id: &str) -> AppResult<PathBuf> {
+    validate_project_id(project_id)?;
+    fs_utils::resolve_within(dir, &format!("{}.json", project_id))
+}
+
+pub(crate) fn project_file(app_handle: &AppHandle, project_id: &str) -> AppResult<PathBuf> {
+    project_file_in(&projects_dir(app_handle)?, project_id)
+}
+
+/// 检查并创建项目目录，返回其路径
+#[tauri::command]
+pub fn check_app_data_directory(app_handle: AppHandle) -> AppResult<String> {
+    let app_dir = projects_dir(&app_handle)?;
+
+    if !app_dir.exists() {
+        fs::create_dir_all(&app_dir).map_err(|e| AppError::io("create_dir", Some(&app_dir), e))?;
+    }
+
+    Ok(app_dir.to_string_lossy().into_owned())
+}
+
+/// 保存项目文件
+///
+/// 内容按当前结构版本校验（旧版本会先迁移），写入的始终是当前版本。
+/// 每次保存都会连同 `author`、`message` 记录到版本历史。
+#[tauri::command]
+pub fn save_project_file(
+    project_id: String,
+    content: String,
+    author: Option<String>,
+    message: Option<String>,
+    app_handle: AppHandle,
+) -> AppResult<()> {
+    validate_project_id(&project_id)?;
+    let project = project_schema::parse_project(&content)?;
+    if project.id != project_id {
+        return Err(AppError::InvalidProject {
+            path: "id".to_string(),
+            reason: format!("does not match project id {}", project_id),
+        });
+    }
+
+    let app_dir = projects_dir(&app_handle)?;
+
+    if !app_dir.exists() {
+        fs::create_dir_all(&app_dir).map_err(|e| AppError::io("create_dir", Some(&app_dir), e))?;
+    }
+
+    let file_path = project_file_in(&app_dir, &project_id)?;
+
+    autosave::write_saved(&file_path, &project)?;
+    project_index::record_saved(&app_handle, &project);
+    history::record_save(&app_handle, &project, author, message);
+
+    if !file_path.exists() {
+        return Err(AppError::NotFound { path: file_path.to_string_lossy().to_string() });
+    }
+
+    info!("项目已保存: {}", project_id);
+    Ok(())
+}
+
+/// 读取项目文件，旧版本文件会迁移到当前结构版本后返回
+#[tauri::command]
+pub fn load_project_file(project_id: String, app_handle: AppHandle) -> AppResult<Project> {
+    let file_path = project_file(&app_handle, &project_id)?;
+    let project = read_project(&file_path)?;
+    project_index::record_opened(&app_handle, &project_id);
+    Ok(project)
+}
+
+/// Read, migrate and validate a project file.
+pub fn read_project(path: &Path) -> AppResult<Project> {
+    if !path.is_file() {
+        return Err(AppError::NotFound { path: path.to_string_lossy().to_string() });
+    }
+    let content = fs::read_to_string(path).map_err(|e| AppError::io("read_file", Some(path), e))?;
+    let mut value: serde_json::Value = serde_json::from_str(&content)?;
+    let from = project_schema::migrate(&mut value)?;
+    if from < project_schema::CURRENT_SCHEMA_VERSION as u64 {
+        info!("项目文件已从版本 {} 迁移到 {}: {}", from, project_schema::CURRENT_SCHEMA_VERSION, path.display());
+    }
+    project_schema::load_value(value)
+}
+
+/// Every project in `dir`, failing on the first file that cannot be read; a missing directory holds none.
+pub fn read_all_projects_in(dir: &Path) -> AppResult<Vec<Project>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(AppError::io("read_dir", Some(dir), e)),
+    };
+    let mut projects = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| AppError::io("read_dir", Some(dir), e))?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            projects.push(read_project(&path)?);
+        }
+    }
+    Ok(projects)
+}
+
+/// IDs of the per-project subdirectories under `root`, as used by history and autosave.
+pub fn project_dirs_in(root: &Path) -> AppResult<Vec<String>> {
+    let entries = match fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(AppError::io("read_dir", Some(root), e)),
+    };
+    let mut ids = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| AppError::io("read_dir", Some(root), e))?;
+        let Some(id) = entry.file_name().to_str().map(str::to_string) else { continue };
+        if entry.path().is_dir() && validate_project_id(&id).is_ok() {
+            ids.push(id);
+        }
+    }
+    Ok(ids)
+}
+
+/// Validate `project` and atomically write it as pretty JSON.
+pub fn write_project(path: &Path, project: &Project) -> AppResult<()> {
+    project_schema::validate(project)?;
+    let content = serde_json::to_string_pretty(project)?;
+    fs_utils::atomic_write(path, content.as_bytes())
+}
+
+/// 列出应用数据目录下某个子目录中的文件
+///
+/// `directory` 必须是应用数据目录内的相对路径；目录不存在时返回空列表。
+#[tauri::command]
+pub fn list_app_data_files(directory: String, app_handle: AppHandle) -> AppResult<Vec<String>> {
+    list_files_within(&app_data_dir(&app_handle)?, &directory)
+}
+
+/// List file names in `directory` under `base`, confined by [`fs_utils::resolve_within`].
+pub fn list_files_within(base: &Path, directory: &str) -> AppResult<Vec<String>> {
+    let target_dir = fs_utils::resolve_within(base, directory)?;
+
+    if !target_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(&target_dir)
+        .map_err(|e| AppError::io("read_dir", Some(&target_dir), e))?;
+
+    let mut files = Vec::new();
+    for entry in entries {
+        match entry {
+            Ok(entry) => {
+                if let Some(file_name) = entry.file_name().to_str() {
+                    files.push(file_name.to_string());
+                }
+            },
+            Err(e) => return Err(AppError::io("read_dir", Some(&target_dir), e)),
+        }
+    }
+
+    Ok(files)
+}
+
+/// 删除项目文件
+#[tauri::command]
+pub fn delete_project_file(project_id: String, app_handle: AppHandle) -> AppResult<()> {
+    let file_path = project_file(&app_handle, &project_id)?;
+
+    if !file_path.exists() {
+        return Err(AppError::NotFound { path: file_path.to_string_lossy().to_string() });
+    }
+
+    fs::remove_file(&file_path).map_err(|e| AppError::io("remove_file", Some(&file_path), e))?;
+    project_index::record_deleted(&app_handle, &project_id);
+    if let Err(e) = autosave::store(&app_handle).and_then(|store| autosave::forget_project(&store, &project_id)) {
+        warn!("清除已删除项目的自动保存失败: {} ({})", project_id, e);
+    }
+    if let Err(e) = checkpoint::store(&app_handle).and_then(|store| store.clear(&project_id, None)) {
+        warn!("清除已删除项目的检查点失败: {} ({})", project_id, e);
+    }
+    info!("项目已删除: {}", project_id);
+    Ok(())
+}
+
+/// 获取应用数据路径
+#[tauri::command]
+pub fn get_app_data_path(app_handle: AppHandle) -> AppResult<String> {
+    let data_dir = app_data_dir(&app_handle)?;
+    Ok(data_dir.to_string_lossy().to_string())
+}
+
+/// 使用系统默认程序打开文件
+///
+/// 只允许打开媒体访问范围内的已知输出类型（视频、图片、字幕、文档）。
+#[tauri::command]
+pub fn open_file(path: String) -> AppResult<()> {
+    media_scope::check_openable(&path)?;
+
+    #[cfg(target_os = "windows")]
+    {
+        let status = Command::new("cmd")
+            .args(["/C", "start", "", &path])
+            .status()
+            .map_err(|e| AppError::io("spawn", None, e))?;
+        if !status.success() {
+            return Err(AppError::io("open", Some(std::path::Path::new(&path)), status));
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let status = Command::new("open")
+            .arg(&path)
+            .status()
+            .map_err(|e| AppError::io("spawn", None, e))?;
+        if !status.success() {
+            return Err(AppError::io("open", Some(std::path::Path::new(&path)), status));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let status = Command::new("xdg-open")
+            .arg(&path)
+            .status()
+            .map_err(|e| AppError::io("spawn", None, e))?;
+        if !status.success() {
+            return Err(AppError::io("open", Some(std::path::Path::new(&path)), status));
+        }
+    }
+
+    Ok(())
+}
+
+/// 在文件管理器中打开文件所在位置
+#[tauri::command]
+pub fn open_file_location(path: String) -> AppResult<()> {
+    media_scope::check(&path)?;
+    let path = PathBuf::from(&path);
+
+    let path_display = path.to_string_lossy().to_string();
+    let parent = if path.is_file() {
+        path.parent().map(|p| p.to_path_buf())
+    } else {
+        Some(path)
+    };
+
+    if let Some(dir) = parent {
+        #[cfg(target_os = "macos")]
+        {
+            Command::new("open").arg(dir).spawn().map_err(|e| AppError::io("spawn", None, e))?;
+        }
+        #[cfg(target_os = "windows")]
+        {
+            Command::new("explorer").arg(dir).spawn().map_err(|e| AppError::io("spawn", None, e))?;
+        }
+        #[cfg(target_os = "linux")]
+        {
+            Command::new("xdg-open").arg(dir).spawn().map_err(|e| AppError::io("spawn", None, e))?;
+        }
+        Ok(())
+    } else {
+        Err(AppError::InvalidPath {
+            path: path_display,
+            reason: "no parent directory".to_string(),
+        })
+    }
+}
+
+/// Build export parameters from a project.
+///
+/// Cuts the timeline clips taken from the video asset `video_id` (or the first video
+/// asset); clips without an asset belong to that video too. Quality, format and subtitles
+/// come from the project's export settings.
+pub fn cut_params_from_project(
+    project: &Project,
+    output_path: &str,
+    video_id: Option<&str>,
+) -> AppResult<CutVideoParams> {
+    let mut videos = project.assets.iter().filter(|a| a.kind == AssetKind::Video);
+    let video = match video_id {
+        Some(id) => videos.find(|a| a.id == id),
+        None => videos.next(),
+    }
+    .ok_or_else(|| AppError::invalid_argument("assets", "project has no matching video"))?;
+
+    let segments: Vec<VideoSegment> = project
+        .timeline
+        .clips
+        .iter()
+        .filter(|clip| clip.asset_id.as_deref().is_none_or(|id| id == video.id))
+        .map(|clip| VideoSegment {
+            start: clip.start_time,
+            end: clip.end_time,
+            segment_type: clip.kind.clone(),
+            content: clip.content.clone(),
+        })
+        .collect();
+    if segments.is_empty() {
+        return Err(AppError::NoSegments);
+    }
+
+    let settings = &project.export_settings;
+    Ok(CutVideoParams {
+        input_path: video.path.clone(),
+        output_path: output_path.to_string(),
+        segments,
+        quality: Some(settings.quality.clone()),
+        format: Some(settings.format.clone()),
+        transition: settings.transition.clone(),
+        transition_duration: None,
+        volume: None,
+        add_subtitles: Some(settings.subtitles_enabled),
+        dry_run: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn accepts_generated_project_ids() {
+        for id in ["proj_1", "0191f6a3c2b47d2e9f3a", "a-b_C", &fs_utils::new_id()] {
+            assert!(validate_project_id(id).is_ok(), "{}", id);
+        }
+    }
+
+    #[test]
+    fn rejects_traversal_in_project_ids() {
+        let dir = TempDir::new();
+        let long = "a".repeat(MAX_PROJECT_ID_LEN + 1);
+        for id in [
+            "", "..", "../secret", "..\\secret", "a/b", "/etc/passwd", "C:\\evil", "a.json",
+            "id\0", " id", "项目", &long,
+        ] {
+            assert!(
+                matches!(project_file_in(&dir.0, id), Err(AppError::InvalidArgument { .. })),
+                "{:?}",
+                id
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_project_file_symlinked_outside() {
+        let dir = TempDir::new();
+        let outside = TempDir::new();
+        fs::write(outside.0.join("target.json"), "{}").unwrap();
+        std::os::unix::fs::symlink(outside.0.join("target.json"), dir.0.join("linked.json")).unwrap();
+
+        assert!(matches!(project_file_in(&dir.0, "linked"), Err(AppError::PathNotAllowed { .. })));
+        assert_eq!(project_file_in(&dir.0, "plain").unwrap(), dir.0.join("plain.json"));
+    }
+
+    #[test]
+    fn listing_is_confined_to_base() {
+        let base = TempDir::new();
+        fs::create_dir(base.0.join(PROJECTS_DIR)).unwrap();
+        fs::write(base.0.join(PROJECTS_DIR).join("p1.json"), "{}").unwrap();
+
+        assert_eq!(list_files_within(&base.0, PROJECTS_DIR).unwrap(), vec!["p1.json"]);
+        assert!(list_files_within(&base.0, "missing").unwrap().is_empty());
+        assert!(!base.0.join("missing").exists());
+        for directory in ["..", "../..", "blazecut/../..", "/etc", "/"] {
+            assert!(
+                matches!(list_files_within(&base.0, directory), Err(AppError::PathNotAllowed { .. })),
+                "{}",
+                directory
+            );
+        }
+    }
+
+    #[test]
+    fn reading_all_projects_fails_on_any_bad_file() {
+        let dir = TempDir::new();
+        assert!(read_all_projects_in(&dir.0.join("missing")).unwrap().is_empty());
+
+        dir.write("p1.json", br#"{ "schemaVersion": 1, "id": "p1", "name": "ok" }"#);
+        dir.write("notes.txt", b"ignored");
+        assert_eq!(read_all_projects_in(&dir.0).unwrap().len(), 1);
+
+        dir.write("p2.json", b"{ not json");
+        assert!(read_all_projects_in(&dir.0).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn listing_rejects_symlinked_directories() {
+        let base = TempDir::new();
+        let outside = TempDir::new();
+        std::os::unix::fs::symlink(&outside.0, base.0.join("escape")).unwrap();
+        assert!(matches!(list_files_within(&base.0, "escape"), Err(AppError::PathNotAllowed { .. })));
+    }
+}
@@ -0,0 +1,491 @@
+//! 项目索引：首页列表与搜索用的项目概要
+//!
+//! `<app_data>/project_index.json` 为每个项目记录标题、封面、集数、时长、最近打开时间、
+//! 标签、状态，以及用于全文搜索的脚本文本。保存、删除项目时同步更新；查询前按项目文件的
+//! 修改时间核对一次，其他途径写入的项目（导入、还原等）也只重新解析变化的文件。
+
+use crate::error::{AppError, AppResult};
+use crate::fs_utils;
+use crate::project;
+use crate::project_schema::{AssetKind, Project, ProjectStatus};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+const INDEX_FILE: &str = "project_index.json";
+const INDEX_VERSION: u32 = 1;
+
+// 串行化对索引的读改写
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+// 项目概要
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectSummary {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub status: ProjectStatus,
+    /// 封面图：项目的 `coverImage`，没有时取第一张图片素材
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cover_image: Option<String>,
+    pub episode_count: usize,
+    /// 时长（秒）：时间线时长，时间线为空时取分镜时长之和
+    pub duration: f64,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+    /// 项目文件的修改时间（毫秒时间戳）
+    pub modified_at: u64,
+    /// 最近打开时间（毫秒时间戳）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_opened_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct IndexEntry {
+    #[serde(flatten)]
+    summary: ProjectSummary,
+    /// 小写的脚本、台词、分镜与角色文本
+    #[serde(default)]
+    search_text: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct IndexFile {
+    version: u32,
+    projects: BTreeMap<String, IndexEntry>,
+}
+
+// 排序字段
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ProjectSort {
+    #[default]
+    Modified,
+    Created,
+    Name,
+    LastOpened,
+    Duration,
+}
+
+// 查询条件，各项同时满足
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectQuery {
+    #[serde(default)]
+    pub status: Option<ProjectStatus>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// 按空白拆分的关键词，全部出现在标题、描述、标签或脚本文本中才匹配（不区分大小写）
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub sort: ProjectSort,
+    /// 默认降序
+    #[serde(default)]
+    pub ascending: bool,
+    #[serde(default)]
+    pub offset: Option<usize>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+// 查询结果，`total` 为分页前的匹配数
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectQueryResult {
+    pub total: usize,
+    pub projects: Vec<ProjectSummary>,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+fn modified_millis(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    modified.duration_since(UNIX_EPOCH).ok().map(|d| d.as_millis() as u64)
+}
+
+fn collect_text(value: &Value, keys: &[&str], out: &mut Vec<String>) {
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| collect_text(item, keys, out)),
+        Value::Object(map) => {
+            for (key, value) in map {
+                match value {
+                    Value::String(text) if keys.contains(&key.as_str()) => out.push(text.clone()),
+                    Value::Array(_) | Value::Object(_) => collect_text(value, keys, out),
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn entry_for(project: &Project, modified_at: u64, last_opened_at: Option<u64>) -> IndexEntry {
+    let extra = &project.extra;
+    let cover_image = extra
+        .get("coverImage")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or_else(|| project.assets.iter().find(|a| a.kind == AssetKind::Image).map(|a| a.path.clone()));
+    let tags = extra
+        .get("tags")
+        .and_then(Value::as_array)
+        .map(|tags| tags.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default();
+    let duration = if project.timeline.duration > 0.0 {
+        project.timeline.duration
+    } else {
+        project.storyboard.iter().map(|f| f.duration).sum()
+    };
+
+    let mut text = Vec::new();
+    if let Some(scripts) = extra.get("scripts") {
+        collect_text(scripts, &["title", "content", "notes"], &mut text);
+    }
+    if let Some(episodes) = extra.get("episodes") {
+        collect_text(episodes, &["title"], &mut text);
+    }
+    text.extend(project.timeline.clips.iter().filter_map(|c| c.content.clone()));
+    for frame in &project.storyboard {
+        text.push(frame.title.clone());
+        text.extend(frame.dialogue.clone());
+    }
+    text.extend(project.characters.iter().map(|c| c.name.clone()));
+
+    IndexEntry {
+        summary: ProjectSummary {
+            id: project.id.clone(),
+            name: project.name.clone(),
+            description: project.description.clone(),
+            status: project.status,
+            cover_image,
+            episode_count: extra.get("episodes").and_then(Value::as_array).map_or(0, Vec::len),
+            duration,
+            tags,
+            created_at: project.created_at.clone(),
+            updated_at: project.updated_at.clone(),
+            modified_at,
+            last_opened_at,
+        },
+        search_text: text.join("\n").to_lowercase(),
+    }
+}
+
+/// Project summaries for the projects in one directory, persisted as a single JSON file.
+pub struct ProjectIndex {
+    path: PathBuf,
+    projects_dir: PathBuf,
+}
+
+impl ProjectIndex {
+    pub fn new(path: impl Into<PathBuf>, projects_dir: impl Into<PathBuf>) -> ProjectIndex {
+        ProjectIndex { path: path.into(), projects_dir: projects_dir.into() }
+    }
+
+    fn load(&self) -> IndexFile {
+        let Ok(content) = fs::read_to_string(&self.path) else { return IndexFile::default() };
+        match serde_json::from_str::<IndexFile>(&content) {
+            Ok(index) if index.version == INDEX_VERSION => index,
+            Ok(_) => IndexFile::default(),
+            Err(e) => {
+                warn!("项目索引损坏，将重建: {} ({})", self.path.display(), e);
+                IndexFile::default()
+            }
+        }
+    }
+
+    fn save(&self, index: &mut IndexFile) -> AppResult<()> {
+        index.version = INDEX_VERSION;
+        fs_utils::atomic_write(&self.path, serde_json::to_string(index)?.as_bytes())
+    }
+
+    /// Run `f` against the index while holding the lock, saving it when `f` reports a change.
+    fn update<T>(&self, f: impl FnOnce(&mut IndexFile) -> AppResult<(T, bool)>) -> AppResult<T> {
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut index = self.load();
+        let (result, changed) = f(&mut index)?;
+        if changed {
+            self.save(&mut index)?;
+        }
+        Ok(result)
+    }
+
+    /// Re-read project files whose modification time differs from the index and drop deleted ones.
+    fn reconcile(&self, index: &mut IndexFile) -> bool {
+        let mut seen = BTreeMap::new();
+        if let Ok(entries) = fs::read_dir(&self.projects_dir) {
+            for path in entries.flatten().map(|e| e.path()) {
+                if path.extension().is_none_or(|ext| ext != "json") {
+                    continue;
+                }
+                let Some(id) = path.file_stem().and_then(|s| s.to_str()) else { continue };
+                if project::validate_project_id(id).is_ok() {
+                    seen.insert(id.to_string(), path.clone());
+                }
+            }
+        }
+
+        let before = index.projects.len();
+        index.projects.retain(|id, _| seen.contains_key(id));
+        let mut changed = index.projects.len() != before;
+        for (id, path) in seen {
+            let modified_at = modified_millis(&path).unwrap_or(0);
+            let current = index.projects.get(&id);
+            if current.is_some_and(|entry| entry.summary.modified_at == modified_at) {
+                continue;
+            }
+            let last_opened_at = current.and_then(|entry| entry.summary.last_opened_at);
+            match project::read_project(&path) {
+                Ok(project) => {
+                    index.projects.insert(id, entry_for(&project, modified_at, last_opened_at));
+                }
+                Err(e) => {
+                    warn!("跳过无法读取的项目文件: {} ({})", path.display(), e);
+                    index.projects.remove(&id);
+                }
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    /// Index a project that was just written to the projects directory.
+    pub fn upsert(&self, project: &Project) -> AppResult<()> {
+        let path = project::project_file_in(&self.projects_dir, &project.id)?;
+        let modified_at = modified_millis(&path).unwrap_or_else(now_millis);
+        self.update(|index| {
+            let last_opened_at = index.projects.get(&project.id).and_then(|e| e.summary.last_opened_at);
+            index.projects.insert(project.id.clone(), entry_for(project, modified_at, last_opened_at));
+            Ok(((), true))
+        })
+    }
+
+    pub fn remove(&self, project_id: &str) -> AppResult<()> {
+        self.update(|index| Ok(((), index.projects.remove(project_id).is_some())))
+    }
+
+    /// Record that a project was opened at `at`.
+    pub fn touch(&self, project_id: &str, at: u64) -> AppResult<()> {
+        self.update(|index| {
+            self.reconcile(index);
+            let entry = index
+                .projects
+                .get_mut(project_id)
+                .ok_or_else(|| AppError::NotFound { path: project_id.to_string() })?;
+            entry.summary.last_opened_at = Some(at);
+            Ok(((), true))
+        })
+    }
+
+    /// Discard the index and rebuild it from the project files, keeping last-opened times.
+    pub fn rebuild(&self) -> AppResult<usize> {
+        self.update(|index| {
+            for entry in index.projects.values_mut() {
+                entry.summary.modified_at = 0;
+            }
+            self.reconcile(index);
+            Ok((index.projects.len(), true))
+        })
+    }
+
+    pub fn query(&self, query: &ProjectQuery) -> AppResult<ProjectQueryResult> {
+        let projects = self.update(|index| {
+            let changed = self.reconcile(index);
+            Ok((index.projects.clone(), changed))
+        })?;
+        let terms: Vec<String> = query
+            .text
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect();
+        let mut matches: Vec<ProjectSummary> = projects
+            .into_values()
+            .filter(|e| query.status.is_none_or(|status| e.summary.status == status))
+            .filter(|e| query.tag.as_ref().is_none_or(|tag| e.summary.tags.contains(tag)))
+            .filter(|e| {
+                let s = &e.summary;
+                let haystack = format!(
+                    "{}\n{}\n{}\n{}",
+                    s.name.to_lowercase(),
+                    s.description.as_deref().unwrap_or_default().to_lowercase(),
+                    s.tags.join("\n").to_lowercase(),
+                    e.search_text
+                );
+                terms.iter().all(|term| haystack.contains(term.as_str()))
+            })
+            .map(|e| e.summary)
+            .collect();
+
+        matches.sort_by(|a, b| {
+            let ordering = match query.sort {
+                ProjectSort::Modified => a.modified_at.cmp(&b.modified_at),
+                ProjectSort::Created => a.created_at.cmp(&b.created_at),
+                ProjectSort::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+                ProjectSort::LastOpened => a.last_opened_at.cmp(&b.last_opened_at),
+                ProjectSort::Duration => a.duration.total_cmp(&b.duration),
+            };
+            let ordering = ordering.then_with(|| a.id.cmp(&b.id));
+            if query.ascending { ordering } else { ordering.reverse() }
+        });
+        let total = matches.len();
+        let projects = matches
+            .into_iter()
+            .skip(query.offset.unwrap_or(0))
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect();
+        Ok(ProjectQueryResult { total, projects })
+    }
+}
+
+fn index(app_handle: &AppHandle) -> AppResult<ProjectIndex> {
+    Ok(ProjectIndex::new(project::app_data_dir(app_handle)?.join(INDEX_FILE), project::projects_dir(app_handle)?))
+}
+
+/// Index a freshly saved project; failures are logged since the next query reconciles anyway.
+pub fn record_saved(app_handle: &AppHandle, project: &Project) {
+    if let Err(e) = index(app_handle).and_then(|index| index.upsert(project)) {
+        warn!("更新项目索引失败: {} ({})", project.id, e);
+    }
+}
+
+pub fn record_deleted(app_handle: &AppHandle, project_id: &str) {
+    if let Err(e) = index(app_handle).and_then(|index| index.remove(project_id)) {
+        warn!("更新项目索引失败: {} ({})", project_id, e);
+    }
+}
+
+pub fn record_opened(app_handle: &AppHandle, project_id: &str) {
+    if let Err(e) = index(app_handle).and_then(|index| index.touch(project_id, now_millis())) {
+        warn!("更新项目索引失败: {} ({})", project_id, e);
+    }
+}
+
+/// 查询项目列表，支持按状态、标签过滤，按标题与脚本文本全文搜索，以及排序和分页
+#[tauri::command]
+pub fn query_projects(query: Option<ProjectQuery>, app_handle: AppHandle) -> AppResult<ProjectQueryResult> {
+    index(&app_handle)?.query(&query.unwrap_or_default())
+}
+
+/// 重建项目索引，返回已索引的项目数
+#[tauri::command]
+pub fn rebuild_project_index(app_handle: AppHandle) -> AppResult<usize> {
+    let count = index(&app_handle)?.rebuild()?;
+    info!("项目索引已重建: {} 个项目", count);
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project_schema;
+    use crate::test_support::TempDir;
+    use serde_json::json;
+
+    fn fixture() -> TempDir {
+        let dir = TempDir::new();
+        fs::create_dir_all(dir.0.join("projects")).unwrap();
+        dir
+    }
+
+    fn index(dir: &TempDir) -> ProjectIndex {
+        ProjectIndex::new(dir.0.join(INDEX_FILE), dir.0.join("projects"))
+    }
+
+    fn save(dir: &TempDir, value: Value) -> Project {
+        let project = project_schema::load_value(value).unwrap();
+        project::write_project(&dir.0.join("projects").join(format!("{}.json", project.id)), &project).unwrap();
+        project
+    }
+
+    fn ids(result: &ProjectQueryResult) -> Vec<&str> {
+        result.projects.iter().map(|p| p.id.as_str()).collect()
+    }
+
+    #[test]
+    fn summarizes_project_metadata() {
+        let dir = fixture();
+        let project = save(&dir, json!({
+            "schemaVersion": 1, "id": "p1", "name": "雨夜",
+            "tags": ["悬疑"], "episodes": [{ "title": "第一集" }, { "title": "第二集" }],
+            "assets": [{ "id": "a1", "kind": "image", "path": "/media/cover.png" }],
+            "storyboard": [{ "id": "f1", "duration": 2.5 }, { "id": "f2", "duration": 1.5 }],
+        }));
+        let entry = entry_for(&project, 7, Some(3));
+        assert_eq!(entry.summary.cover_image.as_deref(), Some("/media/cover.png"));
+        assert_eq!(entry.summary.episode_count, 2);
+        assert_eq!(entry.summary.duration, 4.0);
+        assert_eq!(entry.summary.tags, vec!["悬疑"]);
+        assert_eq!(entry.summary.last_opened_at, Some(3));
+        assert!(entry.search_text.contains("第二集"));
+    }
+
+    #[test]
+    fn query_filters_searches_sorts_and_pages() {
+        let dir = fixture();
+        let index = index(&dir);
+        save(&dir, json!({
+            "schemaVersion": 1, "id": "a", "name": "Beta", "status": "completed",
+            "scripts": [{ "title": "开场", "content": "The Detective enters", "segments": [] }],
+        }));
+        save(&dir, json!({ "schemaVersion": 1, "id": "b", "name": "alpha", "tags": ["draft-cut"] }));
+        save(&dir, json!({ "schemaVersion": 1, "id": "c", "name": "Gamma", "timeline": { "duration": 90.0 } }));
+
+        let by_name = index.query(&ProjectQuery { sort: ProjectSort::Name, ascending: true, ..Default::default() });
+        assert_eq!(ids(&by_name.unwrap()), vec!["b", "a", "c"]);
+        let by_duration = index.query(&ProjectQuery { sort: ProjectSort::Duration, ..Default::default() }).unwrap();
+        assert_eq!(by_duration.projects[0].id, "c");
+
+        let text = |t: &str| index.query(&ProjectQuery { text: Some(t.into()), ..Default::default() }).unwrap();
+        assert_eq!(ids(&text("detective 开场")), vec!["a"]);
+        assert_eq!(ids(&text("detective gamma")), Vec::<&str>::new());
+        assert_eq!(ids(&text("DRAFT")), vec!["b"]);
+
+        let completed = index.query(&ProjectQuery { status: Some(ProjectStatus::Completed), ..Default::default() });
+        assert_eq!(ids(&completed.unwrap()), vec!["a"]);
+        let paged = index
+            .query(&ProjectQuery { sort: ProjectSort::Name, offset: Some(1), limit: Some(1), ..Default::default() })
+            .unwrap();
+        assert_eq!((paged.total, ids(&paged)), (3, vec!["a"]));
+    }
+
+    #[test]
+    fn stays_in_sync_with_project_files() {
+        let dir = fixture();
+        let index = index(&dir);
+        let project = save(&dir, json!({ "schemaVersion": 1, "id": "p1", "name": "Old" }));
+        index.upsert(&project).unwrap();
+        index.touch("p1", 42).unwrap();
+        assert!(matches!(index.touch("missing", 1), Err(AppError::NotFound { .. })));
+
+        // 绕过 upsert 直接改写文件，查询时按修改时间重新解析
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        save(&dir, json!({ "schemaVersion": 1, "id": "p1", "name": "New" }));
+        fs::write(dir.0.join("projects").join("broken.json"), "{").unwrap();
+        let result = index.query(&ProjectQuery::default()).unwrap();
+        assert_eq!(result.projects[0].name, "New");
+        assert_eq!(result.projects[0].last_opened_at, Some(42));
+        assert_eq!(result.total, 1);
+
+        fs::remove_file(dir.0.join("projects").join("p1.json")).unwrap();
+        index.remove("p1").unwrap();
+        assert_eq!(index.query(&ProjectQuery::default()).unwrap().total, 0);
+        assert_eq!(index.rebuild().unwrap(), 0);
+    }
+}
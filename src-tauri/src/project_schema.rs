@@ -0,0 +1,684 @@
+//! 项目文件结构定义与版本迁移
+//!
+//! 项目文件带有 `schemaVersion`。加载时先按版本依次执行 [`MIGRATIONS`] 中的迁移函数，
+//! 升级到 [`CURRENT_SCHEMA_VERSION`] 后再反序列化为 [`Project`] 并校验；出错时
+//! [`AppError::InvalidProject`] 中的 `path` 指向出错的 JSON 位置，如 `timeline.clips[3].endTime`。
+//! 尚未建模的前端字段（脚本、分析结果等）保存在各结构的 `extra` 中，读写时原样保留。
+//! 从旧版本迁移来的文件在校验前先修正不合规的值（见 [`repair_migrated`]），并逐条记录警告，
+//! 避免旧项目因新增的校验规则而无法打开。
+
+use crate::error::{AppError, AppResult};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+
+/// 当前项目文件结构版本
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+/// Migration `i` upgrades a project object from version `i` to `i + 1`.
+const MIGRATIONS: &[fn(&mut Map<String, Value>)] = &[migrate_v0_to_v1];
+
+// 项目状态
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectStatus {
+    #[default]
+    Draft,
+    Completed,
+    Archived,
+}
+
+// 素材类型
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetKind {
+    Video,
+    Image,
+    Audio,
+    Subtitle,
+    Other,
+}
+
+// 项目引用的媒体素材
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Asset {
+    pub id: String,
+    pub kind: AssetKind,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 时长（秒），图片为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// 低分辨率代理文件，编辑时代替原文件使用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_path: Option<String>,
+    /// 文件内容的 BLAKE3 校验值（十六进制）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// 时间线上的片段
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineClip {
+    pub id: String,
+    /// 片段取自的素材
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asset_id: Option<String>,
+    pub start_time: f64,
+    pub end_time: f64,
+    /// narration / dialogue / action / transition
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// 字幕/台词文本
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// 时间线
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Timeline {
+    #[serde(default)]
+    pub duration: f64,
+    #[serde(default)]
+    pub clips: Vec<TimelineClip>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// 角色
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Character {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// 分镜帧
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StoryboardFrame {
+    pub id: String,
+    #[serde(default)]
+    pub title: String,
+    /// 停留时长（秒）
+    pub duration: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_asset_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dialogue: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+fn default_volume() -> f64 {
+    1.0
+}
+
+// 音轨（配音、配乐、音效）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioTrack {
+    pub id: String,
+    pub asset_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 在时间线上的起始位置（秒）
+    #[serde(default)]
+    pub start_time: f64,
+    #[serde(default = "default_volume")]
+    pub volume: f64,
+    #[serde(default)]
+    pub muted: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// 导出设置
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportSettings {
+    pub format: String,
+    pub quality: String,
+    pub resolution: String,
+    pub frame_rate: u32,
+    pub subtitles_enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transition: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        ExportSettings {
+            format: "mp4".to_string(),
+            quality: "medium".to_string(),
+            resolution: "1080p".to_string(),
+            frame_rate: 30,
+            subtitles_enabled: false,
+            transition: None,
+            extra: Map::new(),
+        }
+    }
+}
+
+// 项目文件
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    pub schema_version: u32,
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub status: ProjectStatus,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub updated_at: String,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+    #[serde(default)]
+    pub timeline: Timeline,
+    #[serde(default)]
+    pub characters: Vec<Character>,
+    #[serde(default)]
+    pub storyboard: Vec<StoryboardFrame>,
+    #[serde(default)]
+    pub audio_tracks: Vec<AudioTrack>,
+    #[serde(default)]
+    pub export_settings: ExportSettings,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Project {
+    pub fn asset(&self, id: &str) -> Option<&Asset> {
+        self.assets.iter().find(|a| a.id == id)
+    }
+}
+
+fn invalid(path: impl Into<String>, reason: impl Into<String>) -> AppError {
+    AppError::InvalidProject { path: path.into(), reason: reason.into() }
+}
+
+/// Legacy `ProjectData` (no `schemaVersion`) to version 1.
+///
+/// `videos` become video assets, `settings` becomes `exportSettings`, and the segments of
+/// the script attached to the first video become timeline clips. `scripts` is kept as-is.
+fn migrate_v0_to_v1(project: &mut Map<String, Value>) {
+    let videos = match project.remove("videos") {
+        Some(Value::Array(videos)) => videos,
+        _ => Vec::new(),
+    };
+    let first_video_id = videos.first().and_then(|v| v.get("id")).cloned();
+    let mut assets = match project.remove("assets") {
+        Some(Value::Array(assets)) => assets,
+        _ => Vec::new(),
+    };
+    for mut video in videos {
+        if let Some(obj) = video.as_object_mut() {
+            obj.insert("kind".to_string(), json!("video"));
+        }
+        assets.push(video);
+    }
+    project.insert("assets".to_string(), Value::Array(assets));
+
+    if let Some(Value::Object(mut settings)) = project.remove("settings") {
+        let mut export = Map::new();
+        for (old, new) in [
+            ("outputFormat", "format"),
+            ("videoQuality", "quality"),
+            ("resolution", "resolution"),
+            ("frameRate", "frameRate"),
+            ("subtitleEnabled", "subtitlesEnabled"),
+        ] {
+            if let Some(value) = settings.remove(old) {
+                export.insert(new.to_string(), value);
+            }
+        }
+        export.extend(settings);
+        project.insert("exportSettings".to_string(), Value::Object(export));
+    }
+
+    if !project.contains_key("timeline") {
+        let scripts = project.get("scripts").and_then(Value::as_array);
+        let script = scripts.and_then(|scripts| {
+            scripts
+                .iter()
+                .find(|s| first_video_id.is_some() && s.get("videoId") == first_video_id.as_ref())
+                .or_else(|| scripts.first())
+        });
+        let asset_id = script.and_then(|s| s.get("videoId")).or(first_video_id.as_ref()).cloned();
+        let clips: Vec<Value> = script
+            .and_then(|s| s.get("segments"))
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+            .iter()
+            .map(|segment| {
+                let mut clip = segment.clone();
+                if let (Some(obj), Some(asset_id)) = (clip.as_object_mut(), &asset_id) {
+                    obj.insert("assetId".to_string(), asset_id.clone());
+                }
+                clip
+            })
+            .collect();
+        let duration = clips
+            .iter()
+            .filter_map(|c| c.get("endTime").and_then(Value::as_f64))
+            .fold(0.0, f64::max);
+        project.insert("timeline".to_string(), json!({ "duration": duration, "clips": clips }));
+    }
+}
+
+/// Read `schemaVersion`; files written before versioning have none and count as version 0.
+fn schema_version(project: &Map<String, Value>) -> AppResult<u64> {
+    match project.get("schemaVersion") {
+        None => Ok(0),
+        Some(value) => value
+            .as_u64()
+            .ok_or_else(|| invalid("schemaVersion", "expected a non-negative integer")),
+    }
+}
+
+/// Upgrade a raw project to [`CURRENT_SCHEMA_VERSION`], returning the version it started at.
+pub fn migrate(value: &mut Value) -> AppResult<u64> {
+    let project = value
+        .as_object_mut()
+        .ok_or_else(|| invalid("$", "expected a JSON object"))?;
+    let from = schema_version(project)?;
+    if from > CURRENT_SCHEMA_VERSION as u64 {
+        return Err(AppError::UnsupportedSchemaVersion { found: from, supported: CURRENT_SCHEMA_VERSION });
+    }
+    for migration in &MIGRATIONS[from as usize..] {
+        migration(project);
+    }
+    project.insert("schemaVersion".to_string(), json!(CURRENT_SCHEMA_VERSION));
+    Ok(from)
+}
+
+/// Deserialize an already migrated value, reporting the JSON path of the first mismatch.
+fn deserialize(value: Value) -> AppResult<Project> {
+    serde_path_to_error::deserialize(value).map_err(|e| {
+        let path = e.path().to_string();
+        let path = if path == "." { "$".to_string() } else { path };
+        invalid(path, e.into_inner().to_string())
+    })
+}
+
+/// Bring a project migrated from an older version in line with [`validate`].
+///
+/// Older files were never checked, so out-of-range values are clamped and entries that cannot
+/// be repaired (zero-length clips, duplicates, dangling references) are dropped, each with a warning.
+pub fn repair_migrated(project: &mut Project) {
+    let id = project.id.clone();
+    let fixed = |path: String, what: &str| warn!("迁移项目 {} 时修正 {}: {}", id, path, what);
+
+    if project.name.trim().is_empty() {
+        fixed("name".to_string(), "empty name replaced by the project id");
+        project.name = project.id.clone();
+    }
+
+    let mut asset_ids = HashSet::new();
+    let mut index = 0;
+    project.assets.retain_mut(|asset| {
+        let path = format!("assets[{}]", index);
+        index += 1;
+        if asset.path.trim().is_empty() || !asset_ids.insert(asset.id.clone()) {
+            fixed(path, "dropped asset with an empty path or duplicate id");
+            return false;
+        }
+        if asset.duration.is_some_and(|d| !d.is_finite() || d < 0.0) {
+            fixed(format!("{}.duration", path), "invalid duration cleared");
+            asset.duration = None;
+        }
+        true
+    });
+    let known = |id: &Option<String>| id.as_ref().is_none_or(|id| asset_ids.contains(id));
+
+    let mut index = 0;
+    project.timeline.clips.retain_mut(|clip| {
+        let path = format!("timeline.clips[{}]", index);
+        index += 1;
+        if !clip.start_time.is_finite() || clip.start_time < 0.0 {
+            fixed(format!("{}.startTime", path), "clamped to 0");
+            clip.start_time = 0.0;
+        }
+        if !clip.end_time.is_finite() || clip.end_time <= clip.start_time {
+            fixed(path, "dropped clip that ends before it starts");
+            return false;
+        }
+        if !known(&clip.asset_id) {
+            fixed(format!("{}.assetId", path), "unknown asset cleared");
+            clip.asset_id = None;
+        }
+        true
+    });
+
+    let mut index = 0;
+    project.storyboard.retain_mut(|frame| {
+        let path = format!("storyboard[{}]", index);
+        index += 1;
+        if !frame.duration.is_finite() || frame.duration <= 0.0 {
+            fixed(path, "dropped frame without a positive duration");
+            return false;
+        }
+        if !known(&frame.image_asset_id) {
+            fixed(format!("{}.imageAssetId", path), "unknown asset cleared");
+            frame.image_asset_id = None;
+        }
+        true
+    });
+
+    let mut index = 0;
+    project.audio_tracks.retain_mut(|track| {
+        let path = format!("audioTracks[{}]", index);
+        index += 1;
+        if !asset_ids.contains(&track.asset_id) {
+            fixed(path, "dropped track of an unknown asset");
+            return false;
+        }
+        if !track.start_time.is_finite() || track.start_time < 0.0 {
+            fixed(format!("{}.startTime", path), "clamped to 0");
+            track.start_time = 0.0;
+        }
+        if !track.volume.is_finite() || track.volume < 0.0 {
+            fixed(format!("{}.volume", path), "reset to 1");
+            track.volume = default_volume();
+        }
+        true
+    });
+
+    let mut character_ids = HashSet::new();
+    let mut index = 0;
+    project.characters.retain(|character| {
+        let path = format!("characters[{}]", index);
+        index += 1;
+        if !character_ids.insert(character.id.clone()) {
+            fixed(path, "dropped duplicate character id");
+            return false;
+        }
+        true
+    });
+
+    if project.export_settings.frame_rate == 0 {
+        fixed("exportSettings.frameRate".to_string(), "reset to the default");
+        project.export_settings.frame_rate = ExportSettings::default().frame_rate;
+    }
+}
+
+/// Check cross-field rules serde cannot express: unique IDs, time ranges and asset references.
+pub fn validate(project: &Project) -> AppResult<()> {
+    if project.id.trim().is_empty() {
+        return Err(invalid("id", "must not be empty"));
+    }
+    if project.name.trim().is_empty() {
+        return Err(invalid("name", "must not be empty"));
+    }
+
+    let mut asset_ids = HashSet::new();
+    for (i, asset) in project.assets.iter().enumerate() {
+        if !asset_ids.insert(asset.id.as_str()) {
+            return Err(invalid(format!("assets[{}].id", i), format!("duplicate asset id {}", asset.id)));
+        }
+        if asset.path.trim().is_empty() {
+            return Err(invalid(format!("assets[{}].path", i), "must not be empty"));
+        }
+        if asset.duration.is_some_and(|d| !d.is_finite() || d < 0.0) {
+            return Err(invalid(format!("assets[{}].duration", i), "must be a non-negative number"));
+        }
+    }
+    let check_asset = |path: String, id: &str| {
+        if asset_ids.contains(id) {
+            Ok(())
+        } else {
+            Err(invalid(path, format!("unknown asset {}", id)))
+        }
+    };
+
+    for (i, clip) in project.timeline.clips.iter().enumerate() {
+        if !clip.start_time.is_finite() || clip.start_time < 0.0 {
+            return Err(invalid(format!("timeline.clips[{}].startTime", i), "must be a non-negative number"));
+        }
+        if !clip.end_time.is_finite() || clip.end_time <= clip.start_time {
+            return Err(invalid(format!("timeline.clips[{}].endTime", i), "must be after startTime"));
+        }
+        if let Some(asset_id) = &clip.asset_id {
+            check_asset(format!("timeline.clips[{}].assetId", i), asset_id)?;
+        }
+    }
+
+    for (i, frame) in project.storyboard.iter().enumerate() {
+        if !frame.duration.is_finite() || frame.duration <= 0.0 {
+            return Err(invalid(format!("storyboard[{}].duration", i), "must be a positive number"));
+        }
+        if let Some(asset_id) = &frame.image_asset_id {
+            check_asset(format!("storyboard[{}].imageAssetId", i), asset_id)?;
+        }
+    }
+
+    for (i, track) in project.audio_tracks.iter().enumerate() {
+        check_asset(format!("audioTracks[{}].assetId", i), &track.asset_id)?;
+        if !track.start_time.is_finite() || track.start_time < 0.0 {
+            return Err(invalid(format!("audioTracks[{}].startTime", i), "must be a non-negative number"));
+        }
+        if !track.volume.is_finite() || track.volume < 0.0 {
+            return Err(invalid(format!("audioTracks[{}].volume", i), "must be a non-negative number"));
+        }
+    }
+
+    let mut character_ids = HashSet::new();
+    for (i, character) in project.characters.iter().enumerate() {
+        if !character_ids.insert(character.id.as_str()) {
+            return Err(invalid(format!("characters[{}].id", i), format!("duplicate character id {}", character.id)));
+        }
+    }
+
+    if project.export_settings.frame_rate == 0 {
+        return Err(invalid("exportSettings.frameRate", "must be greater than 0"));
+    }
+    Ok(())
+}
+
+/// Migrate, deserialize and validate a raw project value.
+pub fn load_value(mut value: Value) -> AppResult<Project> {
+    let from = migrate(&mut value)?;
+    let mut project = deserialize(value)?;
+    if from < CURRENT_SCHEMA_VERSION as u64 {
+        repair_migrated(&mut project);
+    }
+    validate(&project)?;
+    Ok(project)
+}
+
+/// Parse project JSON text of any supported version into the current model.
+pub fn parse_project(content: &str) -> AppResult<Project> {
+    load_value(serde_json::from_str(content)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy_project() -> Value {
+        json!({
+            "id": "p1",
+            "name": "第一集",
+            "status": "draft",
+            "videos": [
+                { "id": "v1", "path": "/media/a.mp4", "name": "a.mp4", "duration": 30.0, "width": 1920, "height": 1080, "fps": 30, "format": "mp4", "size": 1024, "createdAt": "2024-01-01" }
+            ],
+            "scripts": [
+                { "id": "s1", "title": "脚本", "content": "", "videoId": "v1", "segments": [
+                    { "id": "g1", "startTime": 0.0, "endTime": 4.5, "content": "开场", "type": "narration" },
+                    { "id": "g2", "startTime": 6.0, "endTime": 9.0, "content": "对白", "type": "dialogue" }
+                ] }
+            ],
+            "settings": { "videoQuality": "high", "outputFormat": "mov", "resolution": "1080p", "frameRate": 24, "subtitleEnabled": true, "audioCodec": "aac" },
+            "createdAt": "2024-01-01",
+            "updatedAt": "2024-01-02"
+        })
+    }
+
+    #[test]
+    fn legacy_projects_are_migrated() {
+        let project = load_value(legacy_project()).unwrap();
+        assert_eq!(project.schema_version, CURRENT_SCHEMA_VERSION);
+
+        assert_eq!(project.assets.len(), 1);
+        let video = &project.assets[0];
+        assert_eq!((video.kind, video.path.as_str()), (AssetKind::Video, "/media/a.mp4"));
+        assert_eq!(video.extra.get("fps"), Some(&json!(30)));
+
+        assert_eq!(project.timeline.clips.len(), 2);
+        assert_eq!(project.timeline.duration, 9.0);
+        let clip = &project.timeline.clips[1];
+        assert_eq!(clip.asset_id.as_deref(), Some("v1"));
+        assert_eq!(clip.kind.as_deref(), Some("dialogue"));
+        assert_eq!(clip.content.as_deref(), Some("对白"));
+
+        let export = &project.export_settings;
+        assert_eq!((export.format.as_str(), export.quality.as_str()), ("mov", "high"));
+        assert_eq!(export.frame_rate, 24);
+        assert!(export.subtitles_enabled);
+        assert_eq!(export.extra.get("audioCodec"), Some(&json!("aac")));
+
+        // 未建模的字段原样保留
+        assert!(project.extra.contains_key("scripts"));
+        assert!(!project.extra.contains_key("videos"));
+    }
+
+    #[test]
+    fn current_version_round_trips() {
+        let project = load_value(legacy_project()).unwrap();
+        let saved = serde_json::to_value(&project).unwrap();
+        assert_eq!(saved["schemaVersion"], json!(CURRENT_SCHEMA_VERSION));
+        assert_eq!(load_value(saved).unwrap(), project);
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let mut value = legacy_project();
+        value["schemaVersion"] = json!(CURRENT_SCHEMA_VERSION + 1);
+        assert_eq!(
+            load_value(value),
+            Err(AppError::UnsupportedSchemaVersion {
+                found: CURRENT_SCHEMA_VERSION as u64 + 1,
+                supported: CURRENT_SCHEMA_VERSION
+            })
+        );
+    }
+
+    fn error_path(value: Value) -> String {
+        match load_value(value) {
+            Err(AppError::InvalidProject { path, .. }) => path,
+            other => panic!("expected InvalidProject, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn type_errors_point_at_the_json_path() {
+        let mut value = legacy_project();
+        value["videos"][0]["duration"] = json!("long");
+        assert_eq!(error_path(value), "assets[0].duration");
+
+        let mut value = legacy_project();
+        value["schemaVersion"] = json!(1);
+        value["timeline"] = json!({ "clips": [{ "id": "c", "startTime": 0, "endTime": "x" }] });
+        assert_eq!(error_path(value), "timeline.clips[0].endTime");
+
+        assert_eq!(error_path(json!({ "schemaVersion": "one" })), "schemaVersion");
+        assert_eq!(error_path(json!([])), "$");
+    }
+
+    /// A current-version project with the same content as [`legacy_project`].
+    fn current_project() -> Value {
+        serde_json::to_value(load_value(legacy_project()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn validation_errors_point_at_the_json_path() {
+        let mut value = current_project();
+        value["timeline"]["clips"][1]["endTime"] = json!(5.0);
+        assert_eq!(error_path(value), "timeline.clips[1].endTime");
+
+        let mut value = current_project();
+        value["audioTracks"] = json!([{ "id": "t1", "assetId": "missing" }]);
+        assert_eq!(error_path(value), "audioTracks[0].assetId");
+
+        let mut value = current_project();
+        value["storyboard"] = json!([{ "id": "f1", "duration": 0 }]);
+        assert_eq!(error_path(value), "storyboard[0].duration");
+
+        let mut value = current_project();
+        let duplicate = value["assets"][0].clone();
+        value["assets"].as_array_mut().unwrap().push(duplicate);
+        assert_eq!(error_path(value), "assets[1].id");
+
+        let mut value = current_project();
+        value["name"] = json!(" ");
+        assert_eq!(error_path(value), "name");
+    }
+
+    #[test]
+    fn migrated_projects_are_repaired_instead_of_rejected() {
+        let mut value = legacy_project();
+        value["name"] = json!(" ");
+        value["scripts"][0]["segments"][0]["startTime"] = json!(-1.0);
+        value["scripts"][0]["segments"][1]["endTime"] = json!(5.0);
+        value["audioTracks"] = json!([{ "id": "t1", "assetId": "missing" }, { "id": "t2", "assetId": "v1", "volume": -2 }]);
+        value["storyboard"] = json!([{ "id": "f1", "duration": 0 }, { "id": "f2", "duration": 2, "imageAssetId": "gone" }]);
+        value["characters"] = json!([{ "id": "c", "name": "甲" }, { "id": "c", "name": "乙" }]);
+        value["settings"]["frameRate"] = json!(0);
+        let duplicate = value["videos"][0].clone();
+        value["videos"].as_array_mut().unwrap().push(duplicate);
+
+        let project = load_value(value).unwrap();
+        assert_eq!(project.name, "p1");
+        assert_eq!(project.assets.len(), 1);
+        assert_eq!(project.timeline.clips.len(), 1);
+        assert_eq!(project.timeline.clips[0].start_time, 0.0);
+        assert_eq!(project.audio_tracks.len(), 1);
+        assert_eq!(project.audio_tracks[0].volume, 1.0);
+        assert_eq!(project.storyboard.len(), 1);
+        assert_eq!(project.storyboard[0].image_asset_id, None);
+        assert_eq!(project.characters.len(), 1);
+        assert_eq!(project.export_settings.frame_rate, 30);
+    }
+
+    #[test]
+    fn unknown_timeline_fields_round_trip() {
+        let mut value = current_project();
+        value["timeline"]["zoom"] = json!(2.5);
+        value["timeline"]["markers"] = json!([{ "at": 1.0, "label": "高潮" }]);
+        let project = load_value(value.clone()).unwrap();
+        assert_eq!(project.timeline.extra.get("zoom"), Some(&json!(2.5)));
+
+        let saved = serde_json::to_value(&project).unwrap();
+        assert_eq!(saved["timeline"]["markers"], value["timeline"]["markers"]);
+        assert_eq!(load_value(saved).unwrap(), project);
+    }
+}
@@ -0,0 +1,366 @@
+//! 缺失媒体检测与重新链接
+//!
+//! 源文件移动后项目仍指向旧的绝对路径。这里先找出文件不存在的素材，再在用户
+//! 选择的目录中按文件名查找候选文件，并用大小、时长与内容校验值确认；用户确认后
+//! 一次性改写项目文件。
+
+use crate::backend::{FfmpegBackend, MediaBackend};
+use crate::error::{AppError, AppResult};
+use crate::fs_utils;
+use crate::media;
+use crate::media_scope;
+use crate::project;
+use crate::project_schema::{Asset, AssetKind, Project};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 搜索目录的最大递归深度
+const MAX_SEARCH_DEPTH: usize = 8;
+
+/// 单次搜索最多检查的文件数
+const MAX_SEARCH_FILES: usize = 100_000;
+
+/// 时长视为一致的误差（秒）
+const DURATION_TOLERANCE: f64 = 0.1;
+
+// 文件不存在的素材
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingAsset {
+    pub asset_id: String,
+    pub kind: AssetKind,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+// 候选文件与各项检查结果，`None` 表示项目中没有可比较的信息
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RelinkCandidate {
+    pub path: String,
+    pub size: u64,
+    pub size_matches: Option<bool>,
+    pub duration_matches: Option<bool>,
+    pub hash_matches: Option<bool>,
+}
+
+impl RelinkCandidate {
+    /// Number of checks that positively matched.
+    fn confirmations(&self) -> usize {
+        [self.size_matches, self.duration_matches, self.hash_matches]
+            .iter()
+            .filter(|m| **m == Some(true))
+            .count()
+    }
+}
+
+// 单个缺失素材的重新链接建议，候选按可信度从高到低排列
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RelinkProposal {
+    pub asset_id: String,
+    pub old_path: String,
+    pub candidates: Vec<RelinkCandidate>,
+}
+
+// 用户确认的重新链接
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Relink {
+    pub asset_id: String,
+    pub path: String,
+}
+
+/// Assets whose media file no longer exists, or that only carry a bare file name (unshipped bundle media).
+pub fn missing_assets(project: &Project) -> Vec<MissingAsset> {
+    project
+        .assets
+        .iter()
+        .filter(|asset| {
+            let path = Path::new(&asset.path);
+            !path.is_absolute() || !path.is_file()
+        })
+        .map(|asset| MissingAsset {
+            asset_id: asset.id.clone(),
+            kind: asset.kind,
+            path: asset.path.clone(),
+            name: asset.name.clone(),
+        })
+        .collect()
+}
+
+/// Lowercased file name used to match candidates across case-insensitive file systems.
+fn match_key(path: &Path) -> Option<String> {
+    path.file_name().map(|n| n.to_string_lossy().to_lowercase())
+}
+
+/// Index regular files under `dir` by [`match_key`], skipping hidden entries and symlinks.
+fn index_files(dir: &Path, depth: usize, index: &mut HashMap<String, Vec<PathBuf>>, seen: &mut usize) {
+    if depth > MAX_SEARCH_DEPTH {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        if *seen >= MAX_SEARCH_FILES {
+            warn!("重新链接搜索文件数达到上限: {}", dir.display());
+            return;
+        }
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else { continue };
+        let path = entry.path();
+        if file_type.is_dir() {
+            index_files(&path, depth + 1, index, seen);
+        } else if file_type.is_file() {
+            *seen += 1;
+            if let Some(key) = match_key(&path) {
+                index.entry(key).or_default().push(path);
+            }
+        }
+    }
+}
+
+/// Compare `path` against what the project recorded for `asset`.
+fn check_candidate(backend: Option<&dyn MediaBackend>, asset: &Asset, path: &Path) -> Option<RelinkCandidate> {
+    let size = fs::metadata(path).ok()?.len();
+    let size_matches = asset.size.map(|expected| expected == size);
+    // 大小不一致时无需再做更昂贵的检查
+    let size_ok = size_matches != Some(false);
+
+    let duration_matches = match (backend, asset.duration, asset.kind) {
+        (Some(backend), Some(expected), AssetKind::Video | AssetKind::Audio) if size_ok => {
+            media::probe_duration(backend, &path.to_string_lossy())
+                .ok()
+                .map(|actual| (actual - expected).abs() <= DURATION_TOLERANCE)
+        }
+        _ => None,
+    };
+    let hash_matches = match &asset.content_hash {
+        Some(expected) if size_ok && duration_matches != Some(false) => {
+            fs_utils::hash_file(path).ok().map(|actual| actual.eq_ignore_ascii_case(expected))
+        }
+        _ => None,
+    };
+
+    Some(RelinkCandidate {
+        path: path.to_string_lossy().to_string(),
+        size,
+        size_matches,
+        duration_matches,
+        hash_matches,
+    })
+}
+
+/// Search `dirs` for files that could replace each missing asset.
+///
+/// Candidates share the missing file's name; any check that positively fails (size,
+/// duration or content hash) rules a candidate out. Durations are only compared when
+/// a backend is given.
+pub fn find_candidates(backend: Option<&dyn MediaBackend>, project: &Project, dirs: &[PathBuf]) -> Vec<RelinkProposal> {
+    let missing = missing_assets(project);
+    if missing.is_empty() {
+        return Vec::new();
+    }
+
+    let mut index = HashMap::new();
+    let mut seen = 0;
+    for dir in dirs {
+        index_files(dir, 0, &mut index, &mut seen);
+    }
+
+    missing
+        .iter()
+        .filter_map(|m| project.asset(&m.asset_id))
+        .map(|asset| {
+            let files = match_key(Path::new(&asset.path)).and_then(|key| index.get(&key));
+            let mut candidates: Vec<RelinkCandidate> = files
+                .into_iter()
+                .flatten()
+                .filter_map(|path| check_candidate(backend, asset, path))
+                .filter(|c| ![c.size_matches, c.duration_matches, c.hash_matches].contains(&Some(false)))
+                .collect();
+            candidates.sort_by_key(|c| std::cmp::Reverse(c.confirmations()));
+            RelinkProposal { asset_id: asset.id.clone(), old_path: asset.path.clone(), candidates }
+        })
+        .collect()
+}
+
+/// Point assets at their confirmed new files. Nothing changes unless every relink is valid.
+pub fn apply_relinks(project: &mut Project, relinks: &[Relink]) -> AppResult<()> {
+    for (i, relink) in relinks.iter().enumerate() {
+        if project.asset(&relink.asset_id).is_none() {
+            return Err(AppError::invalid_argument(
+                &format!("relinks[{}].assetId", i),
+                format!("unknown asset {}", relink.asset_id),
+            ));
+        }
+        if !Path::new(&relink.path).is_file() {
+            return Err(AppError::NotFound { path: relink.path.clone() });
+        }
+    }
+    for relink in relinks {
+        if let Some(asset) = project.assets.iter_mut().find(|a| a.id == relink.asset_id) {
+            asset.path = relink.path.clone();
+            // 旧代理对应的是原文件，重新链接后不再可信
+            asset.proxy_path = None;
+        }
+    }
+    Ok(())
+}
+
+/// 列出项目中文件已不存在的素材
+#[tauri::command]
+pub fn scan_missing_media(project_id: String, app_handle: tauri::AppHandle) -> AppResult<Vec<MissingAsset>> {
+    let project = project::read_project(&project::project_file(&app_handle, &project_id)?)?;
+    Ok(missing_assets(&project))
+}
+
+/// 在用户选择的目录中为缺失素材查找候选文件
+#[tauri::command]
+pub async fn find_relink_candidates(
+    project_id: String,
+    search_dirs: Vec<String>,
+    app_handle: tauri::AppHandle,
+) -> AppResult<Vec<RelinkProposal>> {
+    let dirs = search_dirs
+        .iter()
+        .map(|dir| media_scope::check(dir))
+        .collect::<AppResult<Vec<_>>>()?;
+    let project = project::read_project(&project::project_file(&app_handle, &project_id)?)?;
+
+    // 没有 ffmpeg 时只按大小与校验值确认
+    let backend = FfmpegBackend::new("relink").ok();
+    let proposals = find_candidates(backend.as_ref().map(|b| b as &dyn MediaBackend), &project, &dirs);
+    info!(
+        "重新链接搜索完成: {} 个缺失素材, {} 个有候选",
+        proposals.len(),
+        proposals.iter().filter(|p| !p.candidates.is_empty()).count()
+    );
+    Ok(proposals)
+}
+
+/// 应用用户确认的重新链接，并原子改写项目文件
+#[tauri::command]
+pub fn apply_media_relinks(
+    project_id: String,
+    relinks: Vec<Relink>,
+    app_handle: tauri::AppHandle,
+) -> AppResult<Project> {
+    for relink in &relinks {
+        media_scope::check(&relink.path)?;
+    }
+    let file_path = project::project_file(&app_handle, &project_id)?;
+    let mut project = project::read_project(&file_path)?;
+    apply_relinks(&mut project, &relinks)?;
+    project::write_project(&file_path, &project)?;
+    info!("已重新链接 {} 个素材: {}", relinks.len(), project_id);
+    Ok(project)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::RecordingBackend;
+    use crate::project_schema;
+    use crate::test_support::TempDir;
+    use serde_json::json;
+
+    fn project(assets: serde_json::Value) -> Project {
+        project_schema::load_value(json!({ "schemaVersion": 1, "id": "p1", "name": "测试", "assets": assets })).unwrap()
+    }
+
+    #[test]
+    fn reports_only_missing_assets() {
+        let dir = TempDir::new();
+        let present = dir.write("here.png", b"x");
+        let project = project(json!([
+            { "id": "a", "kind": "image", "path": present },
+            { "id": "b", "kind": "video", "path": "/old/place/clip.mp4", "name": "clip" },
+            { "id": "c", "kind": "audio", "path": "voice.wav" }
+        ]));
+        let missing = missing_assets(&project);
+        assert_eq!(missing.len(), 2);
+        assert_eq!((missing[0].asset_id.as_str(), missing[0].kind), ("b", AssetKind::Video));
+    }
+
+    #[test]
+    fn candidates_are_matched_by_name_and_confirmed_by_size_and_hash() {
+        let dir = TempDir::new();
+        let right = dir.write("moved/Clip.MP4", b"the real clip");
+        dir.write("other/clip.mp4", b"a different clip of other size");
+        dir.write("same_size/clip.mp4", b"the fake clip");
+        dir.write(".hidden/clip.mp4", b"the real clip");
+        let project = project(json!([{
+            "id": "v", "kind": "image", "path": "/old/clip.mp4",
+            "size": 13, "contentHash": fs_utils::hash_file(Path::new(&right)).unwrap()
+        }]));
+
+        let proposals = find_candidates(None, &project, std::slice::from_ref(&dir.0));
+        assert_eq!(proposals.len(), 1);
+        let candidates = &proposals[0].candidates;
+        assert_eq!(candidates.len(), 1, "{:?}", candidates);
+        assert_eq!(candidates[0].path, right);
+        assert_eq!(candidates[0].size_matches, Some(true));
+        assert_eq!(candidates[0].hash_matches, Some(true));
+        assert_eq!(candidates[0].duration_matches, None);
+    }
+
+    #[test]
+    fn duration_is_checked_through_the_backend() {
+        let dir = TempDir::new();
+        dir.write("a/clip.mp4", b"1");
+        dir.write("b/clip.mp4", b"2");
+        let project = project(json!([{ "id": "v", "kind": "video", "path": "/old/clip.mp4", "duration": 12.0 }]));
+
+        let backend = RecordingBackend::new().with_probe_output(r#"{"format":{"duration":"12.04"}}"#);
+        let proposals = find_candidates(Some(&backend), &project, std::slice::from_ref(&dir.0));
+        assert_eq!(proposals[0].candidates.len(), 2);
+        assert!(proposals[0].candidates.iter().all(|c| c.duration_matches == Some(true)));
+
+        let backend = RecordingBackend::new().with_probe_output(r#"{"format":{"duration":"30.0"}}"#);
+        assert!(find_candidates(Some(&backend), &project, std::slice::from_ref(&dir.0))[0].candidates.is_empty());
+    }
+
+    #[test]
+    fn unconfirmed_candidates_rank_last() {
+        let dir = TempDir::new();
+        dir.write("a/clip.mp4", b"12345");
+        dir.write("b/clip.mp4", b"123");
+        let mut project = project(json!([{ "id": "v", "kind": "image", "path": "/old/clip.mp4" }]));
+        assert_eq!(find_candidates(None, &project, std::slice::from_ref(&dir.0))[0].candidates.len(), 2);
+
+        project.assets[0].size = Some(5);
+        let candidates = &find_candidates(None, &project, std::slice::from_ref(&dir.0))[0].candidates;
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].size, 5);
+    }
+
+    #[test]
+    fn relinks_apply_all_or_nothing() {
+        let dir = TempDir::new();
+        let new_path = dir.write("clip.mp4", b"x");
+        let mut project = project(json!([
+            { "id": "v", "kind": "video", "path": "/old/clip.mp4", "proxyPath": "/old/proxy.mp4" }
+        ]));
+
+        let bad = [
+            Relink { asset_id: "v".into(), path: new_path.clone() },
+            Relink { asset_id: "nope".into(), path: new_path.clone() },
+        ];
+        assert!(matches!(apply_relinks(&mut project, &bad), Err(AppError::InvalidArgument { .. })));
+        assert_eq!(project.assets[0].path, "/old/clip.mp4");
+
+        let missing = [Relink { asset_id: "v".into(), path: "/still/missing.mp4".into() }];
+        assert!(matches!(apply_relinks(&mut project, &missing), Err(AppError::NotFound { .. })));
+
+        apply_relinks(&mut project, &[Relink { asset_id: "v".into(), path: new_path.clone() }]).unwrap();
+        assert_eq!(project.assets[0].path, new_path);
+        assert_eq!(project.assets[0].proxy_path, None);
+        assert!(missing_assets(&project).is_empty());
+    }
+}
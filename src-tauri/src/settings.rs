@@ -0,0 +1,319 @@
+//! 应用设置：读取、校验、保存并应用到各后端模块
+//!
+//! `settings.json` 带有 `version`，读取时先按 [`MIGRATIONS`] 升级到 [`SETTINGS_VERSION`]。
+//! 缺失的字段取默认值，类型错误或超出范围的字段记录警告后同样回退到默认值，
+//! 因此旧版本或被手动改坏的文件都不会导致读取失败。保存与部分更新则严格校验，
+//! 成功后向所有窗口广播 [`SETTINGS_CHANGED_EVENT`]。
+
+use crate::autosave;
+use crate::error::{self, AppError, AppResult};
+use crate::fs_utils;
+use crate::toolchain;
+use crate::tray;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 当前设置文件版本；没有 `version` 的旧文件视为版本 0
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// Migration `i` upgrades a settings object from version `i` to `i + 1`.
+const MIGRATIONS: &[fn(&mut Map<String, Value>)] = &[migrate_v0_to_v1];
+
+/// 设置变更后广播的事件，负载为完整的新设置
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+const SETTINGS_FILE: &str = "settings.json";
+
+pub const THEMES: &[&str] = &["light", "dark", "system"];
+pub const LANGUAGES: &[&str] = &["zh-CN", "en-US"];
+pub const QUALITIES: &[&str] = &["low", "medium", "high", "ultra"];
+pub const FORMATS: &[&str] = &["mp4", "mov", "webm", "mkv"];
+
+/// 自动保存间隔的允许范围（秒）
+pub const AUTO_SAVE_INTERVAL_RANGE: RangeInclusive<u32> = 10..=3600;
+
+// 串行化设置文件的读改写
+static SETTINGS_LOCK: Mutex<()> = Mutex::new(());
+
+// 应用设置结构，缺失的字段取默认值
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct AppSettings {
+    pub version: u32,
+    pub theme: String,
+    pub language: String,
+    pub auto_save: bool,
+    pub auto_save_interval: u32,
+    pub default_quality: String,
+    pub default_format: String,
+    pub notification_enabled: bool,
+    pub minimize_to_tray: bool,
+    pub start_minimized: bool,
+    pub check_update_on_start: bool,
+    // 自定义 ffmpeg/ffprobe 路径，为空时自动查找
+    pub ffmpeg_path: Option<String>,
+    pub ffprobe_path: Option<String>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        AppSettings {
+            version: SETTINGS_VERSION,
+            theme: "light".to_string(),
+            language: "zh-CN".to_string(),
+            auto_save: true,
+            auto_save_interval: 300,
+            default_quality: "medium".to_string(),
+            default_format: "mp4".to_string(),
+            notification_enabled: true,
+            minimize_to_tray: true,
+            start_minimized: false,
+            check_update_on_start: true,
+            ffmpeg_path: None,
+            ffprobe_path: None,
+        }
+    }
+}
+
+impl AppSettings {
+    /// Every field that breaks a validation rule, with the reason.
+    fn problems(&self) -> Vec<(&'static str, String)> {
+        let mut problems = Vec::new();
+        let mut one_of = |field: &'static str, value: &str, allowed: &[&str]| {
+            if !allowed.contains(&value) {
+                problems.push((field, format!("must be one of {}", allowed.join(", "))));
+            }
+        };
+        one_of("theme", &self.theme, THEMES);
+        one_of("language", &self.language, LANGUAGES);
+        one_of("default_quality", &self.default_quality, QUALITIES);
+        one_of("default_format", &self.default_format, FORMATS);
+        if !AUTO_SAVE_INTERVAL_RANGE.contains(&self.auto_save_interval) {
+            problems.push((
+                "auto_save_interval",
+                format!(
+                    "must be between {} and {} seconds",
+                    AUTO_SAVE_INTERVAL_RANGE.start(),
+                    AUTO_SAVE_INTERVAL_RANGE.end()
+                ),
+            ));
+        }
+        problems
+    }
+
+    /// Fail with the first invalid field.
+    pub fn validate(&self) -> AppResult<()> {
+        match self.problems().into_iter().next() {
+            Some((field, reason)) => Err(AppError::invalid_argument(field, reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// Reset invalid fields to their defaults, logging each one.
+    fn repair(&mut self) {
+        let defaults = AppSettings::default();
+        for (field, reason) in self.problems() {
+            warn!("设置项 {} 无效（{}），已使用默认值", field, reason);
+            match field {
+                "theme" => self.theme = defaults.theme.clone(),
+                "language" => self.language = defaults.language.clone(),
+                "default_quality" => self.default_quality = defaults.default_quality.clone(),
+                "default_format" => self.default_format = defaults.default_format.clone(),
+                "auto_save_interval" => self.auto_save_interval = defaults.auto_save_interval,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// v0 files wrote unset ffmpeg paths as empty strings.
+fn migrate_v0_to_v1(settings: &mut Map<String, Value>) {
+    for key in ["ffmpeg_path", "ffprobe_path"] {
+        if settings.get(key).and_then(Value::as_str).is_some_and(|path| path.trim().is_empty()) {
+            settings.insert(key.to_string(), Value::Null);
+        }
+    }
+}
+
+/// Parse a settings file leniently: migrate, default missing or mistyped fields and repair invalid values.
+pub fn parse_settings(content: &str) -> AppSettings {
+    let mut settings = match serde_json::from_str::<Value>(content) {
+        Ok(Value::Object(settings)) => settings,
+        Ok(_) | Err(_) => {
+            warn!("设置文件无法解析，已使用默认设置");
+            return AppSettings::default();
+        }
+    };
+    let from = settings.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if from > SETTINGS_VERSION as u64 {
+        warn!("设置文件版本 {} 高于当前支持的版本 {}，未知字段将被忽略", from, SETTINGS_VERSION);
+    }
+    for migration in MIGRATIONS.iter().skip(from as usize) {
+        migration(&mut settings);
+    }
+    settings.insert("version".to_string(), Value::from(SETTINGS_VERSION));
+
+    // 逐个剔除类型错误的字段，直到剩余部分能够反序列化
+    let mut parsed = loop {
+        match serde_path_to_error::deserialize::<_, AppSettings>(Value::Object(settings.clone())) {
+            Ok(parsed) => break parsed,
+            Err(e) => {
+                let field = e.path().iter().next().map(|segment| segment.to_string());
+                match field.and_then(|field| settings.remove_entry(&field)) {
+                    Some((field, _)) => warn!("设置项 {} 无效（{}），已使用默认值", field, e.inner()),
+                    None => break AppSettings::default(),
+                }
+            }
+        }
+    };
+    parsed.repair();
+    parsed
+}
+
+/// Merge `patch` into `current`; unknown keys, wrong types and invalid values are rejected.
+pub fn apply_patch(current: &AppSettings, patch: Map<String, Value>) -> AppResult<AppSettings> {
+    let mut merged = match serde_json::to_value(current)? {
+        Value::Object(map) => map,
+        _ => return Err(AppError::internal("settings did not serialize to an object")),
+    };
+    for (key, value) in patch {
+        if key == "version" || !merged.contains_key(&key) {
+            return Err(AppError::invalid_argument(&key, "unknown setting"));
+        }
+        merged.insert(key, value);
+    }
+    let settings: AppSettings = serde_path_to_error::deserialize(Value::Object(merged))
+        .map_err(|e| AppError::invalid_argument(&e.path().to_string(), e.into_inner().to_string()))?;
+    settings.validate()?;
+    Ok(settings)
+}
+
+/// 获取应用设置
+#[tauri::command]
+pub fn get_app_settings(app_handle: AppHandle) -> AppResult<AppSettings> {
+    let settings = load_app_settings(&app_handle)?;
+    apply_app_settings(&settings);
+    Ok(settings)
+}
+
+/// Push settings that affect backend behaviour (error language, ffmpeg paths, autosave, tray) into their modules.
+pub fn apply_app_settings(settings: &AppSettings) {
+    error::set_language(&settings.language);
+    toolchain::configure(settings.ffmpeg_path.as_deref(), settings.ffprobe_path.as_deref());
+    autosave::configure(settings.auto_save, settings.auto_save_interval);
+    tray::configure(settings.minimize_to_tray);
+}
+
+fn settings_file(app_handle: &AppHandle) -> AppResult<PathBuf> {
+    let config_dir = app_handle.path().app_config_dir()
+        .map_err(|e| AppError::io("resolve_dir", None, e))?;
+    Ok(config_dir.join(SETTINGS_FILE))
+}
+
+/// Read `settings.json` from the app config dir, falling back to defaults when absent.
+pub fn load_app_settings(app_handle: &AppHandle) -> AppResult<AppSettings> {
+    let settings_file = settings_file(app_handle)?;
+
+    if settings_file.exists() {
+        let content = fs::read_to_string(&settings_file)
+            .map_err(|e| AppError::io("read_file", Some(&settings_file), e))?;
+        Ok(parse_settings(&content))
+    } else {
+        Ok(AppSettings::default())
+    }
+}
+
+/// Write validated settings, apply them and notify every window.
+fn store_app_settings(app_handle: &AppHandle, settings: &AppSettings) -> AppResult<()> {
+    let content = serde_json::to_string_pretty(settings)?;
+    fs_utils::atomic_write(&settings_file(app_handle)?, content.as_bytes())?;
+    apply_app_settings(settings);
+    if let Err(e) = app_handle.emit(SETTINGS_CHANGED_EVENT, settings) {
+        warn!("广播设置变更失败: {}", e);
+    }
+    Ok(())
+}
+
+/// 保存应用设置
+///
+/// 未提供的字段取默认值；任一字段无效时返回 `InvalidArgument`，不写入文件。
+#[tauri::command]
+pub fn save_app_settings(app_handle: AppHandle, settings: AppSettings) -> AppResult<()> {
+    let settings = AppSettings { version: SETTINGS_VERSION, ..settings };
+    settings.validate()?;
+    let _guard = SETTINGS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    store_app_settings(&app_handle, &settings)?;
+
+    info!("应用设置已保存");
+    Ok(())
+}
+
+/// 部分更新应用设置，只修改 `patch` 中给出的字段，返回更新后的完整设置
+#[tauri::command]
+pub fn patch_app_settings(app_handle: AppHandle, patch: Map<String, Value>) -> AppResult<AppSettings> {
+    let _guard = SETTINGS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let settings = apply_patch(&load_app_settings(&app_handle)?, patch)?;
+    store_app_settings(&app_handle, &settings)?;
+
+    info!("应用设置已更新");
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn patch(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn missing_fields_take_defaults() {
+        let settings = parse_settings(r#"{ "theme": "dark", "auto_save": false }"#);
+        assert_eq!(settings.theme, "dark");
+        assert!(!settings.auto_save);
+        assert_eq!(settings.default_format, "mp4");
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(parse_settings("not json"), AppSettings::default());
+    }
+
+    #[test]
+    fn invalid_fields_fall_back_individually() {
+        let settings = parse_settings(
+            r#"{ "theme": 3, "language": "en-US", "auto_save_interval": 1, "default_format": "avi", "ffmpeg_path": "" }"#,
+        );
+        assert_eq!(settings.theme, "light");
+        assert_eq!(settings.language, "en-US");
+        assert_eq!(settings.auto_save_interval, 300);
+        assert_eq!(settings.default_format, "mp4");
+        assert_eq!(settings.ffmpeg_path, None);
+
+        let current = parse_settings(r#"{ "version": 1, "ffmpeg_path": "" }"#);
+        assert_eq!(current.ffmpeg_path.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn patches_are_merged_and_validated() {
+        let current = AppSettings::default();
+        let patched = apply_patch(&current, patch(json!({ "theme": "dark", "auto_save_interval": 60 }))).unwrap();
+        assert_eq!((patched.theme.as_str(), patched.auto_save_interval), ("dark", 60));
+        assert_eq!(patched.language, current.language);
+
+        let field = |value: Value| match apply_patch(&current, patch(value)) {
+            Err(AppError::InvalidArgument { field, .. }) => field,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(field(json!({ "auto_save_interval": 5 })), "auto_save_interval");
+        assert_eq!(field(json!({ "auto_save_interval": "soon" })), "auto_save_interval");
+        assert_eq!(field(json!({ "default_format": "avi" })), "default_format");
+        assert_eq!(field(json!({ "volume": 1 })), "volume");
+        assert_eq!(field(json!({ "version": 0 })), "version");
+    }
+}
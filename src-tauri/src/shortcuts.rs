@@ -0,0 +1,323 @@
+//! 全局快捷键
+//!
+//! 快捷键按加速键格式（如 `CommandOrControl+Shift+M`）解析校验后通过 global-shortcut
+//! 插件注册到系统，并保存到 `<app_config>/shortcuts.json`，下次启动时自动恢复。按下时向
+//! 所有窗口广播 [`SHORTCUT_TRIGGERED_EVENT`]；`show` / `hide` / `toggle` 三个动作还会直接
+//! 操作主窗口，因为窗口隐藏时前端无法自己响应。
+
+use crate::error::{AppError, AppResult};
+use crate::fs_utils;
+use crate::project;
+use crate::tray;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+/// 快捷键触发时广播的事件
+pub const SHORTCUT_TRIGGERED_EVENT: &str = "shortcut-triggered";
+
+/// 由后端直接处理的窗口动作
+pub const WINDOW_ACTIONS: &[&str] = &["show", "hide", "toggle"];
+
+const SHORTCUTS_FILE: &str = "shortcuts.json";
+
+// 快捷键注册结构
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ShortcutInfo {
+    pub id: String,
+    pub key: String,
+    pub action: String,
+    #[serde(default)]
+    pub description: String,
+    /// 是否已成功注册到系统；启动时按键被其他程序占用则为 false
+    #[serde(default)]
+    pub active: bool,
+}
+
+// 快捷键触发事件的负载
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutTriggered {
+    pub id: String,
+    pub action: String,
+    pub key: String,
+}
+
+struct Binding {
+    info: ShortcutInfo,
+    shortcut: Shortcut,
+}
+
+// 已配置的快捷键；只在短时间内加锁，不跨插件调用持有
+static REGISTRY: Mutex<Vec<Binding>> = Mutex::new(Vec::new());
+
+// 串行化注册/注销命令
+static OP_LOCK: Mutex<()> = Mutex::new(());
+
+/// Parse an accelerator; keys without modifiers are only accepted for F1–F24 so typing is never hijacked.
+pub fn parse_accelerator(key: &str) -> AppResult<Shortcut> {
+    let shortcut: Shortcut = key.parse().map_err(|e| AppError::invalid_argument("key", format!("{}", e)))?;
+    let last = key.rsplit('+').next().unwrap_or_default().trim();
+    let function_key = last.len() > 1
+        && last[..1].eq_ignore_ascii_case("f")
+        && last[1..].parse::<u8>().is_ok_and(|n| (1..=24).contains(&n));
+    if shortcut.mods.is_empty() && !function_key {
+        return Err(AppError::invalid_argument("key", "global shortcuts need a modifier unless they use F1-F24"));
+    }
+    Ok(shortcut)
+}
+
+/// ID of another binding already using `shortcut`.
+fn conflicting<'a>(bindings: &'a [Binding], id: &str, shortcut: &Shortcut) -> Option<&'a str> {
+    bindings.iter().find(|b| b.info.id != id && b.shortcut == *shortcut).map(|b| b.info.id.as_str())
+}
+
+fn registry() -> std::sync::MutexGuard<'static, Vec<Binding>> {
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Read persisted shortcuts; a missing or unreadable file means none.
+pub fn read_shortcuts(path: &Path) -> Vec<ShortcutInfo> {
+    let Ok(content) = fs::read_to_string(path) else { return Vec::new() };
+    serde_json::from_str(&content).unwrap_or_else(|e| {
+        warn!("快捷键配置无法解析，已忽略: {} ({})", path.display(), e);
+        Vec::new()
+    })
+}
+
+pub fn write_shortcuts(path: &Path, shortcuts: &[ShortcutInfo]) -> AppResult<()> {
+    fs_utils::atomic_write(path, serde_json::to_string_pretty(shortcuts)?.as_bytes())
+}
+
+fn shortcuts_file(app_handle: &AppHandle) -> AppResult<PathBuf> {
+    let config_dir = app_handle.path().app_config_dir()
+        .map_err(|e| AppError::io("resolve_dir", None, e))?;
+    Ok(config_dir.join(SHORTCUTS_FILE))
+}
+
+fn persist(app_handle: &AppHandle) -> AppResult<()> {
+    let shortcuts: Vec<ShortcutInfo> = registry().iter().map(|b| b.info.clone()).collect();
+    write_shortcuts(&shortcuts_file(app_handle)?, &shortcuts)
+}
+
+/// Bind `info` with the OS, replacing any binding with the same ID when `replace` is set.
+fn bind(app_handle: &AppHandle, mut info: ShortcutInfo, replace: bool) -> AppResult<ShortcutInfo> {
+    project::validate_id("id", &info.id)?;
+    if info.action.trim().is_empty() {
+        return Err(AppError::invalid_argument("action", "must not be empty"));
+    }
+    let shortcut = parse_accelerator(&info.key)?;
+    let previous = {
+        let bindings = registry();
+        if let Some(holder) = conflicting(&bindings, &info.id, &shortcut) {
+            return Err(AppError::ShortcutConflict { key: info.key.clone(), holder: Some(holder.to_string()) });
+        }
+        let previous = bindings.iter().find(|b| b.info.id == info.id);
+        if previous.is_some() && !replace {
+            return Err(AppError::ShortcutExists { id: info.id.clone() });
+        }
+        previous.map(|b| (b.shortcut, b.info.active))
+    };
+
+    let global = app_handle.global_shortcut();
+    let rebinding_same_key = previous.is_some_and(|(old, active)| active && old == shortcut);
+    if !rebinding_same_key {
+        if global.is_registered(shortcut) {
+            return Err(AppError::ShortcutConflict { key: info.key.clone(), holder: None });
+        }
+        global.register(shortcut).map_err(|e| {
+            warn!("注册快捷键失败: {} ({})", info.key, e);
+            AppError::ShortcutConflict { key: info.key.clone(), holder: None }
+        })?;
+        if let Some((old, true)) = previous {
+            let _ = global.unregister(old);
+        }
+    }
+
+    info.active = true;
+    let mut bindings = registry();
+    bindings.retain(|b| b.info.id != info.id);
+    bindings.push(Binding { info: info.clone(), shortcut });
+    Ok(info)
+}
+
+/// Drop the bindings matching `predicate` from the OS and the registry, returning how many were removed.
+fn unbind(app_handle: &AppHandle, predicate: impl Fn(&Binding) -> bool) -> usize {
+    let removed: Vec<Binding> = {
+        let mut bindings = registry();
+        let (removed, kept) = std::mem::take(&mut *bindings).into_iter().partition(|b| predicate(b));
+        *bindings = kept;
+        removed
+    };
+    for binding in removed.iter().filter(|b| b.info.active) {
+        if let Err(e) = app_handle.global_shortcut().unregister(binding.shortcut) {
+            warn!("注销快捷键失败: {} ({})", binding.info.key, e);
+        }
+    }
+    removed.len()
+}
+
+/// Re-register the shortcuts saved in config; keys taken by other programs stay configured but inactive.
+pub fn restore(app_handle: &AppHandle) {
+    let Ok(path) = shortcuts_file(app_handle) else { return };
+    for info in read_shortcuts(&path) {
+        let key = info.key.clone();
+        if let Err(e) = bind(app_handle, info.clone(), false) {
+            warn!("恢复快捷键失败: {} ({})", key, e);
+            let mut bindings = registry();
+            if let (Ok(shortcut), false) = (parse_accelerator(&key), bindings.iter().any(|b| b.info.id == info.id)) {
+                bindings.push(Binding { info: ShortcutInfo { active: false, ..info }, shortcut });
+            }
+        }
+    }
+}
+
+/// Global-shortcut plugin handler: run window actions and broadcast the trigger.
+pub fn handle_shortcut(app_handle: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let triggered: Vec<ShortcutInfo> =
+        registry().iter().filter(|b| b.info.active && b.shortcut == *shortcut).map(|b| b.info.clone()).collect();
+    for info in triggered {
+        match info.action.as_str() {
+            "show" => tray::show_main_window(app_handle),
+            "hide" => tray::hide_main_window(app_handle),
+            "toggle" => tray::toggle_main_window(app_handle),
+            _ => {}
+        }
+        let payload = ShortcutTriggered { id: info.id, action: info.action, key: info.key };
+        if let Err(e) = app_handle.emit(SHORTCUT_TRIGGERED_EVENT, payload) {
+            warn!("广播快捷键事件失败: {}", e);
+        }
+    }
+}
+
+/// 注册快捷键
+///
+/// 按键无法解析、与已有快捷键冲突或被其他程序占用时返回错误；成功后保存到配置。
+#[tauri::command]
+pub fn register_shortcut(
+    app_handle: AppHandle,
+    id: String,
+    key: String,
+    action: String,
+    description: String,
+) -> AppResult<ShortcutInfo> {
+    let _guard = OP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let info = bind(&app_handle, ShortcutInfo { id, key, action, description, active: false }, false)?;
+    persist(&app_handle)?;
+    info!("注册快捷键: {} -> {} ({})", info.key, info.action, info.description);
+    Ok(info)
+}
+
+/// 注销快捷键
+#[tauri::command]
+pub fn unregister_shortcut(app_handle: AppHandle, id: String) -> AppResult<()> {
+    let _guard = OP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if unbind(&app_handle, |b| b.info.id == id) > 0 {
+        persist(&app_handle)?;
+    }
+    info!("注销快捷键: {}", id);
+    Ok(())
+}
+
+/// 获取已注册的快捷键列表
+#[tauri::command]
+pub fn get_registered_shortcuts() -> AppResult<Vec<ShortcutInfo>> {
+    Ok(registry().iter().map(|b| b.info.clone()).collect())
+}
+
+/// 为主窗口的显示/隐藏/切换动作绑定全局快捷键，同一动作重复绑定时替换旧按键
+#[tauri::command]
+pub fn register_global_shortcut(app_handle: AppHandle, shortcut: String, action: String) -> AppResult<()> {
+    if !WINDOW_ACTIONS.contains(&action.as_str()) {
+        return Err(AppError::invalid_argument("action", format!("must be one of {}", WINDOW_ACTIONS.join(", "))));
+    }
+    let _guard = OP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let info = ShortcutInfo {
+        id: format!("window-{}", action),
+        key: shortcut,
+        action,
+        description: String::new(),
+        active: false,
+    };
+    let info = bind(&app_handle, info, true)?;
+    persist(&app_handle)?;
+    info!("注册全局快捷键: {} -> {}", info.key, info.action);
+    Ok(())
+}
+
+/// 注销绑定到该按键的全局快捷键
+#[tauri::command]
+pub fn unregister_global_shortcut(app_handle: AppHandle, shortcut: String) -> AppResult<()> {
+    let parsed = parse_accelerator(&shortcut)?;
+    let _guard = OP_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    if unbind(&app_handle, |b| b.shortcut == parsed) > 0 {
+        persist(&app_handle)?;
+    }
+    info!("注销全局快捷键: {}", shortcut);
+    Ok(())
+}
+
+/// 查询按键是否已注册为全局快捷键
+#[tauri::command]
+pub fn is_global_shortcut_registered(app_handle: AppHandle, shortcut: String) -> AppResult<bool> {
+    let parsed = parse_accelerator(&shortcut)?;
+    Ok(app_handle.global_shortcut().is_registered(parsed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn binding(id: &str, key: &str) -> Binding {
+        let info = ShortcutInfo {
+            id: id.into(),
+            key: key.into(),
+            action: "toggle".into(),
+            description: String::new(),
+            active: true,
+        };
+        Binding { shortcut: parse_accelerator(key).unwrap(), info }
+    }
+
+    #[test]
+    fn accelerators_need_a_modifier_or_function_key() {
+        assert!(parse_accelerator("CommandOrControl+Shift+M").is_ok());
+        assert!(parse_accelerator("F5").is_ok());
+        let field = |key: &str| match parse_accelerator(key) {
+            Err(AppError::InvalidArgument { field, .. }) => field,
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        };
+        assert_eq!(field("M"), "key");
+        assert_eq!(field("Ctrl+Nope"), "key");
+        assert_eq!(field(""), "key");
+    }
+
+    #[test]
+    fn conflicts_compare_parsed_keys() {
+        let bindings = vec![binding("a", "Ctrl+Shift+K")];
+        let same = parse_accelerator("control+shift+k").unwrap();
+        assert_eq!(conflicting(&bindings, "b", &same), Some("a"));
+        assert_eq!(conflicting(&bindings, "a", &same), None);
+        assert_eq!(conflicting(&bindings, "b", &parse_accelerator("Ctrl+K").unwrap()), None);
+    }
+
+    #[test]
+    fn shortcuts_round_trip_through_config() {
+        let dir = TempDir::new();
+        let path = dir.0.join("shortcuts.json");
+        assert!(read_shortcuts(&path).is_empty());
+        let shortcuts = vec![binding("a", "Alt+1").info];
+        write_shortcuts(&path, &shortcuts).unwrap();
+        assert_eq!(read_shortcuts(&path), shortcuts);
+        fs::write(&path, "[{}]").unwrap();
+        assert!(read_shortcuts(&path).is_empty());
+    }
+}
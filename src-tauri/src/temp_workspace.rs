@@ -0,0 +1,465 @@
+//! 临时工作区
+//!
+//! 所有命令产生的临时文件（关键帧、缩略图、预览、剪辑中间文件）都写入
+//! `<temp>/mangaai/<session>/`。每个任务拥有独立的会话目录，启动时清理
+//! 过期会话，并提供磁盘占用查询与清理命令。
+
+use crate::error::{AppError, AppResult};
+use crate::fs_utils::new_id;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 会话元数据文件名
+const SESSION_META: &str = "session.json";
+
+/// 其他进程遗留的会话超过该时长（秒）视为过期
+const STALE_SESSION_SECS: u64 = 60 * 60;
+
+/// 旧版本使用的临时目录，启动时一并清理
+const LEGACY_DIRS: [&str; 8] = [
+    "mangaai_temp",
+    "mangaai_keyframes",
+    "mangaai_thumbnails",
+    "mangaai_preview",
+    "blazecut_temp",
+    "blazecut_keyframes",
+    "blazecut_thumbnails",
+    "blazecut_preview",
+];
+
+// 正在使用中的会话，清理时跳过
+static ACTIVE_SESSIONS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+// 会话元数据
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SessionMeta {
+    pub id: String,
+    pub kind: String,
+    pub pid: u32,
+    pub created_at: u64,
+}
+
+// 单个会话的磁盘占用
+#[derive(Serialize, Debug, Clone)]
+pub struct SessionUsage {
+    pub id: String,
+    pub kind: String,
+    pub created_at: u64,
+    pub files: u64,
+    pub bytes: u64,
+    pub active: bool,
+}
+
+// 临时工作区磁盘占用
+#[derive(Serialize, Debug, Clone)]
+pub struct TempUsage {
+    pub root: String,
+    pub total_bytes: u64,
+    pub sessions: Vec<SessionUsage>,
+}
+
+// 清理结果
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PurgeResult {
+    pub removed_sessions: u64,
+    pub freed_bytes: u64,
+}
+
+/// Root directory of the temp workspace.
+pub fn workspace_root() -> PathBuf {
+    std::env::temp_dir().join("mangaai")
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Generate a name that is unique across threads and processes.
+pub fn unique_name(prefix: &str, ext: &str) -> String {
+    let stem = format!("{}_{}", prefix, new_id());
+    if ext.is_empty() {
+        stem
+    } else {
+        format!("{}.{}", stem, ext)
+    }
+}
+
+/// A per-job scratch directory under the workspace root.
+///
+/// The session is marked active while this value is alive so that purges skip it.
+/// Files are kept on drop unless the session was made with [`TempSession::scratch`];
+/// call [`TempSession::cleanup`] to remove intermediates explicitly.
+pub struct TempSession {
+    id: String,
+    dir: PathBuf,
+    remove_on_drop: bool,
+}
+
+impl TempSession {
+    /// A session whose directory is removed when it is dropped, on success and error alike.
+    /// For intermediates that never outlive the job.
+    pub fn scratch(kind: &str) -> AppResult<TempSession> {
+        let mut session = TempSession::create(kind)?;
+        session.remove_on_drop = true;
+        Ok(session)
+    }
+
+    pub fn create(kind: &str) -> AppResult<TempSession> {
+        let id = unique_name(kind, "");
+        let dir = workspace_root().join(&id);
+        fs::create_dir_all(&dir).map_err(|e| AppError::io("create_dir", Some(&dir), e))?;
+
+        let meta = SessionMeta {
+            id: id.clone(),
+            kind: kind.to_string(),
+            pid: std::process::id(),
+            created_at: now_secs(),
+        };
+        let content = serde_json::to_string(&meta)?;
+        let meta_path = dir.join(SESSION_META);
+        fs::write(&meta_path, content).map_err(|e| AppError::io("write_file", Some(&meta_path), e))?;
+
+        if let Ok(mut active) = ACTIVE_SESSIONS.lock() {
+            active.push(id.clone());
+        }
+        Ok(TempSession { id, dir, remove_on_drop: false })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path for a fixed file name inside the session.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    /// Path for a uniquely named file inside the session.
+    pub fn unique_file(&self, prefix: &str, ext: &str) -> PathBuf {
+        self.dir.join(unique_name(prefix, ext))
+    }
+
+    /// Remove the session directory and everything in it.
+    pub fn cleanup(mut self) {
+        self.remove_dir();
+        self.remove_on_drop = false;
+    }
+
+    fn remove_dir(&self) {
+        if let Err(e) = fs::remove_dir_all(&self.dir) {
+            warn!("清理临时会话失败: {} ({})", self.dir.display(), e);
+        }
+    }
+}
+
+impl Drop for TempSession {
+    fn drop(&mut self) {
+        if self.remove_on_drop {
+            self.remove_dir();
+        }
+        if let Ok(mut active) = ACTIVE_SESSIONS.lock() {
+            active.retain(|id| id != &self.id);
+        }
+    }
+}
+
+fn is_active(id: &str) -> bool {
+    ACTIVE_SESSIONS
+        .lock()
+        .map(|active| active.iter().any(|a| a == id))
+        .unwrap_or(true)
+}
+
+/// Validate that `path` resolves to a file inside the temp workspace.
+pub fn validate_temp_path(path: &str) -> AppResult<PathBuf> {
+    let canonical_path = PathBuf::from(path)
+        .canonicalize()
+        .map_err(|e| AppError::InvalidPath {
+            path: path.to_string(),
+            reason: e.to_string(),
+        })?;
+    let root = workspace_root();
+    let canonical_root = root.canonicalize().unwrap_or(root);
+    if !canonical_path.starts_with(&canonical_root) || canonical_path == canonical_root {
+        return Err(AppError::PathNotAllowed {
+            path: path.to_string(),
+        });
+    }
+    Ok(canonical_path)
+}
+
+fn dir_usage(dir: &Path) -> (u64, u64) {
+    let mut files = 0;
+    let mut bytes = 0;
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let Ok(meta) = entry.metadata() else { continue };
+            if meta.is_dir() {
+                let (f, b) = dir_usage(&entry.path());
+                files += f;
+                bytes += b;
+            } else {
+                files += 1;
+                bytes += meta.len();
+            }
+        }
+    }
+    (files, bytes)
+}
+
+fn read_meta(dir: &Path) -> Option<SessionMeta> {
+    let content = fs::read_to_string(dir.join(SESSION_META)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn list_sessions(root: &Path) -> Vec<(PathBuf, SessionMeta)> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .map(|e| {
+            let dir = e.path();
+            // 没有元数据的目录按最后修改时间处理
+            let meta = read_meta(&dir).unwrap_or_else(|| SessionMeta {
+                id: e.file_name().to_string_lossy().to_string(),
+                kind: "unknown".to_string(),
+                pid: 0,
+                created_at: e
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0),
+            });
+            (dir, meta)
+        })
+        .collect()
+}
+
+fn remove_sessions<F>(root: &Path, mut should_remove: F) -> PurgeResult
+where
+    F: FnMut(&SessionMeta) -> bool,
+{
+    let mut result = PurgeResult::default();
+    for (dir, meta) in list_sessions(root) {
+        if is_active(&meta.id) || !should_remove(&meta) {
+            continue;
+        }
+        let (_, bytes) = dir_usage(&dir);
+        match fs::remove_dir_all(&dir) {
+            Ok(_) => {
+                result.removed_sessions += 1;
+                result.freed_bytes += bytes;
+            }
+            Err(e) => warn!("清理临时会话失败: {} ({})", dir.display(), e),
+        }
+    }
+    result
+}
+
+/// Remove sessions left behind by other processes and the pre-workspace temp dirs.
+/// Called once during app setup.
+pub fn collect_garbage() -> PurgeResult {
+    collect_garbage_in(&workspace_root(), &std::env::temp_dir())
+}
+
+/// [`collect_garbage`] for the sessions under `root` and the legacy dirs under `legacy_base`.
+fn collect_garbage_in(root: &Path, legacy_base: &Path) -> PurgeResult {
+    let pid = std::process::id();
+    let now = now_secs();
+    let mut result = remove_sessions(root, |meta| {
+        meta.pid != pid && now.saturating_sub(meta.created_at) > STALE_SESSION_SECS
+    });
+
+    for name in LEGACY_DIRS {
+        let dir = legacy_base.join(name);
+        if dir.is_dir() {
+            let (_, bytes) = dir_usage(&dir);
+            if fs::remove_dir_all(&dir).is_ok() {
+                result.removed_sessions += 1;
+                result.freed_bytes += bytes;
+            }
+        }
+    }
+
+    info!(
+        "临时目录清理完成: 删除 {} 个会话, 释放 {} 字节",
+        result.removed_sessions, result.freed_bytes
+    );
+    result
+}
+
+/// 查询临时工作区磁盘占用
+#[tauri::command]
+pub fn get_temp_usage() -> AppResult<TempUsage> {
+    let mut sessions: Vec<SessionUsage> = list_sessions(&workspace_root())
+        .into_iter()
+        .map(|(dir, meta)| {
+            let (files, bytes) = dir_usage(&dir);
+            SessionUsage {
+                active: is_active(&meta.id),
+                id: meta.id,
+                kind: meta.kind,
+                created_at: meta.created_at,
+                files,
+                bytes,
+            }
+        })
+        .collect();
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+
+    Ok(TempUsage {
+        root: workspace_root().to_string_lossy().to_string(),
+        total_bytes: sessions.iter().map(|s| s.bytes).sum(),
+        sessions,
+    })
+}
+
+/// 清理临时工作区，可只清理早于指定秒数的会话；正在使用的会话不会被删除
+#[tauri::command]
+pub fn purge_temp_files(older_than_secs: Option<u64>) -> AppResult<PurgeResult> {
+    let now = now_secs();
+    let result = remove_sessions(&workspace_root(), |meta| match older_than_secs {
+        Some(age) => now.saturating_sub(meta.created_at) >= age,
+        None => true,
+    });
+    info!(
+        "已清理临时文件: {} 个会话, {} 字节",
+        result.removed_sessions, result.freed_bytes
+    );
+    Ok(result)
+}
+
+// 清理临时文件参数
+#[derive(Deserialize, Debug)]
+pub struct CleanFileParams {
+    pub path: String,
+}
+
+/// 清理临时文件
+#[tauri::command]
+pub fn clean_temp_file(params: CleanFileParams) -> AppResult<()> {
+    info!("清理临时文件: {}", params.path);
+
+    // Validate path is within the temp workspace using canonical paths
+    let canonical_path = validate_temp_path(&params.path).map_err(|e| {
+        error!("拒绝清理非临时目录文件: {} ({})", params.path, e);
+        e
+    })?;
+
+    if canonical_path.is_file() {
+        if let Err(e) = fs::remove_file(&canonical_path) {
+            error!("删除文件失败: {}", e);
+            return Err(AppError::io("remove_file", Some(&canonical_path), e));
+        }
+    }
+
+    Ok(())
+}
+
+/// 删除临时工作区内的文件
+#[tauri::command]
+pub fn remove_file(path: String) -> AppResult<()> {
+    // Restrict to allowed temp directories to prevent arbitrary file deletion
+    let canonical = validate_temp_path(&path)?;
+    fs::remove_file(&canonical).map_err(|e| AppError::io("remove_file", Some(&canonical), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    /// Write a session directory under `root` as another (or this) process would have left it.
+    fn fake_session(root: &Path, kind: &str, pid: u32, age_secs: u64) -> PathBuf {
+        let id = unique_name(kind, "");
+        let dir = root.join(&id);
+        fs::create_dir_all(&dir).unwrap();
+        let meta = SessionMeta { id, kind: kind.to_string(), pid, created_at: now_secs() - age_secs };
+        fs::write(dir.join(SESSION_META), serde_json::to_string(&meta).unwrap()).unwrap();
+        fs::write(dir.join("frame.jpg"), b"jpeg").unwrap();
+        dir
+    }
+
+    #[test]
+    fn garbage_collection_removes_only_stale_foreign_sessions() {
+        let temp = TempDir::new();
+        let root = temp.0.join("mangaai");
+        let other_pid = std::process::id().wrapping_add(1);
+        let stale = fake_session(&root, "gc_stale", other_pid, STALE_SESSION_SECS + 60);
+        let recent = fake_session(&root, "gc_recent", other_pid, 60);
+        let own = fake_session(&root, "gc_own", std::process::id(), STALE_SESSION_SECS + 60);
+        let live = fake_session(&root, "gc_live", other_pid, STALE_SESSION_SECS + 60);
+        let live_id = live.file_name().unwrap().to_string_lossy().to_string();
+        ACTIVE_SESSIONS.lock().unwrap().push(live_id.clone());
+
+        let result = collect_garbage_in(&root, &temp.0);
+        ACTIVE_SESSIONS.lock().unwrap().retain(|id| id != &live_id);
+        assert_eq!(result.removed_sessions, 1);
+        assert!(!stale.exists());
+        assert!(recent.exists() && own.exists() && live.exists());
+    }
+
+    #[test]
+    fn garbage_collection_removes_legacy_dirs() {
+        let temp = TempDir::new();
+        let legacy = temp.0.join(LEGACY_DIRS[0]);
+        fs::create_dir_all(legacy.join("nested")).unwrap();
+        let unrelated = temp.write("mangaai_other/keep.jpg", b"jpeg");
+
+        let result = collect_garbage_in(&temp.0.join("mangaai"), &temp.0);
+        assert_eq!(result.removed_sessions, 1);
+        assert!(!legacy.exists());
+        assert!(Path::new(&unrelated).exists());
+    }
+
+    #[test]
+    fn temp_paths_must_be_inside_the_workspace() {
+        let session = TempSession::create("validate_test").unwrap();
+        let inside = session.path("preview.mp4");
+        fs::write(&inside, b"").unwrap();
+        assert!(validate_temp_path(&inside.to_string_lossy()).is_ok());
+
+        let outside = std::env::temp_dir().join(unique_name("outside", "mp4"));
+        fs::write(&outside, b"").unwrap();
+        let escaped = session.dir().join("..").join("..").join(outside.file_name().unwrap());
+        for path in [&outside, &escaped, &workspace_root()] {
+            assert!(
+                matches!(validate_temp_path(&path.to_string_lossy()), Err(AppError::PathNotAllowed { .. })),
+                "{}",
+                path.display()
+            );
+        }
+        assert!(matches!(
+            validate_temp_path(&session.path("missing.mp4").to_string_lossy()),
+            Err(AppError::InvalidPath { .. })
+        ));
+
+        fs::remove_file(outside).unwrap();
+        session.cleanup();
+    }
+
+    #[test]
+    fn scratch_sessions_are_removed_on_drop() {
+        let session = TempSession::scratch("scratch_test").unwrap();
+        let dir = session.dir().to_path_buf();
+        assert!(is_active(session.id()));
+        let id = session.id().to_string();
+        drop(session);
+        assert!(!dir.exists() && !is_active(&id));
+    }
+}
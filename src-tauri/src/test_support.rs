@@ -0,0 +1,30 @@
+//! 单元测试共用的辅助工具
+
+use crate::fs_utils::new_id;
+use std::fs;
+use std::path::PathBuf;
+
+/// A fresh, canonicalized directory under the system temp dir, removed on drop.
+pub(crate) struct TempDir(pub(crate) PathBuf);
+
+impl TempDir {
+    pub(crate) fn new() -> TempDir {
+        let dir = std::env::temp_dir().join(format!("manga_ai_test_{}", new_id()));
+        fs::create_dir_all(&dir).unwrap();
+        TempDir(dir.canonicalize().unwrap())
+    }
+
+    /// Write `content` to `name` (creating parent directories) and return the full path.
+    pub(crate) fn write(&self, name: &str, content: &[u8]) -> String {
+        let path = self.0.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().to_string()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
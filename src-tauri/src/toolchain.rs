@@ -0,0 +1,204 @@
+//! ffmpeg/ffprobe 可执行文件定位
+//!
+//! 按以下顺序查找并缓存结果：
+//! 1. `AppSettings` 中显式配置的路径
+//! 2. 可执行文件旁打包的 sidecar
+//! 3. 常见安装位置
+//! 4. `PATH`
+//!
+//! 所有调用 ffmpeg/ffprobe 的代码都应通过 [`ffmpeg_command`] / [`ffprobe_command`]
+//! 创建进程，而不是直接 `Command::new("ffmpeg")`。
+
+use crate::error::{AppError, AppResult};
+use log::{info, warn};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::RwLock;
+
+// 路径来源
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BinarySource {
+    Settings,
+    Sidecar,
+    KnownLocation,
+    Path,
+}
+
+// 已定位的可执行文件
+#[derive(Serialize, Debug, Clone)]
+pub struct ResolvedBinary {
+    pub path: PathBuf,
+    pub source: BinarySource,
+}
+
+// ffmpeg 工具链
+#[derive(Serialize, Debug, Clone)]
+pub struct Toolchain {
+    pub ffmpeg: ResolvedBinary,
+    pub ffprobe: ResolvedBinary,
+}
+
+#[derive(Default)]
+struct Overrides {
+    ffmpeg: Option<PathBuf>,
+    ffprobe: Option<PathBuf>,
+}
+
+static OVERRIDES: RwLock<Overrides> = RwLock::new(Overrides { ffmpeg: None, ffprobe: None });
+static RESOLVED: RwLock<Option<Toolchain>> = RwLock::new(None);
+
+fn exe_name(name: &str) -> String {
+    if cfg!(windows) {
+        format!("{}.exe", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Set explicit binary paths from settings and drop the cached resolution.
+pub fn configure(ffmpeg: Option<&str>, ffprobe: Option<&str>) {
+    let to_path = |p: Option<&str>| p.map(str::trim).filter(|p| !p.is_empty()).map(PathBuf::from);
+    if let Ok(mut overrides) = OVERRIDES.write() {
+        overrides.ffmpeg = to_path(ffmpeg);
+        overrides.ffprobe = to_path(ffprobe);
+    }
+    invalidate();
+}
+
+/// Forget the cached toolchain so the next call resolves again.
+pub fn invalidate() {
+    if let Ok(mut resolved) = RESOLVED.write() {
+        *resolved = None;
+    }
+}
+
+fn is_runnable(path: &Path) -> bool {
+    path.is_file()
+        && Command::new(path)
+            .arg("-version")
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+}
+
+/// Sidecar locations next to the running executable (Tauri `externalBin` are bundled there).
+fn sidecar_candidates(name: &str) -> Vec<PathBuf> {
+    let Some(dir) = std::env::current_exe().ok().and_then(|p| p.parent().map(Path::to_path_buf)) else {
+        return Vec::new();
+    };
+    vec![
+        dir.join(exe_name(name)),
+        dir.join("bin").join(exe_name(name)),
+        // macOS 应用包中也可能放在 Contents/Resources
+        dir.join("../Resources").join(exe_name(name)),
+    ]
+}
+
+fn known_locations(name: &str) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    if cfg!(target_os = "macos") {
+        dirs.extend(["/opt/homebrew/bin", "/usr/local/bin", "/opt/local/bin"].map(PathBuf::from));
+    } else if cfg!(windows) {
+        for var in ["ProgramFiles", "ProgramFiles(x86)"] {
+            if let Ok(base) = std::env::var(var) {
+                dirs.push(PathBuf::from(&base).join("ffmpeg").join("bin"));
+            }
+        }
+        if let Ok(local) = std::env::var("LOCALAPPDATA") {
+            dirs.push(PathBuf::from(local).join("Microsoft").join("WinGet").join("Links"));
+        }
+        if let Ok(home) = std::env::var("USERPROFILE") {
+            dirs.push(PathBuf::from(home).join("scoop").join("shims"));
+        }
+        dirs.push(PathBuf::from(r"C:\ProgramData\chocolatey\bin"));
+        dirs.push(PathBuf::from(r"C:\ffmpeg\bin"));
+    } else {
+        dirs.extend(["/usr/local/bin", "/usr/bin", "/snap/bin", "/opt/ffmpeg/bin"].map(PathBuf::from));
+        if let Ok(home) = std::env::var("HOME") {
+            dirs.push(PathBuf::from(home).join(".local").join("bin"));
+        }
+    }
+    dirs.into_iter().map(|d| d.join(exe_name(name))).collect()
+}
+
+fn path_candidates(name: &str) -> Vec<PathBuf> {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).map(|d| d.join(exe_name(name))).collect())
+        .unwrap_or_default()
+}
+
+/// Find `name`, checking the explicit path first, then sidecar, known locations and `PATH`.
+/// `sibling_of` lets ffprobe be found next to an explicitly configured ffmpeg.
+fn resolve_binary(name: &str, explicit: Option<&Path>, sibling_of: Option<&Path>) -> AppResult<ResolvedBinary> {
+    if let Some(path) = explicit {
+        if is_runnable(path) {
+            return Ok(ResolvedBinary { path: path.to_path_buf(), source: BinarySource::Settings });
+        }
+        warn!("设置中的 {} 路径不可用: {}", name, path.display());
+    }
+    if let Some(dir) = sibling_of.and_then(Path::parent) {
+        let path = dir.join(exe_name(name));
+        if is_runnable(&path) {
+            return Ok(ResolvedBinary { path, source: BinarySource::Settings });
+        }
+    }
+
+    let ordered = [
+        (BinarySource::Sidecar, sidecar_candidates(name)),
+        (BinarySource::KnownLocation, known_locations(name)),
+        (BinarySource::Path, path_candidates(name)),
+    ];
+    for (source, candidates) in ordered {
+        if let Some(path) = candidates.into_iter().find(|p| is_runnable(p)) {
+            return Ok(ResolvedBinary { path, source });
+        }
+    }
+    Err(AppError::FfmpegMissing)
+}
+
+/// Resolve (or return the cached) ffmpeg and ffprobe binaries.
+pub fn resolve() -> AppResult<Toolchain> {
+    if let Some(toolchain) = RESOLVED.read().ok().and_then(|r| r.clone()) {
+        return Ok(toolchain);
+    }
+
+    let (ffmpeg_override, ffprobe_override) = OVERRIDES
+        .read()
+        .map(|o| (o.ffmpeg.clone(), o.ffprobe.clone()))
+        .unwrap_or_default();
+
+    let ffmpeg = resolve_binary("ffmpeg", ffmpeg_override.as_deref(), None)?;
+    let sibling = (ffmpeg.source == BinarySource::Settings).then_some(ffmpeg.path.as_path());
+    let ffprobe = resolve_binary("ffprobe", ffprobe_override.as_deref(), sibling)?;
+
+    info!(
+        "FFmpeg 工具链: ffmpeg={} ({:?}), ffprobe={} ({:?})",
+        ffmpeg.path.display(), ffmpeg.source, ffprobe.path.display(), ffprobe.source
+    );
+    let toolchain = Toolchain { ffmpeg, ffprobe };
+    if let Ok(mut resolved) = RESOLVED.write() {
+        *resolved = Some(toolchain.clone());
+    }
+    Ok(toolchain)
+}
+
+/// A `Command` for the resolved ffmpeg binary.
+pub fn ffmpeg_command() -> AppResult<Command> {
+    Ok(Command::new(resolve()?.ffmpeg.path))
+}
+
+/// A `Command` for the resolved ffprobe binary.
+pub fn ffprobe_command() -> AppResult<Command> {
+    Ok(Command::new(resolve()?.ffprobe.path))
+}
+
+/// 获取当前使用的 ffmpeg/ffprobe 路径；`refresh` 为 true 时重新查找
+#[tauri::command]
+pub fn get_ffmpeg_toolchain(refresh: Option<bool>) -> AppResult<Toolchain> {
+    if refresh.unwrap_or(false) {
+        invalidate();
+    }
+    resolve()
+}
@@ -0,0 +1,249 @@
+//! 系统托盘与主窗口行为
+//!
+//! 托盘菜单提供显示/隐藏主窗口、当前任务进度、打开最近一次导出所在的文件夹和退出。
+//! `minimize_to_tray` 开启时关闭主窗口只会隐藏到托盘；`start_minimized` 决定启动时是否
+//! 显示主窗口。托盘创建失败时两项设置都退化为普通窗口行为，避免窗口无法找回。
+
+use crate::error::{self, Language};
+use crate::project;
+use log::{info, warn};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, Window, WindowEvent, Wry};
+
+const TRAY_ID: &str = "main";
+const MAIN_WINDOW: &str = "main";
+
+const MENU_TOGGLE: &str = "toggle";
+const MENU_PROGRESS: &str = "progress";
+const MENU_OPEN_OUTPUT: &str = "open_output";
+const MENU_QUIT: &str = "quit";
+
+static MINIMIZE_TO_TRAY: AtomicBool = AtomicBool::new(true);
+static TRAY_READY: AtomicBool = AtomicBool::new(false);
+
+// 需要在运行中更新的托盘菜单项
+struct TrayItems {
+    progress: MenuItem<Wry>,
+    open_output: MenuItem<Wry>,
+}
+
+static ITEMS: Mutex<Option<TrayItems>> = Mutex::new(None);
+
+// 进行中的任务
+#[derive(Debug, Clone, PartialEq)]
+struct ActiveJob {
+    id: u64,
+    kind: &'static str,
+    done: usize,
+    total: usize,
+}
+
+static JOBS: Mutex<Vec<ActiveJob>> = Mutex::new(Vec::new());
+static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(1);
+
+// 最近一次导出的输出文件
+static LAST_OUTPUT: Mutex<Option<String>> = Mutex::new(None);
+
+fn text(lang: Language, zh: &'static str, en: &'static str) -> &'static str {
+    match lang {
+        Language::ZhCn => zh,
+        Language::EnUs => en,
+    }
+}
+
+fn job_label(kind: &str, lang: Language) -> &str {
+    match kind {
+        "export" => text(lang, "导出视频", "Exporting video"),
+        "preview" => text(lang, "生成预览", "Rendering preview"),
+        other => other,
+    }
+}
+
+/// Menu text summarizing the running jobs: the oldest one's step count plus how many others run.
+fn progress_text(jobs: &[ActiveJob], lang: Language) -> String {
+    let Some(first) = jobs.first() else { return text(lang, "没有进行中的任务", "No running jobs").to_string() };
+    let mut line = format!("{} {}/{}", job_label(first.kind, lang), first.done, first.total);
+    if jobs.len() > 1 {
+        line.push_str(&format!(" (+{})", jobs.len() - 1));
+    }
+    line
+}
+
+fn refresh_progress() {
+    let line = progress_text(&JOBS.lock().unwrap_or_else(|e| e.into_inner()), error::current_language());
+    if let Some(items) = ITEMS.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        let _ = items.progress.set_text(line);
+    }
+}
+
+/// Progress of one running job as shown in the tray; the entry disappears when dropped.
+pub struct JobProgress {
+    id: u64,
+}
+
+impl JobProgress {
+    pub fn start(kind: &'static str, total: usize) -> JobProgress {
+        let id = NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed);
+        JOBS.lock().unwrap_or_else(|e| e.into_inner()).push(ActiveJob { id, kind, done: 0, total });
+        refresh_progress();
+        JobProgress { id }
+    }
+
+    pub fn set_done(&self, done: usize) {
+        if let Some(job) = JOBS.lock().unwrap_or_else(|e| e.into_inner()).iter_mut().find(|j| j.id == self.id) {
+            job.done = done;
+        }
+        refresh_progress();
+    }
+}
+
+impl Drop for JobProgress {
+    fn drop(&mut self) {
+        JOBS.lock().unwrap_or_else(|e| e.into_inner()).retain(|j| j.id != self.id);
+        refresh_progress();
+    }
+}
+
+/// Remember the latest export so the tray can open its folder.
+pub fn set_last_output(path: &str) {
+    *LAST_OUTPUT.lock().unwrap_or_else(|e| e.into_inner()) = Some(path.to_string());
+    if let Some(items) = ITEMS.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        let _ = items.open_output.set_enabled(true);
+    }
+}
+
+/// Apply the minimize-to-tray setting; only honoured once the tray exists.
+pub fn configure(minimize_to_tray: bool) {
+    MINIMIZE_TO_TRAY.store(minimize_to_tray, Ordering::Relaxed);
+}
+
+fn minimize_to_tray() -> bool {
+    MINIMIZE_TO_TRAY.load(Ordering::Relaxed) && TRAY_READY.load(Ordering::Relaxed)
+}
+
+pub(crate) fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+pub(crate) fn hide_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        let _ = window.hide();
+    }
+}
+
+pub(crate) fn toggle_main_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else { return };
+    if window.is_visible().unwrap_or(false) {
+        hide_main_window(app);
+    } else {
+        show_main_window(app);
+    }
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id.as_ref() {
+        MENU_TOGGLE => toggle_main_window(app),
+        MENU_OPEN_OUTPUT => {
+            let output = LAST_OUTPUT.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            if let Some(path) = output {
+                if let Err(e) = project::open_file_location(path) {
+                    warn!("打开输出文件夹失败: {}", e);
+                }
+            }
+        }
+        MENU_QUIT => {
+            info!("从托盘退出应用");
+            app.exit(0);
+        }
+        _ => {}
+    }
+}
+
+fn on_tray_icon_event(tray: &TrayIcon<Wry>, event: TrayIconEvent) {
+    if let TrayIconEvent::Click { button: MouseButton::Left, button_state: MouseButtonState::Up, .. } = event {
+        show_main_window(tray.app_handle());
+    }
+}
+
+/// Build the tray icon and its menu.
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let lang = error::current_language();
+    let toggle = MenuItem::with_id(app, MENU_TOGGLE, text(lang, "显示/隐藏主窗口", "Show/Hide Window"), true, None::<&str>)?;
+    let progress = MenuItem::with_id(app, MENU_PROGRESS, progress_text(&[], lang), false, None::<&str>)?;
+    let has_output = LAST_OUTPUT.lock().unwrap_or_else(|e| e.into_inner()).is_some();
+    let open_output =
+        MenuItem::with_id(app, MENU_OPEN_OUTPUT, text(lang, "打开输出文件夹", "Open Output Folder"), has_output, None::<&str>)?;
+    let quit = MenuItem::with_id(app, MENU_QUIT, text(lang, "退出", "Quit"), true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[
+        &toggle,
+        &PredefinedMenuItem::separator(app)?,
+        &progress,
+        &open_output,
+        &PredefinedMenuItem::separator(app)?,
+        &quit,
+    ])?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("PlotCraft")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(on_menu_event)
+        .on_tray_icon_event(on_tray_icon_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone()).icon_as_template(true);
+    }
+    builder.build(app)?;
+
+    *ITEMS.lock().unwrap_or_else(|e| e.into_inner()) = Some(TrayItems { progress, open_output });
+    TRAY_READY.store(true, Ordering::Relaxed);
+    refresh_progress();
+    info!("系统托盘已创建");
+    Ok(())
+}
+
+/// Show the main window at launch unless `start_minimized`, which hides it to the tray or,
+/// without one, minimizes it to the taskbar.
+pub fn show_on_startup(app: &AppHandle, start_minimized: bool) {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else { return };
+    if !start_minimized {
+        show_main_window(app);
+    } else if !minimize_to_tray() {
+        let _ = window.show();
+        let _ = window.minimize();
+    }
+}
+
+/// Hide the main window instead of closing it when minimize-to-tray is on.
+pub fn handle_window_event(window: &Window, event: &WindowEvent) {
+    if let WindowEvent::CloseRequested { api, .. } = event {
+        if window.label() == MAIN_WINDOW && minimize_to_tray() {
+            api.prevent_close();
+            let _ = window.hide();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(kind: &'static str, done: usize, total: usize) -> ActiveJob {
+        ActiveJob { id: 0, kind, done, total }
+    }
+
+    #[test]
+    fn progress_text_summarizes_running_jobs() {
+        let en = Language::EnUs;
+        assert_eq!(progress_text(&[], en), "No running jobs");
+        assert_eq!(progress_text(&[job("export", 2, 5)], en), "Exporting video 2/5");
+        assert_eq!(progress_text(&[job("waveform", 0, 1), job("export", 1, 3)], en), "waveform 0/1 (+1)");
+        assert_eq!(progress_text(&[job("export", 2, 5)], Language::ZhCn), "导出视频 2/5");
+    }
+}
@@ -0,0 +1,571 @@
+//! 音频波形峰值提取
+//!
+//! 通过 ffmpeg 将音频解码为单声道 16 位 PCM，按多个缩放级别计算 min/max 峰值对，
+//! 输出与 audiowaveform 兼容的 `.dat`（版本 2）与 JSON 格式，并按源文件缓存。
+
+use crate::error::{AppError, AppResult};
+use crate::ffmpeg::{ensure_ffmpeg, stderr_tail, STDERR_TAIL_LINES};
+use crate::fs_utils::atomic_write;
+use crate::job_log::JobLog;
+use crate::media_scope;
+use crate::toolchain;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::thread;
+use std::time::{Instant, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+/// 默认解码采样率
+const DEFAULT_SAMPLE_RATE: u32 = 22050;
+
+/// 默认缩放级别（每像素采样数），从细到粗
+const DEFAULT_ZOOM_LEVELS: [u32; 5] = [256, 512, 1024, 2048, 4096];
+
+/// audiowaveform `.dat` 文件格式版本
+const DAT_VERSION: i32 = 2;
+
+// 波形生成参数
+#[derive(Deserialize, Debug)]
+pub struct WaveformParams {
+    pub path: String,
+    pub sample_rate: Option<u32>,
+    pub zoom_levels: Option<Vec<u32>>,
+}
+
+// 单个缩放级别的波形数据，字段与 audiowaveform JSON 格式一致
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WaveformData {
+    pub version: i32,
+    pub channels: u32,
+    pub sample_rate: u32,
+    pub samples_per_pixel: u32,
+    pub bits: u32,
+    pub length: u32,
+    pub data: Vec<i16>,
+}
+
+// 缓存中单个缩放级别的描述
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WaveformLevel {
+    pub samples_per_pixel: u32,
+    pub length: u32,
+    pub dat_path: String,
+}
+
+// 波形生成结果
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WaveformInfo {
+    pub source: String,
+    pub sample_rate: u32,
+    pub duration: f64,
+    pub levels: Vec<WaveformLevel>,
+}
+
+impl WaveformData {
+    /// Duration in seconds covered by the peak data.
+    pub fn duration(&self) -> f64 {
+        self.length as f64 * self.samples_per_pixel as f64 / self.sample_rate as f64
+    }
+
+    /// Merge every `factor` consecutive pixels into one, producing a coarser zoom level.
+    pub fn downsample(&self, factor: u32) -> WaveformData {
+        let factor = factor.max(1) as usize;
+        let mut data = Vec::with_capacity(self.data.len() / factor + 2);
+        for chunk in self.data.chunks(factor * 2) {
+            let mut min = i16::MAX;
+            let mut max = i16::MIN;
+            for pair in chunk.chunks_exact(2) {
+                min = min.min(pair[0]);
+                max = max.max(pair[1]);
+            }
+            data.push(min);
+            data.push(max);
+        }
+        WaveformData {
+            samples_per_pixel: self.samples_per_pixel * factor as u32,
+            length: (data.len() / 2) as u32,
+            data,
+            ..*self
+        }
+    }
+
+    /// Return the peak pairs covering `[start, end)` seconds.
+    pub fn slice(&self, start: f64, end: f64) -> WaveformData {
+        let pixels_per_second = self.sample_rate as f64 / self.samples_per_pixel as f64;
+        let first = ((start.max(0.0) * pixels_per_second).floor() as usize).min(self.length as usize);
+        let last = ((end.max(0.0) * pixels_per_second).ceil() as usize).clamp(first, self.length as usize);
+        let data = self.data[first * 2..last * 2].to_vec();
+        WaveformData {
+            length: (last - first) as u32,
+            data,
+            ..*self
+        }
+    }
+
+    /// Serialize into the audiowaveform binary `.dat` (version 2) layout.
+    pub fn to_dat(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(24 + self.data.len() * 2);
+        buf.extend_from_slice(&DAT_VERSION.to_le_bytes());
+        // flags: bit 0 为 0 表示 16 位数据
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf.extend_from_slice(&(self.sample_rate as i32).to_le_bytes());
+        buf.extend_from_slice(&(self.samples_per_pixel as i32).to_le_bytes());
+        buf.extend_from_slice(&self.length.to_le_bytes());
+        buf.extend_from_slice(&(self.channels as i32).to_le_bytes());
+        for value in &self.data {
+            buf.extend_from_slice(&value.to_le_bytes());
+        }
+        buf
+    }
+
+    /// Parse an audiowaveform `.dat` file (version 1 or 2, 8 or 16 bit).
+    pub fn from_dat(bytes: &[u8]) -> AppResult<WaveformData> {
+        let malformed = |reason: &str| AppError::Serialization {
+            reason: format!("waveform .dat: {}", reason),
+        };
+        let read_u32 = |offset: usize| -> AppResult<u32> {
+            bytes
+                .get(offset..offset + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .ok_or_else(|| malformed("truncated header"))
+        };
+
+        let version = read_u32(0)? as i32;
+        let flags = read_u32(4)?;
+        let sample_rate = read_u32(8)?;
+        let samples_per_pixel = read_u32(12)?;
+        let length = read_u32(16)?;
+        let (channels, header_len) = match version {
+            1 => (1, 20),
+            2 => (read_u32(20)?, 24),
+            _ => return Err(malformed(&format!("unsupported version {}", version))),
+        };
+        if sample_rate == 0 || samples_per_pixel == 0 {
+            return Err(malformed("sample rate and samples per pixel must be greater than 0"));
+        }
+        let eight_bit = flags & 1 == 1;
+
+        // 每个像素每个声道一对 min/max；用 checked 运算防止伪造的头部溢出
+        let value_size = if eight_bit { 1 } else { 2 };
+        let body = &bytes[header_len..];
+        let expected = u64::from(length)
+            .checked_mul(u64::from(channels))
+            .and_then(|n| n.checked_mul(2 * value_size))
+            .ok_or_else(|| malformed("header sizes overflow"))?;
+        if body.len() as u64 != expected {
+            return Err(malformed("data length does not match header"));
+        }
+        let data: Vec<i16> = if eight_bit {
+            body.iter().map(|&b| (b as i8 as i16) << 8).collect()
+        } else {
+            body.chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]))
+                .collect()
+        };
+
+        Ok(WaveformData {
+            version,
+            channels,
+            sample_rate,
+            samples_per_pixel,
+            bits: 16,
+            length,
+            data,
+        })
+    }
+}
+
+/// Accumulates PCM samples into min/max pairs at a fixed `samples_per_pixel`.
+pub struct PeakBuilder {
+    samples_per_pixel: u32,
+    count: u32,
+    min: i16,
+    max: i16,
+    data: Vec<i16>,
+}
+
+impl PeakBuilder {
+    pub fn new(samples_per_pixel: u32) -> Self {
+        PeakBuilder {
+            samples_per_pixel: samples_per_pixel.max(1),
+            count: 0,
+            min: i16::MAX,
+            max: i16::MIN,
+            data: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, sample: i16) {
+        self.min = self.min.min(sample);
+        self.max = self.max.max(sample);
+        self.count += 1;
+        if self.count == self.samples_per_pixel {
+            self.flush();
+        }
+    }
+
+    /// Feed raw little-endian s16 PCM bytes. Returns the number of bytes consumed.
+    pub fn push_pcm(&mut self, bytes: &[u8]) -> usize {
+        for b in bytes.chunks_exact(2) {
+            self.push(i16::from_le_bytes([b[0], b[1]]));
+        }
+        bytes.len() - bytes.len() % 2
+    }
+
+    fn flush(&mut self) {
+        self.data.push(self.min);
+        self.data.push(self.max);
+        self.count = 0;
+        self.min = i16::MAX;
+        self.max = i16::MIN;
+    }
+
+    pub fn finish(mut self, sample_rate: u32) -> WaveformData {
+        if self.count > 0 {
+            self.flush();
+        }
+        WaveformData {
+            version: DAT_VERSION,
+            channels: 1,
+            sample_rate,
+            samples_per_pixel: self.samples_per_pixel,
+            bits: 16,
+            length: (self.data.len() / 2) as u32,
+            data: self.data,
+        }
+    }
+}
+
+/// Sort and de-duplicate zoom levels, requiring each to be a multiple of the finest one.
+pub fn normalize_zoom_levels(levels: &[u32]) -> AppResult<Vec<u32>> {
+    let mut levels: Vec<u32> = levels.to_vec();
+    levels.sort_unstable();
+    levels.dedup();
+
+    let base = *levels
+        .first()
+        .ok_or_else(|| AppError::invalid_argument("zoom_levels", "at least one zoom level is required"))?;
+    if base == 0 {
+        return Err(AppError::invalid_argument("zoom_levels", "zoom levels must be greater than 0"));
+    }
+    if let Some(bad) = levels.iter().find(|&&l| l % base != 0) {
+        return Err(AppError::invalid_argument(
+            "zoom_levels",
+            format!("{} is not a multiple of {}", bad, base),
+        ));
+    }
+    Ok(levels)
+}
+
+/// Feed ffmpeg's PCM output into `builder` until EOF.
+fn read_pcm(stdout: &mut impl Read, builder: &mut PeakBuilder) -> io::Result<()> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut pending = 0usize;
+    loop {
+        let n = stdout.read(&mut buf[pending..])?;
+        if n == 0 {
+            return Ok(());
+        }
+        let filled = pending + n;
+        let consumed = builder.push_pcm(&buf[..filled]);
+        // 奇数字节留到下一轮与后续数据拼接
+        buf.copy_within(consumed..filled, 0);
+        pending = filled - consumed;
+    }
+}
+
+/// Decode `path` to mono PCM with ffmpeg and build peaks at `samples_per_pixel`.
+fn decode_peaks(log: &JobLog, path: &str, sample_rate: u32, samples_per_pixel: u32) -> AppResult<WaveformData> {
+    let sample_rate_str = sample_rate.to_string();
+    let args = [
+        "-v", "error",
+        "-i", path,
+        "-vn",
+        "-ac", "1",
+        "-ar", &sample_rate_str,
+        "-f", "s16le",
+        "-acodec", "pcm_s16le",
+        "-",
+    ];
+    let mut command = toolchain::ffmpeg_command()?;
+    let program = command.get_program().to_string_lossy().to_string();
+    let started = Instant::now();
+    let mut child = command
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            log.record(&program, "waveform", &args, started.elapsed(), None, e.to_string().as_bytes());
+            AppError::io("spawn", None, e)
+        })?;
+
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| AppError::internal("ffmpeg stdout is not piped"))?;
+    // 并行读取 stderr，避免 ffmpeg 输出大量错误信息时写满管道而阻塞
+    let mut stderr_pipe = child
+        .stderr
+        .take()
+        .ok_or_else(|| AppError::internal("ffmpeg stderr is not piped"))?;
+    let stderr_reader = thread::spawn(move || {
+        let mut stderr = Vec::new();
+        let _ = stderr_pipe.read_to_end(&mut stderr);
+        stderr
+    });
+    let mut builder = PeakBuilder::new(samples_per_pixel);
+    let read = read_pcm(&mut stdout, &mut builder);
+    if read.is_err() {
+        // 读取失败时结束 ffmpeg，仍然回收进程并记录日志
+        let _ = child.kill();
+    }
+
+    let status = child.wait().map_err(|e| AppError::io("spawn", None, e))?;
+    let stderr = stderr_reader.join().unwrap_or_default();
+    log.record(&program, "waveform", &args, started.elapsed(), Some(status), &stderr);
+    read.map_err(|e| AppError::io("read_file", Some(Path::new(path)), e))?;
+    if !status.success() {
+        return Err(AppError::EncodeFailed {
+            stage: "waveform".to_string(),
+            exit_code: status.code(),
+            stderr_tail: stderr_tail(&stderr, STDERR_TAIL_LINES),
+            log_id: Some(log.job_id().to_string()),
+        });
+    }
+
+    Ok(builder.finish(sample_rate))
+}
+
+/// Cache key derived from the source path, size and modification time.
+///
+/// Hashed with BLAKE3 so keys stay the same across Rust releases.
+fn cache_key(path: &Path, sample_rate: u32) -> AppResult<String> {
+    let meta = fs::metadata(path).map_err(|e| AppError::InvalidPath {
+        path: path.to_string_lossy().to_string(),
+        reason: e.to_string(),
+    })?;
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&meta.len().to_le_bytes());
+    hasher.update(&modified.to_le_bytes());
+    hasher.update(&sample_rate.to_le_bytes());
+    // 路径放在最后，定长字段之后不会产生歧义
+    hasher.update(path.as_os_str().as_encoded_bytes());
+    Ok(hasher.finalize().to_hex()[..16].to_string())
+}
+
+fn waveform_cache_dir(app_handle: &AppHandle) -> AppResult<PathBuf> {
+    let cache_dir = app_handle.path().app_cache_dir()
+        .map_err(|e| AppError::io("resolve_dir", None, e))?;
+    Ok(cache_dir.join("waveforms"))
+}
+
+fn level_path(dir: &Path, samples_per_pixel: u32) -> PathBuf {
+    dir.join(format!("{}.dat", samples_per_pixel))
+}
+
+/// 生成音频波形峰值（多缩放级别，带缓存）
+#[tauri::command]
+pub async fn generate_waveform(params: WaveformParams, app_handle: AppHandle) -> AppResult<WaveformInfo> {
+    info!("生成波形: {:?}", params);
+    media_scope::check(&params.path)?;
+
+    ensure_ffmpeg()?;
+
+    let sample_rate = params.sample_rate.unwrap_or(DEFAULT_SAMPLE_RATE);
+    let levels = normalize_zoom_levels(
+        params.zoom_levels.as_deref().unwrap_or(&DEFAULT_ZOOM_LEVELS),
+    )?;
+
+    let source = PathBuf::from(&params.path);
+    let key = cache_key(&source, sample_rate)?;
+    let dir = waveform_cache_dir(&app_handle)?.join(&key);
+    fs::create_dir_all(&dir).map_err(|e| AppError::io("create_dir", Some(&dir), e))?;
+
+    let base_path = level_path(&dir, levels[0]);
+    let base = if base_path.exists() {
+        let bytes = fs::read(&base_path).map_err(|e| AppError::io("read_file", Some(&base_path), e))?;
+        WaveformData::from_dat(&bytes)?
+    } else {
+        let log = JobLog::create("waveform");
+        let base = decode_peaks(&log, &params.path, sample_rate, levels[0])?;
+        atomic_write(&base_path, &base.to_dat())?;
+        base
+    };
+
+    let mut result = Vec::with_capacity(levels.len());
+    for &spp in &levels {
+        let path = level_path(&dir, spp);
+        let length = if spp == base.samples_per_pixel {
+            base.length
+        } else if path.exists() {
+            let bytes = fs::read(&path).map_err(|e| AppError::io("read_file", Some(&path), e))?;
+            WaveformData::from_dat(&bytes)?.length
+        } else {
+            let level = base.downsample(spp / base.samples_per_pixel);
+            atomic_write(&path, &level.to_dat())?;
+            level.length
+        };
+        result.push(WaveformLevel {
+            samples_per_pixel: spp,
+            length,
+            dat_path: path.to_string_lossy().to_string(),
+        });
+    }
+
+    Ok(WaveformInfo {
+        source: params.path,
+        sample_rate,
+        duration: base.duration(),
+        levels: result,
+    })
+}
+
+/// 读取指定缩放级别、时间范围内的波形峰值（audiowaveform JSON 格式）
+#[tauri::command]
+pub fn get_waveform_peaks(
+    dat_path: String,
+    start: Option<f64>,
+    end: Option<f64>,
+    app_handle: AppHandle,
+) -> AppResult<WaveformData> {
+    // 只允许读取波形缓存目录中的文件
+    let cache_dir = waveform_cache_dir(&app_handle)?;
+    let canonical_cache = cache_dir.canonicalize().unwrap_or(cache_dir);
+    let canonical_path = PathBuf::from(&dat_path)
+        .canonicalize()
+        .map_err(|e| AppError::InvalidPath {
+            path: dat_path.clone(),
+            reason: e.to_string(),
+        })?;
+    if !canonical_path.starts_with(&canonical_cache) {
+        return Err(AppError::PathNotAllowed { path: dat_path });
+    }
+
+    let bytes = fs::read(&canonical_path).map_err(|e| AppError::io("read_file", Some(&canonical_path), e))?;
+    let data = WaveformData::from_dat(&bytes)?;
+    match (start, end) {
+        (None, None) => Ok(data),
+        (start, end) => {
+            let duration = data.duration();
+            Ok(data.slice(start.unwrap_or(0.0), end.unwrap_or(duration)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pcm(samples: &[i16]) -> Vec<u8> {
+        samples.iter().flat_map(|s| s.to_le_bytes()).collect()
+    }
+
+    fn waveform(data: Vec<i16>) -> WaveformData {
+        WaveformData {
+            version: DAT_VERSION,
+            channels: 1,
+            sample_rate: 8000,
+            samples_per_pixel: 4,
+            bits: 16,
+            length: (data.len() / 2) as u32,
+            data,
+        }
+    }
+
+    /// A `.dat` v2 header followed by `body`.
+    fn dat(sample_rate: u32, samples_per_pixel: u32, length: u32, channels: u32, body: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for field in [2, 0, sample_rate, samples_per_pixel, length, channels] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    #[test]
+    fn peaks_carry_odd_bytes_between_reads() {
+        let bytes = pcm(&[1, -5, 3, 7, -2, 9]);
+        let mut builder = PeakBuilder::new(4);
+        // 第一次只喂 5 个字节，最后一个奇数字节应留到下一次
+        assert_eq!(builder.push_pcm(&bytes[..5]), 4);
+        assert_eq!(builder.push_pcm(&bytes[4..]), 8);
+
+        let data = builder.finish(8000);
+        assert_eq!(data.data, vec![-5, 7, -2, 9]);
+        assert_eq!(data.length, 2);
+        assert_eq!(data.samples_per_pixel, 4);
+    }
+
+    #[test]
+    fn read_errors_stop_reading_pcm() {
+        struct Broken;
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+            }
+        }
+        let mut builder = PeakBuilder::new(2);
+        assert!(read_pcm(&mut Broken, &mut builder).is_err());
+        read_pcm(&mut pcm(&[1, -1, 5]).as_slice(), &mut builder).unwrap();
+        assert_eq!(builder.finish(8000).data, vec![-1, 1, 5, 5]);
+    }
+
+    #[test]
+    fn cache_keys_follow_the_source_file() {
+        let dir = crate::test_support::TempDir::new();
+        let audio = dir.write("voice.wav", b"riff");
+        let key = cache_key(Path::new(&audio), 8000).unwrap();
+        assert_eq!(key.len(), 16);
+        assert_eq!(cache_key(Path::new(&audio), 8000).unwrap(), key);
+        assert_ne!(cache_key(Path::new(&audio), 16000).unwrap(), key);
+        fs::write(&audio, b"longer riff").unwrap();
+        assert_ne!(cache_key(Path::new(&audio), 8000).unwrap(), key);
+    }
+
+    #[test]
+    fn downsample_merges_pixel_pairs() {
+        let data = waveform(vec![-1, 1, -4, 2, -3, 8, 0, 0, -7, 5]);
+        let coarse = data.downsample(2);
+        assert_eq!(coarse.data, vec![-4, 2, -3, 8, -7, 5]);
+        assert_eq!((coarse.length, coarse.samples_per_pixel), (3, 8));
+        assert_eq!(data.downsample(0), data);
+    }
+
+    #[test]
+    fn dat_round_trips() {
+        let data = waveform(vec![-100, 200, i16::MIN, i16::MAX, 0, 0]);
+        let parsed = WaveformData::from_dat(&data.to_dat()).unwrap();
+        assert_eq!(parsed, data);
+        assert_eq!(parsed.duration(), 3.0 * 4.0 / 8000.0);
+        assert_eq!(parsed.slice(0.0005, 1.0).data, vec![i16::MIN, i16::MAX, 0, 0]);
+    }
+
+    #[test]
+    fn malformed_dat_is_rejected() {
+        let malformed = |bytes: &[u8]| matches!(WaveformData::from_dat(bytes), Err(AppError::Serialization { .. }));
+        let body = pcm(&[-1, 1]);
+
+        assert!(malformed(&[]));
+        assert!(malformed(&dat(8000, 4, 1, 1, &body)[..22]), "truncated header");
+        assert!(malformed(&dat(8000, 4, 2, 1, &body)), "header claims more data");
+        assert!(malformed(&dat(8000, 4, 1, 1, &pcm(&[-1, 1, 0]))), "trailing data");
+        assert!(malformed(&dat(8000, 4, u32::MAX, u32::MAX, &body)), "oversized header");
+        assert!(malformed(&dat(0, 4, 1, 1, &body)), "zero sample rate");
+        assert!(malformed(&dat(8000, 0, 1, 1, &body)), "zero samples per pixel");
+        let mut v3 = dat(8000, 4, 1, 1, &body);
+        v3[0] = 3;
+        assert!(malformed(&v3));
+        assert!(WaveformData::from_dat(&dat(8000, 4, 1, 1, &body)).is_ok());
+    }
+}
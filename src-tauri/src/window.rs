@@ -0,0 +1,43 @@
+//! 主窗口控制命令
+
+use crate::error::{AppError, AppResult};
+use log::info;
+use tauri::{AppHandle, Manager};
+
+/// 显示主窗口
+#[tauri::command]
+pub fn show_main_window(app_handle: AppHandle) -> AppResult<()> {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        window.show()?;
+        window.set_focus()?;
+        info!("主窗口已显示");
+        Ok(())
+    } else {
+        Err(AppError::WindowNotFound { label: "main".to_string() })
+    }
+}
+
+/// 隐藏主窗口
+#[tauri::command]
+pub fn hide_main_window(app_handle: AppHandle) -> AppResult<()> {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        window.hide()?;
+        info!("主窗口已隐藏");
+        Ok(())
+    } else {
+        Err(AppError::WindowNotFound { label: "main".to_string() })
+    }
+}
+
+/// 切换全屏
+#[tauri::command]
+pub fn toggle_fullscreen(app_handle: AppHandle) -> AppResult<bool> {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let is_fullscreen = window.is_fullscreen()?;
+        window.set_fullscreen(!is_fullscreen)?;
+        info!("全屏状态: {}", !is_fullscreen);
+        Ok(!is_fullscreen)
+    } else {
+        Err(AppError::WindowNotFound { label: "main".to_string() })
+    }
+}
@@ -0,0 +1,85 @@
+//! manga-ai-cli 的端到端检查
+//!
+//! 命令行不经过桌面端的媒体访问范围，任意本机路径都应交给 ffmpeg 处理。
+//! 需要真实 ffmpeg 的测试在找不到 ffmpeg 时跳过。
+
+use app_lib::backend::{FfmpegBackend, MediaBackend};
+use app_lib::fs_utils::new_id;
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// A temp directory removed on drop.
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new() -> Scratch {
+        let dir = std::env::temp_dir().join(format!("mangaai_cli_{}", new_id()));
+        fs::create_dir_all(&dir).unwrap();
+        Scratch(dir)
+    }
+
+    fn path(&self, name: &str) -> String {
+        self.0.join(name).to_string_lossy().to_string()
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Run the CLI and parse the JSON line it prints.
+fn cli(args: &[&str]) -> (Option<i32>, Value) {
+    let output = Command::new(env!("CARGO_BIN_EXE_manga-ai-cli")).args(args).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value = serde_json::from_str(stdout.trim()).unwrap_or_else(|e| panic!("{}: {}", e, stdout));
+    (output.status.code(), value)
+}
+
+#[test]
+fn media_commands_are_not_limited_to_the_media_scope() {
+    let scratch = Scratch::new();
+    let input = scratch.path("not_a_video.mp4");
+    fs::write(&input, b"garbage").unwrap();
+
+    for command in [vec!["probe", input.as_str()], vec!["thumbnails", input.as_str()], vec![
+        "thumbnails", input.as_str(), "--count", "2",
+    ]] {
+        let (code, output) = cli(&command);
+        assert_ne!(code, Some(0), "{:?}", command);
+        assert_ne!(output["error"]["code"], "PathNotAllowed", "{:?}: {}", command, output);
+    }
+}
+
+#[test]
+fn probe_and_thumbnails_generated_clip() {
+    let backend = match FfmpegBackend::new("test") {
+        Ok(backend) => backend,
+        Err(e) => {
+            eprintln!("skipping: {}", e);
+            return;
+        }
+    };
+    let scratch = Scratch::new();
+    let clip = scratch.path("source.mp4");
+    backend
+        .ffmpeg("generate", &[
+            "-y", "-f", "lavfi", "-i", "testsrc=duration=3:size=320x240:rate=25",
+            "-c:v", "mpeg4", "-pix_fmt", "yuv420p", &clip,
+        ])
+        .unwrap();
+
+    let (code, output) = cli(&["probe", &clip]);
+    assert_eq!(code, Some(0), "{}", output);
+    assert_eq!(output["result"]["width"], 320);
+
+    let out_dir = scratch.path("thumbs");
+    let (code, output) = cli(&["thumbnails", &clip, "--count", "2", "--out-dir", &out_dir]);
+    assert_eq!(code, Some(0), "{}", output);
+    let thumbnails = output["result"]["thumbnails"].as_array().unwrap();
+    assert_eq!(thumbnails.len(), 2);
+    assert!(thumbnails.iter().all(|t| PathBuf::from(t.as_str().unwrap()).starts_with(&out_dir)));
+}
@@ -0,0 +1,185 @@
+//! 使用 lavfi 生成的测试素材对真实 ffmpeg 做端到端检查
+//!
+//! 找不到 ffmpeg/ffprobe 时各测试直接跳过。
+
+use app_lib::backend::{FfmpegBackend, MediaBackend};
+use app_lib::export::{self, AnimaticFrame, AnimaticParams, CutVideoParams, VideoSegment};
+use app_lib::fs_utils::new_id;
+use app_lib::media;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn backend(kind: &str) -> Option<FfmpegBackend> {
+    match FfmpegBackend::new(kind) {
+        Ok(backend) => Some(backend),
+        Err(e) => {
+            eprintln!("skipping: {}", e);
+            None
+        }
+    }
+}
+
+/// A temp directory removed on drop.
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new() -> Scratch {
+        let dir = std::env::temp_dir().join(format!("mangaai_lavfi_{}", new_id()));
+        fs::create_dir_all(&dir).unwrap();
+        Scratch(dir)
+    }
+
+    fn path(&self, name: &str) -> String {
+        self.0.join(name).to_string_lossy().to_string()
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Generate a test pattern with a sine tone, using encoders every ffmpeg build has.
+fn test_clip(backend: &dyn MediaBackend, scratch: &Scratch, seconds: u32) -> String {
+    let path = scratch.path("source.mp4");
+    let video = format!("testsrc=duration={}:size=320x240:rate=25", seconds);
+    let audio = format!("sine=frequency=440:duration={}", seconds);
+    backend
+        .ffmpeg("generate", &[
+            "-y", "-f", "lavfi", "-i", &video, "-f", "lavfi", "-i", &audio,
+            "-c:v", "mpeg4", "-pix_fmt", "yuv420p", "-c:a", "aac", "-shortest", &path,
+        ])
+        .unwrap();
+    path
+}
+
+fn test_image(backend: &dyn MediaBackend, scratch: &Scratch, name: &str, color: &str) -> String {
+    let path = scratch.path(name);
+    let source = format!("color=c={}:size=320x240", color);
+    backend
+        .ffmpeg("generate", &["-y", "-f", "lavfi", "-i", &source, "-frames:v", "1", &path])
+        .unwrap();
+    path
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 0.35, "expected ~{}s, got {}s", expected, actual);
+}
+
+fn segment(start: f64, end: f64, content: Option<&str>) -> VideoSegment {
+    VideoSegment { start, end, segment_type: None, content: content.map(str::to_string) }
+}
+
+#[test]
+fn probe_generated_clip() {
+    let Some(backend) = backend("test") else { return };
+    let scratch = Scratch::new();
+    let clip = test_clip(&backend, &scratch, 4);
+
+    let meta = media::probe_video(&backend, &clip).unwrap();
+    assert_eq!((meta.width, meta.height), (320, 240));
+    assert_eq!(meta.fps, 25.0);
+    assert_close(meta.duration, 4.0);
+}
+
+#[test]
+fn cut_concatenates_segments() {
+    let Some(backend) = backend("test") else { return };
+    let scratch = Scratch::new();
+    let clip = test_clip(&backend, &scratch, 5);
+    let output = scratch.path("cut.mp4");
+
+    export::render_cut_with(&backend, CutVideoParams {
+        input_path: clip,
+        output_path: output.clone(),
+        segments: vec![segment(0.5, 1.5, None), segment(2.0, 3.5, None)],
+        quality: Some("high".to_string()),
+        format: Some("mp4".to_string()),
+        transition: None,
+        transition_duration: None,
+        volume: Some(0.5),
+        add_subtitles: None,
+        dry_run: None,
+    })
+    .unwrap();
+
+    assert_close(media::probe_video(&backend, &output).unwrap().duration, 2.5);
+}
+
+#[test]
+fn cut_burns_subtitles_when_supported() {
+    let Some(backend) = backend("test") else { return };
+    if !backend.capabilities().unwrap().filters.contains("subtitles") {
+        eprintln!("skipping: ffmpeg built without libass");
+        return;
+    }
+    let scratch = Scratch::new();
+    let clip = test_clip(&backend, &scratch, 3);
+    let output = scratch.path("subtitled.mp4");
+
+    export::render_cut_with(&backend, CutVideoParams {
+        input_path: clip,
+        output_path: output.clone(),
+        segments: vec![segment(0.0, 2.0, Some("测试字幕"))],
+        quality: Some("low".to_string()),
+        format: None,
+        transition: None,
+        transition_duration: None,
+        volume: None,
+        add_subtitles: Some(true),
+        dry_run: None,
+    })
+    .unwrap();
+
+    let meta = media::probe_video(&backend, &output).unwrap();
+    assert_eq!((meta.width, meta.height), (1280, 720));
+    assert_close(meta.duration, 2.0);
+}
+
+#[test]
+fn thumbnails_and_key_frames() {
+    let Some(backend) = backend("test") else { return };
+    let scratch = Scratch::new();
+    let clip = test_clip(&backend, &scratch, 4);
+
+    let thumbnail = media::thumbnail_with(&backend, &clip).unwrap();
+    assert!(Path::new(&thumbnail).is_file());
+
+    let frames = media::key_frames_with(&backend, &clip, 3).unwrap();
+    assert_eq!(frames.len(), 3);
+    assert!(frames.iter().all(|f| Path::new(f).is_file()));
+
+    for file in frames.iter().chain([&thumbnail]) {
+        if let Some(dir) = Path::new(file).parent() {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+#[test]
+fn animatic_holds_each_frame() {
+    let Some(backend) = backend("test") else { return };
+    let scratch = Scratch::new();
+    let red = test_image(&backend, &scratch, "red.png", "red");
+    let blue = test_image(&backend, &scratch, "blue.png", "blue");
+    let output = scratch.path("animatic.mp4");
+
+    export::render_animatic_with(&backend, AnimaticParams {
+        frames: vec![
+            AnimaticFrame { image: red, duration: 0.5 },
+            AnimaticFrame { image: blue, duration: 1.0 },
+        ],
+        output_path: output.clone(),
+        width: Some(320),
+        height: Some(240),
+        fps: Some(10),
+        format: None,
+        audio_path: None,
+    })
+    .unwrap();
+
+    let meta = media::probe_video(&backend, &output).unwrap();
+    assert_eq!((meta.width, meta.height), (320, 240));
+    assert_close(meta.duration, 1.5);
+}